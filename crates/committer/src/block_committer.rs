@@ -1,3 +1,7 @@
+pub mod batch_commit;
 pub mod commit;
 pub mod errors;
 pub mod input;
+pub mod pipelined_commit;
+pub mod prune;
+pub mod root_registry;
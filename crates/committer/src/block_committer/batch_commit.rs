@@ -0,0 +1,71 @@
+use std::collections::{HashMap, HashSet};
+
+use crate::block_committer::commit::{commit_validated_state_diff, BlockCommitmentResult};
+use crate::block_committer::errors::InvalidStateDiff;
+use crate::block_committer::input::{Config, StateDiff};
+use crate::hash::hash_trait::HashOutput;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageValue};
+
+#[cfg(test)]
+#[path = "batch_commit_test.rs"]
+pub mod batch_commit_test;
+
+/// The commitment of a single block in a batch.
+#[derive(Debug, Eq, PartialEq)]
+pub struct BlockCommitment {
+    pub contracts_trie_root_hash: HashOutput,
+    pub classes_trie_root_hash: HashOutput,
+    /// The facts (nodes) created by the block, which were written to the storage.
+    pub facts: HashMap<StorageKey, StorageValue>,
+    /// The keys of the previous facts orphaned by the block (see
+    /// [crate::patricia_merkle_tree::filled_tree::forest::FilledForest::orphaned_node_keys]).
+    pub orphaned_node_keys: HashSet<StorageKey>,
+    /// The references to previous facts released by the block (see
+    /// [crate::patricia_merkle_tree::filled_tree::forest::FilledForest::released_node_references]).
+    pub released_node_references: HashMap<StorageKey, u64>,
+}
+
+/// Commits the given state diffs one after another, starting from the tries with the given roots.
+/// The facts of each block are written to the storage before the next block is committed, so each
+/// block is committed on top of the roots of the previous one and reads its nodes from the storage.
+/// All the state diffs are validated (see [StateDiff::validate]) before the first block is
+/// committed. Returns the commitment of each block, in order.
+pub async fn commit_blocks(
+    storage: &mut impl AsyncStorage,
+    state_diffs: &[StateDiff],
+    mut contracts_trie_root_hash: HashOutput,
+    mut classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<Vec<BlockCommitment>> {
+    validate_state_diffs(state_diffs)?;
+    let mut block_commitments = Vec::with_capacity(state_diffs.len());
+    for state_diff in state_diffs {
+        let filled_forest = commit_validated_state_diff(
+            storage,
+            state_diff,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            config,
+        )
+        .await?;
+        contracts_trie_root_hash = filled_forest.get_contract_root_hash();
+        classes_trie_root_hash = filled_forest.get_compiled_class_root_hash();
+        let facts = filled_forest.serialize();
+        filled_forest
+            .write_facts_to_storage(storage, facts.clone())
+            .await?;
+        block_commitments.push(BlockCommitment {
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            facts,
+            orphaned_node_keys: filled_forest.orphaned_node_keys,
+            released_node_references: filled_forest.released_node_references,
+        });
+    }
+    Ok(block_commitments)
+}
+
+/// Validates the given state diffs, so that no block of a batch is committed if any is invalid.
+pub(crate) fn validate_state_diffs(state_diffs: &[StateDiff]) -> Result<(), InvalidStateDiff> {
+    state_diffs.iter().try_for_each(StateDiff::validate)
+}
@@ -0,0 +1,120 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::batch_commit::{commit_blocks, BlockCommitment};
+use crate::block_committer::input::{ConfigImpl, StateDiff};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::internal_test_utils::{address, commit_to_storage, key, value};
+use crate::storage::map_storage::MapStorage;
+
+/// A block which sets the given key of contract 1 to the given value.
+fn set_value(storage_key: u128, storage_value: u128) -> StateDiff {
+    StateDiff {
+        storage_updates: HashMap::from([(
+            address(1),
+            HashMap::from([(key(storage_key), value(storage_value))]),
+        )]),
+        ..Default::default()
+    }
+}
+
+async fn commit(state_diffs: &[StateDiff]) -> (MapStorage, Vec<BlockCommitment>) {
+    let mut storage = MapStorage::default();
+    let block_commitments = commit_blocks(
+        &mut storage,
+        state_diffs,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &ConfigImpl::new(false, log::LevelFilter::Debug),
+    )
+    .await
+    .unwrap();
+    (storage, block_commitments)
+}
+
+fn roots(block_commitment: &BlockCommitment) -> (HashOutput, HashOutput) {
+    (
+        block_commitment.contracts_trie_root_hash,
+        block_commitment.classes_trie_root_hash,
+    )
+}
+
+#[rstest]
+#[case::single_block(vec![set_value(5, 50)])]
+#[case::consecutive_blocks(vec![set_value(5, 50), set_value(6, 60), set_value(5, 51)])]
+#[case::empty_block_in_the_middle(vec![set_value(5, 50), StateDiff::default(), set_value(6, 60)])]
+#[case::repeated_block(vec![set_value(5, 50), set_value(5, 50)])]
+#[case::deleted_state(vec![set_value(5, 50), set_value(5, 0), set_value(5, 50)])]
+#[tokio::test]
+async fn test_commit_blocks_matches_sequential_commits(#[case] state_diffs: Vec<StateDiff>) {
+    let mut expected_storage = MapStorage::default();
+    let mut expected_roots = Vec::new();
+    let mut roots_before = (
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    );
+    for state_diff in &state_diffs {
+        roots_before = commit_to_storage(
+            &mut expected_storage,
+            state_diff,
+            roots_before.0,
+            roots_before.1,
+        )
+        .await;
+        expected_roots.push(roots_before);
+    }
+
+    let (storage, block_commitments) = commit(&state_diffs).await;
+    assert_eq!(
+        block_commitments.iter().map(roots).collect::<Vec<_>>(),
+        expected_roots
+    );
+    assert_eq!(storage.storage, expected_storage.storage);
+    // The facts of each block are exactly the ones written by it.
+    let mut facts = HashMap::new();
+    for block_commitment in &block_commitments {
+        facts.extend(block_commitment.facts.clone());
+    }
+    assert_eq!(facts, storage.storage);
+}
+
+/// A block which does not change the state has the roots of its parent, orphans no nodes and
+/// leaves the storage as is.
+#[rstest]
+#[case::empty_block(StateDiff::default())]
+#[case::same_values(set_value(5, 50))]
+#[tokio::test]
+async fn test_commit_block_without_changes(#[case] state_diff: StateDiff) {
+    let (parent_storage, _) = commit(&[set_value(5, 50)]).await;
+    let (storage, block_commitments) = commit(&[set_value(5, 50), state_diff]).await;
+    assert_eq!(roots(&block_commitments[1]), roots(&block_commitments[0]));
+    assert!(block_commitments[1].orphaned_node_keys.is_empty());
+    assert_eq!(storage.storage, parent_storage.storage);
+}
+
+/// A block deleting all the values goes back to the empty roots, and orphans all the nodes of its
+/// parent.
+#[tokio::test]
+async fn test_commit_block_deleting_all_values() {
+    let (_, block_commitments) = commit(&[set_value(5, 50), set_value(5, 0)]).await;
+    assert_eq!(
+        roots(&block_commitments[1]),
+        (
+            HashOutput::ROOT_OF_EMPTY_TREE,
+            HashOutput::ROOT_OF_EMPTY_TREE
+        )
+    );
+    assert!(block_commitments[1].facts.is_empty());
+    assert_eq!(
+        block_commitments[1].orphaned_node_keys,
+        block_commitments[0].facts.keys().cloned().collect()
+    );
+}
+
+#[tokio::test]
+async fn test_commit_no_blocks() {
+    let (storage, block_commitments) = commit(&[]).await;
+    assert!(block_commitments.is_empty());
+    assert!(storage.storage.is_empty());
+}
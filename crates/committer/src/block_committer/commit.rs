@@ -6,10 +6,17 @@ use crate::block_committer::input::Config;
 use crate::block_committer::input::ConfigImpl;
 use crate::block_committer::input::ContractAddress;
 use crate::block_committer::input::Input;
+use crate::block_committer::input::StarknetStorageValue;
 use crate::block_committer::input::StateDiff;
-use crate::patricia_merkle_tree::filled_tree::forest::FilledForest;
-use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, Nonce};
-use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::block_committer::root_registry::{
+    get_previous_block_roots, record_block_roots, BlockNumber, BlockRoots,
+};
+use crate::forest_errors::ForestResult;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::forest::{FilledForest, HashingMode};
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, LeafModifications};
+use crate::patricia_merkle_tree::original_skeleton_tree::skeleton_forest::ForestPreviousLeaves;
 use crate::patricia_merkle_tree::original_skeleton_tree::skeleton_forest::ForestSortedIndices;
 use crate::patricia_merkle_tree::original_skeleton_tree::skeleton_forest::OriginalSkeletonForest;
 use crate::patricia_merkle_tree::types::NodeIndex;
@@ -17,12 +24,288 @@ use crate::patricia_merkle_tree::types::SortedLeafIndices;
 use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
 use crate::patricia_merkle_tree::updated_skeleton_tree::skeleton_forest::UpdatedSkeletonForest;
 use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey};
 
-type BlockCommitmentResult<T> = Result<T, BlockCommitmentError>;
+#[cfg(test)]
+#[path = "commit_test.rs"]
+pub mod commit_test;
 
+pub(crate) type BlockCommitmentResult<T> = Result<T, BlockCommitmentError>;
+
+/// Validates the state diff of the given input (see [StateDiff::validate]), and commits it on top
+/// of the input storage.
 pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<FilledForest> {
+    commit_state_diff(
+        &MapStorage::from(input.storage),
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        &input.config,
+    )
+    .await
+}
+
+/// Commits the given block on top of the recorded roots of the previous block (see
+/// [crate::block_committer::root_registry]), writes its facts to the storage and records its
+/// roots, so that the state can later be read as of the block.
+pub async fn commit_and_record_block(
+    storage: &mut impl AsyncStorage,
+    block_number: BlockNumber,
+    state_diff: &StateDiff,
+    config: &impl Config,
+) -> BlockCommitmentResult<FilledForest> {
+    let previous_roots = get_previous_block_roots(storage, block_number).await?;
+    let filled_forest = commit_state_diff(
+        storage,
+        state_diff,
+        previous_roots.contracts_trie_root_hash,
+        previous_roots.classes_trie_root_hash,
+        config,
+    )
+    .await?;
+    filled_forest.write_to_storage(storage).await?;
+    record_block_roots(
+        storage,
+        block_number,
+        &BlockRoots {
+            contracts_trie_root_hash: filled_forest.get_contract_root_hash(),
+            classes_trie_root_hash: filled_forest.get_compiled_class_root_hash(),
+        },
+    )
+    .await?;
+    Ok(filled_forest)
+}
+
+/// Same as [commit_block], but also returns the inverse state diff, which reverts the block (see
+/// [revert_block]).
+pub async fn commit_block_with_inverse(
+    input: Input<ConfigImpl>,
+) -> BlockCommitmentResult<(FilledForest, StateDiff)> {
+    commit_state_diff_with_inverse(
+        &MapStorage::from(input.storage),
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        &input.config,
+    )
+    .await
+}
+
+/// Same as [commit_block], but only computes the new roots, along with summary counts (see
+/// [dry_run_commit_state_diff]).
+pub async fn dry_run_commit_block(
+    input: Input<ConfigImpl>,
+) -> BlockCommitmentResult<DryRunCommitment> {
+    dry_run_commit_state_diff(
+        &MapStorage::from(input.storage),
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        &input.config,
+    )
+    .await
+}
+
+/// Reverts a block by committing its inverse state diff (given as the input's state diff) on top of
+/// the roots after the block. Fails if the resulting roots are not the given previous roots.
+/// Inverse state diffs may delete classes (see [StateDiff::validate_inverse]), so they are only
+/// committed through this function, rather than as the state diff of a block.
+pub async fn revert_block(
+    input: Input<ConfigImpl>,
+    previous_contracts_trie_root_hash: HashOutput,
+    previous_classes_trie_root_hash: HashOutput,
+) -> BlockCommitmentResult<FilledForest> {
+    revert_state_diff(
+        &MapStorage::from(input.storage),
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        previous_contracts_trie_root_hash,
+        previous_classes_trie_root_hash,
+        &input.config,
+    )
+    .await
+}
+
+/// Validates the given state diff (see [StateDiff::validate]), and commits it on top of the tries
+/// with the given roots, reading the previous state from the given storage. The new facts are not
+/// written to the storage; use [FilledForest::write_to_storage] to persist them.
+pub async fn commit_state_diff(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<FilledForest> {
+    state_diff.validate()?;
+    commit_validated_state_diff(
+        storage,
+        state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        config,
+    )
+    .await
+}
+
+/// Same as [commit_state_diff], for a state diff which was already validated.
+pub async fn commit_validated_state_diff(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<FilledForest> {
+    let skeleton_stage_output = create_updated_forest(
+        storage,
+        state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        config,
+    )
+    .await?;
+    fill_forest(skeleton_stage_output, HashingMode::from_config(config)).await
+}
+
+/// Same as [commit_state_diff], but also returns the inverse state diff, which holds the previous
+/// value of every modified leaf. Requires the storage to contain the previous modified leaves.
+pub async fn commit_state_diff_with_inverse(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<(FilledForest, StateDiff)> {
+    state_diff.validate()?;
+    let (skeleton_stage_output, inverse_state_diff) = create_updated_forest_with_inverse(
+        storage,
+        state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        config,
+    )
+    .await?;
+    let filled_forest =
+        fill_forest(skeleton_stage_output, HashingMode::from_config(config)).await?;
+    Ok((filled_forest, inverse_state_diff))
+}
+
+/// The new roots of a commitment computed without its facts, along with summary counts.
+#[derive(Debug, Eq, PartialEq)]
+pub struct DryRunCommitment {
+    pub contracts_trie_root_hash: HashOutput,
+    pub classes_trie_root_hash: HashOutput,
+    /// The number of contracts whose nonce, class hash or storage is modified.
+    pub n_modified_contracts: usize,
+    /// The number of storage leaf modifications, over all contracts.
+    pub n_storage_updates: usize,
+    /// The number of compiled class hash modifications.
+    pub n_class_updates: usize,
+    /// The number of new nodes in all tries, i.e., the number of facts the commitment produces.
+    pub n_new_nodes: usize,
+}
+
+/// Same as [commit_state_diff], but only computes the new roots: the new nodes are hashed and
+/// dropped, without being serialized or kept in the filled tries.
+pub async fn dry_run_commit_state_diff(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<DryRunCommitment> {
+    state_diff.validate()?;
+    let skeleton_stage_output = create_updated_forest(
+        storage,
+        state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        config,
+    )
+    .await?;
+    let n_modified_contracts = skeleton_stage_output.actual_storage_updates.len();
+    let n_storage_updates = skeleton_stage_output
+        .actual_storage_updates
+        .values()
+        .map(|updates| updates.len())
+        .sum();
+    let n_class_updates = skeleton_stage_output.actual_classes_updates.len();
+    let n_new_nodes = skeleton_stage_output.updated_forest.n_modified_nodes();
+    let filled_forest = fill_forest(skeleton_stage_output, HashingMode::RootsOnly).await?;
+    Ok(DryRunCommitment {
+        contracts_trie_root_hash: filled_forest.get_contract_root_hash(),
+        classes_trie_root_hash: filled_forest.get_compiled_class_root_hash(),
+        n_modified_contracts,
+        n_storage_updates,
+        n_class_updates,
+        n_new_nodes,
+    })
+}
+
+/// Reverts a block by committing its inverse state diff on top of the roots after the block, and
+/// checks that the resulting roots are the previous roots. The inverse state diff is validated
+/// with [StateDiff::validate_inverse].
+pub async fn revert_state_diff(
+    storage: &impl AsyncStorage,
+    inverse_state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    previous_contracts_trie_root_hash: HashOutput,
+    previous_classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<FilledForest> {
+    inverse_state_diff.validate_inverse()?;
+    let filled_forest = commit_validated_state_diff(
+        storage,
+        inverse_state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        config,
+    )
+    .await?;
+    let actual_roots = (
+        filled_forest.get_contract_root_hash(),
+        filled_forest.get_compiled_class_root_hash(),
+    );
+    let expected_roots = (
+        previous_contracts_trie_root_hash,
+        previous_classes_trie_root_hash,
+    );
+    if actual_roots != expected_roots {
+        return Err(BlockCommitmentError::RevertedRootsMismatch {
+            expected: expected_roots,
+            actual: actual_roots,
+        });
+    }
+    Ok(filled_forest)
+}
+
+/// The output of the first (I/O bound) stage of a commitment, which reads the previous state from
+/// the storage. The second (CPU bound) stage computes the new tries from it, without any storage
+/// access.
+pub(crate) struct SkeletonStageOutput {
+    updated_forest: UpdatedSkeletonForest,
+    actual_storage_updates: HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+    actual_classes_updates: LeafModifications<CompiledClassHash>,
+    original_contracts_trie_leaves: HashMap<NodeIndex, ContractState>,
+    address_to_class_hash: HashMap<ContractAddress, ClassHash>,
+    address_to_nonce: HashMap<ContractAddress, Nonce>,
+    previous_node_keys: Vec<StorageKey>,
+    previous_contracts_trie_root_hash: HashOutput,
+    flat_state_index: bool,
+}
+
+/// The first stage of a commitment: builds the original skeleton forest from the storage, and the
+/// updated skeleton forest from it.
+pub(crate) async fn create_updated_forest(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<SkeletonStageOutput> {
     let (mut storage_tries_indices, mut contracts_trie_indices, mut classes_trie_indices) =
-        get_all_modified_indices(&input.state_diff);
+        get_all_modified_indices(state_diff);
     let forest_sorted_indices = ForestSortedIndices {
         storage_tries_sorted_indices: storage_tries_indices
             .iter_mut()
@@ -31,44 +314,210 @@ pub async fn commit_block(input: Input<ConfigImpl>) -> BlockCommitmentResult<Fil
         contracts_trie_sorted_indices: SortedLeafIndices::new(&mut contracts_trie_indices),
         classes_trie_sorted_indices: SortedLeafIndices::new(&mut classes_trie_indices),
     };
-    let actual_storage_updates = input.state_diff.actual_storage_updates();
-    let actual_classes_updates = input.state_diff.actual_classes_updates();
-    let (mut original_forest, original_contracts_trie_leaves) = OriginalSkeletonForest::create(
-        MapStorage::from(input.storage),
-        input.contracts_trie_root_hash,
-        input.classes_trie_root_hash,
+    let actual_storage_updates = state_diff.actual_storage_updates();
+    let actual_classes_updates = state_diff.actual_classes_updates();
+    let (original_forest, original_contracts_trie_leaves) = OriginalSkeletonForest::create(
+        storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
         &actual_storage_updates,
         &actual_classes_updates,
         &forest_sorted_indices,
-        &input.config,
+        config,
+    )
+    .await?;
+    Ok(update_forest(
+        original_forest,
+        contracts_trie_root_hash,
+        original_contracts_trie_leaves,
+        actual_storage_updates,
+        actual_classes_updates,
+        state_diff,
+        config,
+    )?)
+}
+
+/// Same as [create_updated_forest], but also reads the previous modified leaves, and returns the
+/// inverse state diff computed from them.
+pub(crate) async fn create_updated_forest_with_inverse(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<(SkeletonStageOutput, StateDiff)> {
+    let (mut storage_tries_indices, mut contracts_trie_indices, mut classes_trie_indices) =
+        get_all_modified_indices(state_diff);
+    let forest_sorted_indices = ForestSortedIndices {
+        storage_tries_sorted_indices: storage_tries_indices
+            .iter_mut()
+            .map(|(address, indices)| (*address, SortedLeafIndices::new(indices)))
+            .collect(),
+        contracts_trie_sorted_indices: SortedLeafIndices::new(&mut contracts_trie_indices),
+        classes_trie_sorted_indices: SortedLeafIndices::new(&mut classes_trie_indices),
+    };
+    let actual_storage_updates = state_diff.actual_storage_updates();
+    let actual_classes_updates = state_diff.actual_classes_updates();
+    let (original_forest, previous_leaves) =
+        OriginalSkeletonForest::create_and_get_previous_leaves(
+            storage,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            &actual_storage_updates,
+            &actual_classes_updates,
+            &forest_sorted_indices,
+            config,
+        )
+        .await?;
+    let inverse_state_diff = compute_inverse_state_diff(state_diff, &previous_leaves);
+    let skeleton_stage_output = update_forest(
+        original_forest,
+        contracts_trie_root_hash,
+        previous_leaves.contracts_trie_leaves,
+        actual_storage_updates,
+        actual_classes_updates,
+        state_diff,
+        config,
     )?;
+    Ok((skeleton_stage_output, inverse_state_diff))
+}
 
-    if input.config.warn_on_trivial_modifications() {
+/// Builds the updated skeleton forest from the given original skeleton forest.
+fn update_forest(
+    mut original_forest: OriginalSkeletonForest<'_>,
+    contracts_trie_root_hash: HashOutput,
+    original_contracts_trie_leaves: HashMap<NodeIndex, ContractState>,
+    actual_storage_updates: HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+    actual_classes_updates: LeafModifications<CompiledClassHash>,
+    state_diff: &StateDiff,
+    config: &impl Config,
+) -> ForestResult<SkeletonStageOutput> {
+    if config.warn_on_trivial_modifications() {
         check_trivial_nonce_and_class_hash_updates(
             &original_contracts_trie_leaves,
-            &input.state_diff.address_to_class_hash,
-            &input.state_diff.address_to_nonce,
+            &state_diff.address_to_class_hash,
+            &state_diff.address_to_nonce,
         );
     }
 
     let updated_forest = UpdatedSkeletonForest::create(
         &mut original_forest,
-        &input.state_diff.skeleton_classes_updates(),
-        &input.state_diff.skeleton_storage_updates(),
+        &state_diff.skeleton_classes_updates(),
+        &state_diff.skeleton_storage_updates(),
         &original_contracts_trie_leaves,
-        &input.state_diff.address_to_class_hash,
-        &input.state_diff.address_to_nonce,
+        &state_diff.address_to_class_hash,
+        &state_diff.address_to_nonce,
     )?;
 
-    Ok(FilledForest::create::<TreeHashFunctionImpl>(
+    Ok(SkeletonStageOutput {
+        updated_forest,
+        actual_storage_updates,
+        actual_classes_updates,
+        original_contracts_trie_leaves,
+        address_to_class_hash: state_diff.address_to_class_hash.clone(),
+        address_to_nonce: state_diff.address_to_nonce.clone(),
+        previous_node_keys: original_forest.previous_node_keys,
+        previous_contracts_trie_root_hash: contracts_trie_root_hash,
+        flat_state_index: config.flat_state_index(),
+    })
+}
+
+/// The second stage of a commitment: hashes the updated skeleton forest into a filled forest, and
+/// finds the previous nodes it orphans (unless only the roots are computed).
+pub(crate) async fn fill_forest(
+    skeleton_stage_output: SkeletonStageOutput,
+    hashing_mode: HashingMode,
+) -> BlockCommitmentResult<FilledForest> {
+    let SkeletonStageOutput {
+        updated_forest,
+        actual_storage_updates,
+        actual_classes_updates,
+        original_contracts_trie_leaves,
+        address_to_class_hash,
+        address_to_nonce,
+        previous_node_keys,
+        previous_contracts_trie_root_hash,
+        flat_state_index,
+    } = skeleton_stage_output;
+    let flat_storage_updates = (flat_state_index && hashing_mode != HashingMode::RootsOnly)
+        .then(|| actual_storage_updates.clone());
+    let mut filled_forest = FilledForest::create::<TreeHashFunctionImpl>(
         updated_forest,
         actual_storage_updates,
         actual_classes_updates,
         &original_contracts_trie_leaves,
-        &input.state_diff.address_to_class_hash,
-        &input.state_diff.address_to_nonce,
+        &address_to_class_hash,
+        &address_to_nonce,
+        hashing_mode,
     )
-    .await?)
+    .await?;
+    filled_forest.previous_contracts_trie_root_hash = previous_contracts_trie_root_hash;
+    if hashing_mode != HashingMode::RootsOnly {
+        filled_forest.set_previous_node_keys(previous_node_keys);
+    }
+    if let Some(flat_storage_updates) = flat_storage_updates {
+        filled_forest.set_flat_state_entries(&flat_storage_updates);
+    }
+    Ok(filled_forest)
+}
+
+/// Returns the state diff which sets every leaf modified by the given state diff back to its
+/// previous value.
+fn compute_inverse_state_diff(
+    state_diff: &StateDiff,
+    previous_leaves: &ForestPreviousLeaves,
+) -> StateDiff {
+    let previous_contract_state = |address: &ContractAddress| {
+        previous_leaves
+            .contracts_trie_leaves
+            .get(&NodeIndex::from_contract_address(address))
+            .cloned()
+            .unwrap_or_default()
+    };
+    StateDiff {
+        address_to_class_hash: state_diff
+            .address_to_class_hash
+            .keys()
+            .map(|address| (*address, previous_contract_state(address).class_hash))
+            .collect(),
+        address_to_nonce: state_diff
+            .address_to_nonce
+            .keys()
+            .map(|address| (*address, previous_contract_state(address).nonce))
+            .collect(),
+        class_hash_to_compiled_class_hash: state_diff
+            .class_hash_to_compiled_class_hash
+            .keys()
+            .map(|class_hash| {
+                let previous_compiled_class_hash = previous_leaves
+                    .classes_trie_leaves
+                    .get(&NodeIndex::from_class_hash(class_hash))
+                    .copied()
+                    .unwrap_or_default();
+                (*class_hash, previous_compiled_class_hash)
+            })
+            .collect(),
+        storage_updates: state_diff
+            .storage_updates
+            .iter()
+            .map(|(address, updates)| {
+                let previous_storage_leaves = previous_leaves.storage_tries_leaves.get(address);
+                let previous_updates = updates
+                    .keys()
+                    .map(|key| {
+                        let previous_value = previous_storage_leaves
+                            .and_then(|leaves| {
+                                leaves.get(&NodeIndex::from_starknet_storage_key(key))
+                            })
+                            .copied()
+                            .unwrap_or_default();
+                        (*key, previous_value)
+                    })
+                    .collect();
+                (*address, previous_updates)
+            })
+            .collect(),
+    }
 }
 
 /// Compares the previous state's nonce and class hash with the given in the state diff.
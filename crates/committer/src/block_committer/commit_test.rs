@@ -0,0 +1,440 @@
+use crate::block_committer::commit::{
+    commit_block, commit_block_with_inverse, commit_state_diff, commit_state_diff_with_inverse,
+    dry_run_commit_state_diff, revert_block, revert_state_diff, DryRunCommitment,
+};
+use crate::block_committer::errors::{BlockCommitmentError, InvalidStateDiff, StateDiffProblem};
+use crate::block_committer::input::{
+    ConfigImpl, ContractAddress, Input, StarknetStorageValue, StateDiff,
+};
+use crate::felt::Felt;
+use crate::forest_errors::ForestError;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::CompiledClassHash;
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, class_hash, create_committed_state, inverse_update_state_diff, key, reachable_facts,
+    update_state_diff, value,
+};
+use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
+use crate::patricia_merkle_tree::traversal::node_db_key;
+use crate::patricia_merkle_tree::types::{NodeIndex, TrieId};
+use crate::state_reader::reader::get_contract_state;
+use crate::storage::errors::{DeserializationError, NodeKind};
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageValue};
+use rstest::rstest;
+use std::collections::{HashMap, HashSet};
+
+fn config() -> ConfigImpl {
+    ConfigImpl::new(false, log::LevelFilter::Debug)
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_layered_hashing_commit() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let expected_forest = commit_state_diff(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    let actual_forest = commit_state_diff(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &ConfigImpl::new(false, log::LevelFilter::Debug).with_layered_hashing(true),
+    )
+    .await
+    .unwrap();
+    assert_eq!(actual_forest.serialize(), expected_forest.serialize());
+    assert_eq!(
+        actual_forest.get_contract_root_hash(),
+        expected_forest.get_contract_root_hash()
+    );
+    assert_eq!(
+        actual_forest.get_compiled_class_root_hash(),
+        expected_forest.get_compiled_class_root_hash()
+    );
+}
+
+#[tokio::test(flavor = "multi_thread")]
+async fn test_dry_run_commit() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let state_diff = update_state_diff();
+    let filled_forest = commit_state_diff(
+        &storage,
+        &state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    let dry_run_commitment = dry_run_commit_state_diff(
+        &storage,
+        &state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+
+    let n_new_nodes = filled_forest
+        .storage_tries
+        .values()
+        .map(|tree| tree.tree_map.len())
+        .sum::<usize>()
+        + filled_forest.contracts_trie.tree_map.len()
+        + filled_forest.classes_trie.tree_map.len();
+    assert_eq!(
+        dry_run_commitment,
+        DryRunCommitment {
+            contracts_trie_root_hash: filled_forest.get_contract_root_hash(),
+            classes_trie_root_hash: filled_forest.get_compiled_class_root_hash(),
+            n_modified_contracts: state_diff.accessed_addresses().len(),
+            n_storage_updates: state_diff.storage_updates.values().map(|u| u.len()).sum(),
+            n_class_updates: state_diff.class_hash_to_compiled_class_hash.len(),
+            n_new_nodes,
+        }
+    );
+}
+
+#[tokio::test]
+async fn test_orphaned_node_keys() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let filled_forest = commit_state_diff(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    let mut new_storage = storage.clone();
+    filled_forest
+        .write_to_storage(&mut new_storage)
+        .await
+        .unwrap();
+    let new_facts = reachable_facts(
+        &new_storage,
+        filled_forest.get_contract_root_hash(),
+        filled_forest.get_compiled_class_root_hash(),
+    )
+    .await;
+
+    // The orphaned nodes are the previous nodes that are no longer reachable.
+    let expected_orphaned_node_keys: HashSet<StorageKey> = storage
+        .storage
+        .keys()
+        .filter(|key| !new_facts.contains_key(key))
+        .cloned()
+        .collect();
+    assert!(!expected_orphaned_node_keys.is_empty());
+    assert_eq!(
+        filled_forest.orphaned_node_keys,
+        expected_orphaned_node_keys
+    );
+}
+
+#[tokio::test]
+async fn test_commit_and_revert() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let (filled_forest, inverse_state_diff) = commit_state_diff_with_inverse(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(inverse_state_diff, inverse_update_state_diff());
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+
+    let reverted_forest = revert_state_diff(
+        &storage,
+        &inverse_state_diff,
+        filled_forest.get_contract_root_hash(),
+        filled_forest.get_compiled_class_root_hash(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        reverted_forest.get_contract_root_hash(),
+        contracts_trie_root_hash
+    );
+    assert_eq!(
+        reverted_forest.get_compiled_class_root_hash(),
+        classes_trie_root_hash
+    );
+}
+
+#[tokio::test]
+async fn test_commit_and_revert_block() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let (filled_forest, inverse_state_diff) = commit_block_with_inverse(Input {
+        storage: storage.storage.clone(),
+        state_diff: update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        config: config(),
+    })
+    .await
+    .unwrap();
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+
+    let reverted_forest = revert_block(
+        Input {
+            storage: storage.storage,
+            state_diff: inverse_state_diff,
+            contracts_trie_root_hash: filled_forest.get_contract_root_hash(),
+            classes_trie_root_hash: filled_forest.get_compiled_class_root_hash(),
+            config: config(),
+        },
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        reverted_forest.get_contract_root_hash(),
+        contracts_trie_root_hash
+    );
+}
+
+#[tokio::test]
+async fn test_revert_with_wrong_inverse() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let (filled_forest, _) = commit_state_diff_with_inverse(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+
+    let result = revert_state_diff(
+        &storage,
+        &StateDiff::default(),
+        filled_forest.get_contract_root_hash(),
+        filled_forest.get_compiled_class_root_hash(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(BlockCommitmentError::RevertedRootsMismatch { expected, actual })
+            if expected == (contracts_trie_root_hash, classes_trie_root_hash)
+                && actual
+                    == (
+                        filled_forest.get_contract_root_hash(),
+                        filled_forest.get_compiled_class_root_hash()
+                    )
+    ));
+}
+
+#[rstest]
+#[case::contracts_trie(TrieId::ContractsTrie)]
+#[case::classes_trie(TrieId::ClassesTrie)]
+#[case::storage_trie(TrieId::StorageTrie(address(1)))]
+#[tokio::test]
+async fn test_verify_on_read(#[case] trie: TrieId, #[values(true, false)] verify_on_read: bool) {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let root_hash = match trie {
+        TrieId::ContractsTrie => contracts_trie_root_hash,
+        TrieId::ClassesTrie => classes_trie_root_hash,
+        TrieId::StorageTrie(address) => {
+            get_contract_state(&storage, contracts_trie_root_hash, &address)
+                .await
+                .unwrap()
+                .storage_root_hash
+        }
+    };
+    // Corrupt the root, so that it still decodes. All inner nodes share a key prefix.
+    let root_key = node_db_key::<StarknetStorageValue>(root_hash, false);
+    let StorageValue(mut root) = storage.get(&root_key).await.unwrap().unwrap();
+    root[31] ^= 1;
+    storage.set(root_key, StorageValue(root)).await.unwrap();
+
+    let result = commit_state_diff(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &ConfigImpl::new(false, log::LevelFilter::Debug).with_verify_on_read(verify_on_read),
+    )
+    .await;
+    if verify_on_read {
+        assert!(matches!(
+            result,
+            Err(BlockCommitmentError::ForestError(ForestError::OriginalSkeleton(
+                OriginalSkeletonTreeError::NodeHashMismatch {
+                    trie: error_trie,
+                    index: NodeIndex::ROOT,
+                    expected,
+                }
+            ))) if error_trie == trie && expected == root_hash
+        ));
+    } else {
+        // The corruption goes unnoticed, or is only noticed when reading a missing node.
+        assert!(!matches!(
+            result,
+            Err(BlockCommitmentError::ForestError(
+                ForestError::OriginalSkeleton(OriginalSkeletonTreeError::NodeHashMismatch { .. })
+            ))
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_commit_on_truncated_node() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let root_key = node_db_key::<StarknetStorageValue>(contracts_trie_root_hash, false);
+    let StorageValue(mut root) = storage.get(&root_key).await.unwrap().unwrap();
+    root.truncate(1);
+    storage
+        .set(root_key.clone(), StorageValue(root))
+        .await
+        .unwrap();
+
+    let result = commit_state_diff(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(BlockCommitmentError::ForestError(ForestError::OriginalSkeleton(
+            OriginalSkeletonTreeError::Deserialization(DeserializationError::UnexpectedNodeSize {
+                key,
+                node_kind: NodeKind::Inner,
+                actual: 1,
+                ..
+            })
+        ))) if key == root_key
+    ));
+}
+
+#[tokio::test]
+async fn test_commit_block_with_invalid_state_diff() {
+    let state_diff = StateDiff {
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(10),
+            CompiledClassHash(Felt::ZERO),
+        )]),
+        ..Default::default()
+    };
+    let result = commit_block(Input {
+        storage: HashMap::new(),
+        state_diff,
+        contracts_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+        classes_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+        config: config(),
+    })
+    .await;
+    assert!(matches!(
+        result,
+        Err(BlockCommitmentError::InvalidStateDiff(InvalidStateDiff(problems)))
+            if problems == [StateDiffProblem::ZeroCompiledClassHash(class_hash(10))]
+    ));
+}
+
+/// Out-of-range felts are rejected before they reach the tries.
+#[tokio::test]
+async fn test_commit_state_diff_with_out_of_range_address() {
+    let out_of_range_address = ContractAddress(Felt::MAX);
+    let state_diff = StateDiff {
+        storage_updates: HashMap::from([(
+            out_of_range_address,
+            HashMap::from([(key(1), value(1))]),
+        )]),
+        ..Default::default()
+    };
+    let result = commit_state_diff(
+        &MapStorage::default(),
+        &state_diff,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &config(),
+    )
+    .await;
+    assert!(matches!(
+        result,
+        Err(BlockCommitmentError::InvalidStateDiff(InvalidStateDiff(problems)))
+            if problems == [StateDiffProblem::AddressOutOfRange(out_of_range_address)]
+    ));
+}
+
+/// The inverse of a block which declares a class maps it to a zero compiled class hash, which is
+/// accepted when reverting the block.
+#[tokio::test]
+async fn test_revert_block_deleting_declared_class() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let state_diff = StateDiff {
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(12),
+            CompiledClassHash(Felt::from(102_u128)),
+        )]),
+        ..Default::default()
+    };
+    let (filled_forest, inverse_state_diff) = commit_state_diff_with_inverse(
+        &storage,
+        &state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+    assert_eq!(
+        inverse_state_diff.class_hash_to_compiled_class_hash,
+        HashMap::from([(class_hash(12), CompiledClassHash(Felt::ZERO))])
+    );
+    assert!(matches!(
+        inverse_state_diff.validate(),
+        Err(InvalidStateDiff(problems))
+            if problems == [StateDiffProblem::ZeroCompiledClassHash(class_hash(12))]
+    ));
+
+    let reverted_forest = revert_state_diff(
+        &storage,
+        &inverse_state_diff,
+        filled_forest.get_contract_root_hash(),
+        filled_forest.get_compiled_class_root_hash(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        reverted_forest.get_compiled_class_root_hash(),
+        classes_trie_root_hash
+    );
+}
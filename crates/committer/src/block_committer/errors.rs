@@ -1,9 +1,65 @@
 use thiserror::Error;
 
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey};
+use crate::block_committer::root_registry::BlockNumber;
 use crate::forest_errors::ForestError;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::ClassHash;
+use crate::storage::errors::StorageError;
 
 #[derive(Debug, Error)]
 pub enum BlockCommitmentError {
     #[error(transparent)]
     ForestError(#[from] ForestError),
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
+    #[error(transparent)]
+    RootRegistry(#[from] RootRegistryError),
+    #[error(transparent)]
+    InvalidStateDiff(#[from] InvalidStateDiff),
+    #[error(
+        "Reverting the block resulted in the roots (contracts, classes) {actual:?}, instead of the \
+         previous roots {expected:?}."
+    )]
+    RevertedRootsMismatch {
+        expected: (HashOutput, HashOutput),
+        actual: (HashOutput, HashOutput),
+    },
+}
+
+/// A problem found in a state diff by [crate::block_committer::input::StateDiff::validate].
+#[derive(Debug, Error, PartialEq, Eq, PartialOrd, Ord)]
+pub enum StateDiffProblem {
+    #[error("Contract address {0:?} is out of the range of the tree.")]
+    AddressOutOfRange(ContractAddress),
+    #[error("Storage key {key:?} of contract {address:?} is out of the range of the tree.")]
+    StorageKeyOutOfRange {
+        address: ContractAddress,
+        key: StarknetStorageKey,
+    },
+    #[error("Class hash {0:?} is out of the range of the tree.")]
+    ClassHashOutOfRange(ClassHash),
+    #[error("Contract address {0:?} is reserved, and cannot be assigned a class hash or a nonce.")]
+    ReservedAddress(ContractAddress),
+    #[error("Class hash {0:?} is mapped to a zero compiled class hash.")]
+    ZeroCompiledClassHash(ClassHash),
+}
+
+#[derive(Debug, Error)]
+#[error(
+    "Invalid state diff: {}",
+    .0.iter().map(ToString::to_string).collect::<Vec<_>>().join(" ")
+)]
+pub struct InvalidStateDiff(pub Vec<StateDiffProblem>);
+
+#[derive(Debug, Error)]
+pub enum RootRegistryError {
+    #[error("The roots of block {0:?} are not recorded.")]
+    MissingBlock(BlockNumber),
+    #[error("The recorded roots of block {0:?} are corrupted.")]
+    CorruptedRoots(BlockNumber),
+    #[error(transparent)]
+    StorageError(#[from] StorageError),
 }
+
+pub type RootRegistryResult<T> = Result<T, RootRegistryError>;
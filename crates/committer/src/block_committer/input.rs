@@ -1,5 +1,6 @@
 use log::LevelFilter;
 
+use crate::block_committer::errors::{InvalidStateDiff, StateDiffProblem};
 use crate::felt::Felt;
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
@@ -9,17 +10,30 @@ use crate::storage::storage_trait::{StorageKey, StorageValue};
 use std::collections::{HashMap, HashSet};
 use std::fmt::Debug;
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[cfg(test)]
+#[path = "input_test.rs"]
+pub mod input_test;
+
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 // TODO(Nimrod, 1/6/2025): Use the ContractAddress defined in starknet-types-core when available.
 pub struct ContractAddress(pub Felt);
 
-#[derive(Clone, Copy, Debug, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, PartialEq, Eq, PartialOrd, Ord, Hash)]
 // TODO(Nimrod, 1/6/2025):  Use the StarknetStorageValue defined in starknet-types-core when available.
 pub struct StarknetStorageKey(pub Felt);
 
 #[derive(Clone, Copy, Default, Debug, Eq, PartialEq)]
 pub struct StarknetStorageValue(pub Felt);
 
+/// Addresses which are never the address of a deployed contract; only their storage may be
+/// modified. These are the zero address, the block hash contract (0x1) and the alias contract
+/// (0x2).
+pub const RESERVED_CONTRACT_ADDRESSES: [ContractAddress; 3] = [
+    ContractAddress(Felt::ZERO),
+    ContractAddress(Felt::ONE),
+    ContractAddress(Felt::TWO),
+];
+
 #[derive(Debug, Default, Eq, PartialEq)]
 pub struct StateDiff {
     pub address_to_class_hash: HashMap<ContractAddress, ClassHash>,
@@ -38,12 +52,36 @@ pub trait Config: Debug + Eq + PartialEq {
 
     /// Indicates from which log level output should be printed out to console.
     fn logger_level(&self) -> LevelFilter;
+
+    /// Indicates whether the new tries are hashed bottom-up, layer by layer, on a thread pool,
+    /// rather than by a task per node. Both ways produce the same tries.
+    fn layered_hashing(&self) -> bool {
+        false
+    }
+
+    /// Indicates whether a flat index of the storage values and contract states at the latest root
+    /// is written alongside the tries (see [crate::state_reader::flat_index]). While the index is
+    /// at the root committed on, it is also used for the trivial modification checks of storage
+    /// values, instead of the previous leaves.
+    fn flat_state_index(&self) -> bool {
+        false
+    }
+
+    /// Indicates whether the hash of every node read while building the original skeletons is
+    /// recomputed from its preimage and compared to the hash it is stored under, to detect
+    /// corrupted storage before it affects the new roots.
+    fn verify_on_read(&self) -> bool {
+        false
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
 pub struct ConfigImpl {
     warn_on_trivial_modifications: bool,
     log_level: LevelFilter,
+    layered_hashing: bool,
+    flat_state_index: bool,
+    verify_on_read: bool,
 }
 
 impl Config for ConfigImpl {
@@ -54,15 +92,49 @@ impl Config for ConfigImpl {
     fn logger_level(&self) -> LevelFilter {
         self.log_level
     }
+
+    fn layered_hashing(&self) -> bool {
+        self.layered_hashing
+    }
+
+    fn flat_state_index(&self) -> bool {
+        self.flat_state_index
+    }
+
+    fn verify_on_read(&self) -> bool {
+        self.verify_on_read
+    }
 }
 
 impl ConfigImpl {
+    /// Creates a configuration with the optional features (see the setters below) disabled.
     pub fn new(warn_on_trivial_modifications: bool, log_level: LevelFilter) -> Self {
         Self {
             warn_on_trivial_modifications,
             log_level,
+            layered_hashing: false,
+            flat_state_index: false,
+            verify_on_read: false,
         }
     }
+
+    /// See [Config::layered_hashing].
+    pub fn with_layered_hashing(mut self, layered_hashing: bool) -> Self {
+        self.layered_hashing = layered_hashing;
+        self
+    }
+
+    /// See [Config::flat_state_index].
+    pub fn with_flat_state_index(mut self, flat_state_index: bool) -> Self {
+        self.flat_state_index = flat_state_index;
+        self
+    }
+
+    /// See [Config::verify_on_read].
+    pub fn with_verify_on_read(mut self, verify_on_read: bool) -> Self {
+        self.verify_on_read = verify_on_read;
+        self
+    }
 }
 
 #[derive(Debug, Eq, PartialEq)]
@@ -76,6 +148,66 @@ pub struct Input<C: Config> {
 }
 
 impl StateDiff {
+    /// Checks that the state diff can be committed, and returns all the problems found otherwise:
+    /// every address, storage key and class hash must be a leaf of a 251-bit tree, reserved
+    /// addresses cannot be assigned a class hash or a nonce, and compiled class hashes cannot be
+    /// zero. Duplicate entries cannot be represented by the maps, so they are rejected when the
+    /// state diff is parsed. The problems are sorted by kind and then by key.
+    pub fn validate(&self) -> Result<(), InvalidStateDiff> {
+        self.validate_impl(false)
+    }
+
+    /// Same as [Self::validate], for the inverse state diff of a block (see
+    /// [crate::block_committer::commit::revert_block]), which maps the classes declared in the
+    /// block to a zero compiled class hash to delete them.
+    pub fn validate_inverse(&self) -> Result<(), InvalidStateDiff> {
+        self.validate_impl(true)
+    }
+
+    fn validate_impl(&self, allow_zero_compiled_class_hash: bool) -> Result<(), InvalidStateDiff> {
+        let mut problems = Vec::new();
+        for address in self.accessed_addresses() {
+            if NodeIndex::try_from_contract_address(address).is_err() {
+                problems.push(StateDiffProblem::AddressOutOfRange(*address));
+            }
+        }
+        for (address, updates) in self.storage_updates.iter() {
+            for key in updates.keys() {
+                if NodeIndex::try_from_starknet_storage_key(key).is_err() {
+                    problems.push(StateDiffProblem::StorageKeyOutOfRange {
+                        address: *address,
+                        key: *key,
+                    });
+                }
+            }
+        }
+        let deployed_addresses: HashSet<&ContractAddress> = self
+            .address_to_class_hash
+            .keys()
+            .chain(self.address_to_nonce.keys())
+            .collect();
+        for address in deployed_addresses {
+            if RESERVED_CONTRACT_ADDRESSES.contains(address) {
+                problems.push(StateDiffProblem::ReservedAddress(*address));
+            }
+        }
+        for (class_hash, compiled_class_hash) in self.class_hash_to_compiled_class_hash.iter() {
+            if NodeIndex::try_from_class_hash(class_hash).is_err() {
+                problems.push(StateDiffProblem::ClassHashOutOfRange(*class_hash));
+            }
+            if !allow_zero_compiled_class_hash && compiled_class_hash.0 == Felt::ZERO {
+                problems.push(StateDiffProblem::ZeroCompiledClassHash(*class_hash));
+            }
+        }
+        if problems.is_empty() {
+            Ok(())
+        } else {
+            // The problems were found in the (arbitrary) iteration order of the maps.
+            problems.sort();
+            Err(InvalidStateDiff(problems))
+        }
+    }
+
     pub(crate) fn accessed_addresses(&self) -> HashSet<&ContractAddress> {
         HashSet::from_iter(
             self.address_to_class_hash
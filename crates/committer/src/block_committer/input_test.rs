@@ -0,0 +1,137 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::errors::StateDiffProblem;
+use crate::block_committer::input::{
+    ContractAddress, StarknetStorageKey, StateDiff, RESERVED_CONTRACT_ADDRESSES,
+};
+use crate::felt::Felt;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::{address, class_hash, key, value};
+use crate::patricia_merkle_tree::types::NodeIndex;
+
+/// The smallest felt which is out of the range of the tree (2^251).
+fn first_out_of_range_felt() -> Felt {
+    Felt::try_from(NodeIndex::FIRST_LEAF).unwrap()
+}
+
+fn valid_state_diff() -> StateDiff {
+    StateDiff {
+        address_to_class_hash: HashMap::from([(address(2), class_hash(10))]),
+        address_to_nonce: HashMap::from([(address(2), Nonce(Felt::ONE))]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(10),
+            CompiledClassHash(Felt::from(100_u128)),
+        )]),
+        storage_updates: HashMap::from([
+            (address(2), HashMap::from([(key(5), value(50))])),
+            // The storage of a reserved address may be modified.
+            (
+                RESERVED_CONTRACT_ADDRESSES[0],
+                HashMap::from([(key(7), value(70))]),
+            ),
+        ]),
+    }
+}
+
+#[rstest]
+fn test_valid_state_diff() {
+    valid_state_diff().validate().unwrap();
+    StateDiff::default().validate().unwrap();
+
+    let max_felt = Felt::try_from(NodeIndex::MAX - NodeIndex::FIRST_LEAF).unwrap();
+    StateDiff {
+        storage_updates: HashMap::from([(
+            address(2),
+            HashMap::from([(StarknetStorageKey(max_felt), value(1))]),
+        )]),
+        ..Default::default()
+    }
+    .validate()
+    .unwrap();
+}
+
+#[rstest]
+fn test_out_of_range_state_diff() {
+    let out_of_range_address = ContractAddress(Felt::MAX);
+    let out_of_range_key = StarknetStorageKey(first_out_of_range_felt());
+    let out_of_range_class_hash = ClassHash(first_out_of_range_felt());
+    let mut state_diff = valid_state_diff();
+    state_diff
+        .address_to_nonce
+        .insert(out_of_range_address, Nonce(Felt::ONE));
+    state_diff
+        .storage_updates
+        .get_mut(&address(2))
+        .unwrap()
+        .insert(out_of_range_key, value(1));
+    state_diff.class_hash_to_compiled_class_hash.insert(
+        out_of_range_class_hash,
+        CompiledClassHash(Felt::from(100_u128)),
+    );
+
+    let problems = state_diff.validate().unwrap_err().0;
+    assert_eq!(problems.len(), 3, "{problems:?}");
+    assert!(problems.contains(&StateDiffProblem::AddressOutOfRange(out_of_range_address)));
+    assert!(problems.contains(&StateDiffProblem::StorageKeyOutOfRange {
+        address: address(2),
+        key: out_of_range_key,
+    }));
+    assert!(problems.contains(&StateDiffProblem::ClassHashOutOfRange(
+        out_of_range_class_hash
+    )));
+}
+
+#[rstest]
+fn test_reserved_address_and_zero_compiled_class_hash(
+    #[values(RESERVED_CONTRACT_ADDRESSES[0], RESERVED_CONTRACT_ADDRESSES[1], RESERVED_CONTRACT_ADDRESSES[2])]
+    reserved_address: ContractAddress,
+) {
+    let mut state_diff = valid_state_diff();
+    state_diff
+        .address_to_class_hash
+        .insert(reserved_address, class_hash(10));
+    state_diff
+        .address_to_nonce
+        .insert(reserved_address, Nonce(Felt::ONE));
+    state_diff
+        .class_hash_to_compiled_class_hash
+        .insert(class_hash(11), CompiledClassHash(Felt::ZERO));
+
+    let problems = state_diff.validate().unwrap_err().0;
+    // A reserved address is reported once, even if it has both a class hash and a nonce.
+    assert_eq!(
+        problems,
+        vec![
+            StateDiffProblem::ReservedAddress(reserved_address),
+            StateDiffProblem::ZeroCompiledClassHash(class_hash(11)),
+        ]
+    );
+    // An inverse state diff may delete classes.
+    assert_eq!(
+        state_diff.validate_inverse().unwrap_err().0,
+        vec![StateDiffProblem::ReservedAddress(reserved_address)]
+    );
+}
+
+#[rstest]
+fn test_state_diff_problems_are_sorted() {
+    let out_of_range_address = |n: u128| ContractAddress(first_out_of_range_felt() + Felt::from(n));
+    let mut state_diff = valid_state_diff();
+    for n in 0..5 {
+        state_diff
+            .address_to_nonce
+            .insert(out_of_range_address(n), Nonce(Felt::ONE));
+        state_diff
+            .class_hash_to_compiled_class_hash
+            .insert(class_hash(n), CompiledClassHash(Felt::ZERO));
+    }
+
+    let problems = state_diff.validate().unwrap_err().0;
+    let expected_problems: Vec<_> = (0..5)
+        .map(|n| StateDiffProblem::AddressOutOfRange(out_of_range_address(n)))
+        .chain((0..5).map(|n| StateDiffProblem::ZeroCompiledClassHash(class_hash(n))))
+        .collect();
+    assert_eq!(problems, expected_problems);
+}
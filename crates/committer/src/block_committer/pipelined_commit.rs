@@ -0,0 +1,173 @@
+use crate::block_committer::batch_commit::{validate_state_diffs, BlockCommitment};
+use std::collections::HashMap;
+
+use crate::block_committer::commit::{
+    create_updated_forest, fill_forest, get_all_modified_indices, BlockCommitmentResult,
+};
+use crate::block_committer::input::{Config, StarknetStorageValue, StateDiff};
+use crate::forest_errors::ForestError;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TraversalResult;
+use crate::patricia_merkle_tree::filled_tree::forest::HashingMode;
+use crate::patricia_merkle_tree::filled_tree::node::CompiledClassHash;
+use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::traversal::fetch_paths;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
+use crate::storage::cached_storage::CachedStorage;
+use crate::storage::storage_trait::AsyncStorage;
+
+#[cfg(test)]
+#[path = "pipelined_commit_test.rs"]
+pub mod pipelined_commit_test;
+
+/// Same as [crate::block_committer::batch_commit::commit_blocks], but pipelined: while a block is
+/// hashed on a separate task, the nodes needed by the next block are read ahead into an in-memory
+/// cache.
+///
+/// The next block's roots are not known before the current block is hashed, so the nodes are read
+/// ahead from the state before the current block. Nodes in subtrees which are not modified by the
+/// current block are identical in both states. Nodes in modified subtrees have new hashes, and
+/// therefore new storage keys; these are taken from the current block's facts, which are cached as
+/// they are written.
+///
+/// Only the read-ahead overlaps with hashing: the skeleton stage of the next block needs the roots
+/// of the current block, so it runs after the current block is hashed and its facts are written.
+///
+/// As with the unpipelined version, all the state diffs are validated before the first block is
+/// committed.
+pub async fn commit_blocks_pipelined(
+    storage: &mut impl AsyncStorage,
+    state_diffs: &[StateDiff],
+    mut contracts_trie_root_hash: HashOutput,
+    mut classes_trie_root_hash: HashOutput,
+    config: &impl Config,
+) -> BlockCommitmentResult<Vec<BlockCommitment>> {
+    validate_state_diffs(state_diffs)?;
+    let mut storage = CachedStorage::new(storage);
+    let mut block_commitments = Vec::with_capacity(state_diffs.len());
+    let mut skeleton_stage_output = match state_diffs.first() {
+        Some(state_diff) => Some(
+            create_updated_forest(
+                &storage,
+                state_diff,
+                contracts_trie_root_hash,
+                classes_trie_root_hash,
+                config,
+            )
+            .await?,
+        ),
+        None => None,
+    };
+
+    // The skeleton stage of each block is done before it is hashed.
+    let mut next_state_diffs = state_diffs.iter().skip(1);
+    while let Some(current_skeleton_stage_output) = skeleton_stage_output.take() {
+        let next_state_diff = next_state_diffs.next();
+        let hashing_task = tokio::spawn(fill_forest(
+            current_skeleton_stage_output,
+            HashingMode::from_config(config),
+        ));
+        if let Some(next_state_diff) = next_state_diff {
+            read_ahead(
+                &storage,
+                next_state_diff,
+                contracts_trie_root_hash,
+                classes_trie_root_hash,
+            )
+            .await;
+        }
+        let filled_forest = hashing_task.await.map_err(ForestError::from)??;
+        contracts_trie_root_hash = filled_forest.get_contract_root_hash();
+        classes_trie_root_hash = filled_forest.get_compiled_class_root_hash();
+        let facts = filled_forest.serialize();
+        filled_forest
+            .write_facts_to_storage(&mut storage, facts.clone())
+            .await?;
+        block_commitments.push(BlockCommitment {
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            facts,
+            orphaned_node_keys: filled_forest.orphaned_node_keys,
+            released_node_references: filled_forest.released_node_references,
+        });
+
+        if let Some(next_state_diff) = next_state_diff {
+            skeleton_stage_output = Some(
+                create_updated_forest(
+                    &storage,
+                    next_state_diff,
+                    contracts_trie_root_hash,
+                    classes_trie_root_hash,
+                    config,
+                )
+                .await?,
+            );
+            storage.clear_cache();
+        }
+    }
+    Ok(block_commitments)
+}
+
+/// Reads the nodes on the paths to the leaves modified by the given state diff, in the tries with
+/// the given roots, so that they are cached. Failures are ignored; they are reported when the state
+/// diff is actually committed.
+async fn read_ahead<S: AsyncStorage>(
+    storage: &CachedStorage<'_, S>,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+) {
+    let _: TraversalResult<()> = fetch_modified_paths(
+        storage,
+        state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+    )
+    .await;
+}
+
+async fn fetch_modified_paths(
+    storage: &impl AsyncStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+) -> TraversalResult<()> {
+    let (mut storage_tries_indices, mut contracts_trie_indices, mut classes_trie_indices) =
+        get_all_modified_indices(state_diff);
+    let mut storage_root_hashes = HashMap::new();
+    let (contracts_trie_result, classes_trie_result) = tokio::join!(
+        fetch_paths::<ContractState>(
+            storage,
+            contracts_trie_root_hash,
+            SortedLeafIndices::new(&mut contracts_trie_indices),
+            |index, node| {
+                if let NodeData::Leaf(contract_state) = node.data {
+                    storage_root_hashes.insert(index, contract_state.storage_root_hash);
+                }
+            },
+        ),
+        fetch_paths::<CompiledClassHash>(
+            storage,
+            classes_trie_root_hash,
+            SortedLeafIndices::new(&mut classes_trie_indices),
+            |_, _| {},
+        ),
+    );
+    contracts_trie_result?;
+    classes_trie_result?;
+    for (address, indices) in storage_tries_indices.iter_mut() {
+        let storage_root_hash = storage_root_hashes
+            .get(&NodeIndex::from_contract_address(address))
+            .copied()
+            .unwrap_or(HashOutput::ROOT_OF_EMPTY_TREE);
+        fetch_paths::<StarknetStorageValue>(
+            storage,
+            storage_root_hash,
+            SortedLeafIndices::new(indices),
+            |_, _| {},
+        )
+        .await?;
+    }
+    Ok(())
+}
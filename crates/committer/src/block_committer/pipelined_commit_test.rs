@@ -0,0 +1,178 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::batch_commit::commit_blocks;
+use crate::block_committer::errors::{BlockCommitmentError, InvalidStateDiff, StateDiffProblem};
+use crate::block_committer::input::{ConfigImpl, StateDiff, RESERVED_CONTRACT_ADDRESSES};
+use crate::block_committer::pipelined_commit::{commit_blocks_pipelined, read_ahead};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::Nonce;
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, create_committed_state, initial_state_diff, inverse_update_state_diff, key,
+    update_state_diff, value,
+};
+use crate::storage::cached_storage::CachedStorage;
+use crate::storage::map_storage::MapStorage;
+
+/// A block which sets the given (contract, key, value) storage triplets.
+fn set_values(updates: &[(u128, u128, u128)]) -> StateDiff {
+    let mut storage_updates: HashMap<_, HashMap<_, _>> = HashMap::new();
+    for (contract, storage_key, storage_value) in updates {
+        storage_updates
+            .entry(address(*contract))
+            .or_default()
+            .insert(key(*storage_key), value(*storage_value));
+    }
+    StateDiff {
+        storage_updates,
+        ..Default::default()
+    }
+}
+
+#[rstest]
+#[case::no_blocks(vec![])]
+#[case::single_block(vec![initial_state_diff()])]
+#[case::dependent_blocks(vec![initial_state_diff(), update_state_diff(), inverse_update_state_diff()])]
+#[case::empty_block(vec![initial_state_diff(), StateDiff::default(), update_state_diff()])]
+// The blocks write the same nodes: both contracts share their storage trie, and the last block
+// recreates the nodes of the first one.
+#[case::shared_nodes(vec![
+    set_values(&[(1, 5, 50), (2, 5, 50)]),
+    set_values(&[(1, 5, 51)]),
+    set_values(&[(1, 5, 50)]),
+])]
+// The second block deletes all the values, and the third one reads from the empty state.
+#[case::deleted_state(vec![
+    set_values(&[(1, 5, 50), (2, 6, 60)]),
+    set_values(&[(1, 5, 0), (2, 6, 0)]),
+    set_values(&[(1, 5, 50)]),
+])]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_pipelined_commit_matches_sequential_commit(#[case] state_diffs: Vec<StateDiff>) {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut expected_storage = MapStorage::default();
+    let expected_block_commitments = commit_blocks(
+        &mut expected_storage,
+        &state_diffs,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &config,
+    )
+    .await
+    .unwrap();
+
+    let mut storage = MapStorage::default();
+    let block_commitments = commit_blocks_pipelined(
+        &mut storage,
+        &state_diffs,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &config,
+    )
+    .await
+    .unwrap();
+    assert_eq!(block_commitments, expected_block_commitments);
+    assert_eq!(storage.storage, expected_storage.storage);
+}
+
+#[tokio::test]
+async fn test_reverting_block_restores_roots() {
+    let mut storage = MapStorage::default();
+    let block_commitments = commit_blocks_pipelined(
+        &mut storage,
+        &[
+            initial_state_diff(),
+            update_state_diff(),
+            inverse_update_state_diff(),
+        ],
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &ConfigImpl::new(false, log::LevelFilter::Debug),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        block_commitments[2].contracts_trie_root_hash,
+        block_commitments[0].contracts_trie_root_hash
+    );
+    assert_eq!(
+        block_commitments[2].classes_trie_root_hash,
+        block_commitments[0].classes_trie_root_hash
+    );
+}
+
+#[rstest]
+#[case::complete_state(false)]
+#[case::missing_nodes(true)]
+#[tokio::test]
+async fn test_read_ahead(#[case] drop_nodes: bool) {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    if drop_nodes {
+        // Keep only the roots of the contracts trie and the classes trie.
+        storage.storage.retain(|key, _| {
+            [contracts_trie_root_hash, classes_trie_root_hash]
+                .iter()
+                .any(|root_hash| key.0.ends_with(&root_hash.0.to_bytes_be()))
+        });
+    }
+    let n_entries = storage.storage.len();
+    let cached_storage = CachedStorage::new(&mut storage);
+    read_ahead(
+        &cached_storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+    )
+    .await;
+    // The read nodes are cached, and read failures are ignored.
+    let cache_size = cached_storage.cache_size();
+    assert!(cache_size > 0 && cache_size <= n_entries);
+    if drop_nodes {
+        assert_eq!(cache_size, 2);
+    }
+}
+
+/// No block of a batch is committed if any of its state diffs is invalid.
+#[rstest]
+#[tokio::test]
+async fn test_invalid_block_commits_no_blocks(#[values(false, true)] pipelined: bool) {
+    let invalid_state_diff = StateDiff {
+        address_to_nonce: HashMap::from([(RESERVED_CONTRACT_ADDRESSES[1], Nonce(Felt::ONE))]),
+        ..Default::default()
+    };
+    let state_diffs = [initial_state_diff(), invalid_state_diff];
+    let mut storage = MapStorage::default();
+    let (contracts_trie_root_hash, classes_trie_root_hash) = (
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    );
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let result = if pipelined {
+        commit_blocks_pipelined(
+            &mut storage,
+            &state_diffs,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            &config,
+        )
+        .await
+    } else {
+        commit_blocks(
+            &mut storage,
+            &state_diffs,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            &config,
+        )
+        .await
+    };
+    assert!(matches!(
+        result,
+        Err(BlockCommitmentError::InvalidStateDiff(InvalidStateDiff(problems)))
+            if problems == [StateDiffProblem::ReservedAddress(RESERVED_CONTRACT_ADDRESSES[1])]
+    ));
+    assert!(storage.storage.is_empty());
+}
@@ -0,0 +1,114 @@
+use std::collections::{HashMap, VecDeque};
+
+use crate::block_committer::root_registry::{delete_block_roots, BlockNumber};
+use crate::storage::ref_counted_storage::RefCountedStorage;
+use crate::storage::storage_trait::{AsyncStorage, Storage, StorageKey, StorageResult};
+
+#[cfg(test)]
+#[path = "prune_test.rs"]
+pub mod prune_test;
+
+/// Deletes the nodes orphaned by consecutive commitments from a [RefCountedStorage], while keeping
+/// the tries of a given number of historical roots readable.
+///
+/// Nodes are keyed by their hashes, so a node may be held by several positions in the tries (e.g.,
+/// two storage leaves with the same value, or identical storage tries of two contracts). Hence,
+/// the pruner releases references to nodes rather than deleting them, and a node is deleted only
+/// once no position holds it. Nodes written without references (e.g., before the storage was
+/// wrapped) are never deleted, as the positions holding them are unknown.
+///
+/// The references released by a commitment belong to the roots before it. They are released once
+/// these roots are no longer retained, i.e., after `n_retained_roots` further commitments.
+///
+/// The pending references are kept in memory only: if the pruner is dropped (e.g., on restart),
+/// the nodes they hold are never deleted. This leaks storage, but never deletes a reachable node.
+pub struct Pruner {
+    n_retained_roots: usize,
+    /// The references to release for each of the recent commitments whose previous roots are still
+    /// retained, oldest first.
+    pending_released_references: VecDeque<Vec<StorageKey>>,
+}
+
+impl Pruner {
+    pub fn new(n_retained_roots: usize) -> Self {
+        Self {
+            n_retained_roots,
+            pending_released_references: VecDeque::new(),
+        }
+    }
+
+    /// Records a commitment written to the storage, given the references to previous nodes it
+    /// released (see
+    /// [crate::patricia_merkle_tree::filled_tree::forest::FilledForest::released_node_references]),
+    /// and releases the references of commitments whose previous roots are no longer retained.
+    /// Returns the number of deleted nodes.
+    pub async fn prune<S: Storage + Send + Sync>(
+        &mut self,
+        storage: &mut RefCountedStorage<S>,
+        released_node_references: &HashMap<StorageKey, u64>,
+    ) -> StorageResult<usize> {
+        self.pending_released_references.push_back(
+            released_node_references
+                .iter()
+                .flat_map(|(key, n_references)| {
+                    std::iter::repeat_n(
+                        key.clone(),
+                        usize::try_from(*n_references)
+                            .expect("The number of references to a node fits in usize."),
+                    )
+                })
+                .collect(),
+        );
+        self.release_expired_references(storage).await
+    }
+
+    /// Deletes the recorded roots (see [crate::block_committer::root_registry]) of the blocks whose
+    /// tries are no longer retained after committing the given block, so that only blocks whose
+    /// state is still readable can be queried. Returns the number of blocks whose recorded roots
+    /// were deleted.
+    ///
+    /// Blocks are recorded consecutively, and expired blocks are deleted from the newest down to
+    /// the first block which is no longer recorded, so calls may be skipped for some blocks.
+    pub async fn prune_block_roots(
+        &self,
+        storage: &mut impl AsyncStorage,
+        block_number: BlockNumber,
+    ) -> StorageResult<usize> {
+        let n_retained_roots = u64::try_from(self.n_retained_roots)
+            .expect("The number of retained roots fits in u64.");
+        let mut n_deleted_blocks = 0;
+        let mut expired_block_number = block_number.0.checked_sub(n_retained_roots + 1);
+        while let Some(expired) = expired_block_number {
+            if !delete_block_roots(storage, BlockNumber(expired)).await? {
+                break;
+            }
+            n_deleted_blocks += 1;
+            expired_block_number = expired.checked_sub(1);
+        }
+        Ok(n_deleted_blocks)
+    }
+
+    async fn release_expired_references<S: Storage + Send + Sync>(
+        &mut self,
+        storage: &mut RefCountedStorage<S>,
+    ) -> StorageResult<usize> {
+        let mut n_deleted_nodes = 0;
+        while self.pending_released_references.len() > self.n_retained_roots {
+            let expired_keys = self
+                .pending_released_references
+                .pop_front()
+                .expect("The pending keys are not empty.");
+            for key in expired_keys {
+                if storage.release_reference(&key)?.is_some() {
+                    n_deleted_nodes += 1;
+                }
+            }
+        }
+        Ok(n_deleted_nodes)
+    }
+
+    /// Returns the number of node references which are not released yet.
+    pub fn n_pending_references(&self) -> usize {
+        self.pending_released_references.iter().map(Vec::len).sum()
+    }
+}
@@ -0,0 +1,254 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::commit::commit_state_diff;
+use crate::block_committer::input::{ConfigImpl, StateDiff};
+use crate::block_committer::prune::Pruner;
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{CompiledClassHash, FilledNode, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, class_hash, initial_state_diff, inverse_update_state_diff, key, reachable_facts,
+    update_state_diff, value,
+};
+use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
+use crate::state_reader::reader::get_storage_value;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::ref_counted_storage::RefCountedStorage;
+
+/// A diff on top of [update_state_diff], which modifies every trie.
+fn third_state_diff() -> StateDiff {
+    StateDiff {
+        address_to_nonce: HashMap::from([(address(2), Nonce(Felt::ONE))]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(10),
+            CompiledClassHash(Felt::from(103_u128)),
+        )]),
+        storage_updates: HashMap::from([(address(1), HashMap::from([(key(6), value(61))]))]),
+        ..Default::default()
+    }
+}
+
+/// Stores the same value in two slots of a contract and in a slot of another contract, then
+/// overwrites one of the slots. Storage leaves are keyed by their values, so the leaf released by
+/// the overwrite is still held by the other slots.
+fn shared_value_state_diffs() -> Vec<StateDiff> {
+    vec![
+        StateDiff {
+            storage_updates: HashMap::from([
+                (
+                    address(1),
+                    HashMap::from([(key(5), value(7)), (key(6), value(7))]),
+                ),
+                (address(2), HashMap::from([(key(5), value(7))])),
+            ]),
+            ..Default::default()
+        },
+        set_value_state_diffs(&[8]).remove(0),
+    ]
+}
+
+/// Sets the same storage leaf to each of the given values, one block after another.
+fn set_value_state_diffs(values: &[u128]) -> Vec<StateDiff> {
+    values
+        .iter()
+        .map(|stored_value| StateDiff {
+            storage_updates: HashMap::from([(
+                address(1),
+                HashMap::from([(key(5), value(*stored_value))]),
+            )]),
+            ..Default::default()
+        })
+        .collect()
+}
+
+#[rstest]
+#[case::modify_all_tries(vec![initial_state_diff(), update_state_diff(), third_state_diff()])]
+#[case::recreate_orphaned_nodes(set_value_state_diffs(&[1, 2, 1, 3, 2]))]
+// The update copies the storage trie of address 2 to address 3, and the inverse update deletes the
+// copy, releasing nodes which are still held by address 2.
+#[case::shared_subtrees(vec![initial_state_diff(), update_state_diff(), inverse_update_state_diff()])]
+#[case::shared_leaf_values(shared_value_state_diffs())]
+// Empty blocks release no references, but still count as retained roots.
+#[case::empty_blocks(vec![initial_state_diff(), StateDiff::default(), StateDiff::default()])]
+// Deleting the only value empties all the tries, releasing all the nodes of the previous block.
+#[case::deleted_state(set_value_state_diffs(&[1, 0, 1, 0]))]
+// Deleting key 0 collapses the binary node above it, merging the unmodified edge to keys 6 and 7
+// into the edge above it.
+#[case::merged_edge(vec![
+    StateDiff {
+        storage_updates: HashMap::from([(
+            address(1),
+            HashMap::from([(key(0), value(1)), (key(6), value(2)), (key(7), value(3))]),
+        )]),
+        ..Default::default()
+    },
+    StateDiff {
+        storage_updates: HashMap::from([(address(1), HashMap::from([(key(0), value(0))]))]),
+        ..Default::default()
+    },
+])]
+#[tokio::test]
+async fn test_prune(
+    #[case] state_diffs: Vec<StateDiff>,
+    #[values(0, 1, 2, 3)] n_retained_roots: usize,
+) {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+    // Holds all written facts, to compute the facts reachable from each root.
+    let mut unpruned_storage = MapStorage::default();
+    let mut pruner = Pruner::new(n_retained_roots);
+    let mut roots = vec![(
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )];
+    for state_diff in state_diffs {
+        let (contracts_trie_root_hash, classes_trie_root_hash) = *roots.last().unwrap();
+        let filled_forest = commit_state_diff(
+            &storage,
+            &state_diff,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            &config,
+        )
+        .await
+        .unwrap();
+        filled_forest.write_to_storage(&mut storage).await.unwrap();
+        filled_forest
+            .write_to_storage(&mut unpruned_storage)
+            .await
+            .unwrap();
+        pruner
+            .prune(&mut storage, &filled_forest.released_node_references)
+            .await
+            .unwrap();
+        roots.push((
+            filled_forest.get_contract_root_hash(),
+            filled_forest.get_compiled_class_root_hash(),
+        ));
+    }
+
+    // Exactly the nodes of the current root and of the retained historical roots are kept.
+    let mut expected_facts = HashMap::new();
+    for (contracts_trie_root_hash, classes_trie_root_hash) in
+        roots.iter().rev().take(n_retained_roots + 1)
+    {
+        expected_facts.extend(
+            reachable_facts(
+                &unpruned_storage,
+                *contracts_trie_root_hash,
+                *classes_trie_root_hash,
+            )
+            .await,
+        );
+    }
+    let facts: HashMap<_, _> = storage
+        .into_inner()
+        .storage
+        .into_iter()
+        .filter(|(key, _)| !key.0.starts_with(b"refcount:"))
+        .collect();
+    assert_eq!(facts, expected_facts);
+}
+
+#[tokio::test]
+async fn test_prune_keeps_overwritten_shared_value() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+    let mut pruner = Pruner::new(0);
+    let mut roots = (
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    );
+    for state_diff in shared_value_state_diffs() {
+        let filled_forest = commit_state_diff(&storage, &state_diff, roots.0, roots.1, &config)
+            .await
+            .unwrap();
+        filled_forest.write_to_storage(&mut storage).await.unwrap();
+        pruner
+            .prune(&mut storage, &filled_forest.released_node_references)
+            .await
+            .unwrap();
+        roots = (
+            filled_forest.get_contract_root_hash(),
+            filled_forest.get_compiled_class_root_hash(),
+        );
+    }
+
+    // The overwritten slot released one of the three references to the shared leaf.
+    let shared_leaf_key = FilledNode {
+        hash: HashOutput(value(7).0),
+        data: NodeData::Leaf(value(7)),
+    }
+    .db_key();
+    assert_eq!(storage.reference_count(&shared_leaf_key).unwrap(), 2);
+    assert_eq!(pruner.n_pending_references(), 0);
+    for (contract, slot, expected_value) in [(1, 5, 8), (1, 6, 7), (2, 5, 7)] {
+        assert_eq!(
+            get_storage_value(&storage, roots.0, &address(contract), &key(slot))
+                .await
+                .unwrap(),
+            value(expected_value)
+        );
+    }
+}
+
+/// Nodes written without references (e.g., a database populated before pruning was enabled) may
+/// be held by any number of positions, so releasing them keeps them.
+#[tokio::test]
+async fn test_prune_keeps_untracked_shared_value() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let [first_state_diff, second_state_diff]: [StateDiff; 2] =
+        shared_value_state_diffs().try_into().unwrap();
+    let first_forest = commit_state_diff(
+        &MapStorage::default(),
+        &first_state_diff,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &config,
+    )
+    .await
+    .unwrap();
+    let mut storage = RefCountedStorage::new(MapStorage {
+        storage: first_forest.serialize(),
+    });
+
+    let second_forest = commit_state_diff(
+        &storage,
+        &second_state_diff,
+        first_forest.get_contract_root_hash(),
+        first_forest.get_compiled_class_root_hash(),
+        &config,
+    )
+    .await
+    .unwrap();
+    second_forest.write_to_storage(&mut storage).await.unwrap();
+    let shared_leaf_key = FilledNode {
+        hash: HashOutput(value(7).0),
+        data: NodeData::Leaf(value(7)),
+    }
+    .db_key();
+    assert!(second_forest
+        .released_node_references
+        .contains_key(&shared_leaf_key));
+    Pruner::new(0)
+        .prune(&mut storage, &second_forest.released_node_references)
+        .await
+        .unwrap();
+
+    assert_eq!(storage.reference_count(&shared_leaf_key).unwrap(), 0);
+    for (contract, slot, expected_value) in [(1, 5, 8), (1, 6, 7), (2, 5, 7)] {
+        assert_eq!(
+            get_storage_value(
+                &storage,
+                second_forest.get_contract_root_hash(),
+                &address(contract),
+                &key(slot)
+            )
+            .await
+            .unwrap(),
+            value(expected_value)
+        );
+    }
+}
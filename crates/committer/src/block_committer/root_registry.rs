@@ -0,0 +1,107 @@
+use crate::block_committer::errors::{RootRegistryError, RootRegistryResult};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::storage::storage_trait::{
+    create_db_key, AsyncStorage, StorageKey, StorageResult, StorageValue,
+};
+
+#[cfg(test)]
+#[path = "root_registry_test.rs"]
+pub mod root_registry_test;
+
+const BLOCK_ROOTS_PREFIX: &[u8] = b"block_roots";
+
+#[derive(Clone, Copy, Debug, Eq, Hash, Ord, PartialEq, PartialOrd)]
+pub struct BlockNumber(pub u64);
+
+/// The roots of the tries after a block.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub struct BlockRoots {
+    pub contracts_trie_root_hash: HashOutput,
+    pub classes_trie_root_hash: HashOutput,
+}
+
+impl BlockRoots {
+    /// The roots of the empty state, before the first block.
+    pub const EMPTY: Self = Self {
+        contracts_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+        classes_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
+    };
+
+    /// Serializes the roots as the big-endian contracts trie root followed by the big-endian
+    /// classes trie root.
+    fn serialize(&self) -> StorageValue {
+        StorageValue(
+            [
+                self.contracts_trie_root_hash.0.to_bytes_be(),
+                self.classes_trie_root_hash.0.to_bytes_be(),
+            ]
+            .concat(),
+        )
+    }
+
+    fn deserialize(value: &StorageValue) -> Option<Self> {
+        let bytes: &[u8; 64] = value.0.as_slice().try_into().ok()?;
+        let (contracts_trie_root_hash, classes_trie_root_hash) = bytes.split_at(32);
+        Some(Self {
+            contracts_trie_root_hash: HashOutput(Felt::from_bytes_be_slice(
+                contracts_trie_root_hash,
+            )),
+            classes_trie_root_hash: HashOutput(Felt::from_bytes_be_slice(classes_trie_root_hash)),
+        })
+    }
+}
+
+fn block_roots_key(block_number: BlockNumber) -> StorageKey {
+    create_db_key(BLOCK_ROOTS_PREFIX.to_vec(), &block_number.0.to_be_bytes())
+}
+
+/// Records the roots of the tries after the given block, overwriting previously recorded roots of
+/// the block (e.g., after a reorg).
+pub async fn record_block_roots(
+    storage: &mut impl AsyncStorage,
+    block_number: BlockNumber,
+    roots: &BlockRoots,
+) -> StorageResult<()> {
+    storage
+        .set(block_roots_key(block_number), roots.serialize())
+        .await?;
+    Ok(())
+}
+
+/// Returns the recorded roots of the tries after the given block.
+pub async fn get_block_roots(
+    storage: &impl AsyncStorage,
+    block_number: BlockNumber,
+) -> RootRegistryResult<BlockRoots> {
+    let value = storage
+        .get(&block_roots_key(block_number))
+        .await?
+        .ok_or(RootRegistryError::MissingBlock(block_number))?;
+    BlockRoots::deserialize(&value).ok_or(RootRegistryError::CorruptedRoots(block_number))
+}
+
+/// Returns the roots of the tries before the given block, i.e., the recorded roots of the previous
+/// block, or the roots of the empty state before block 0.
+pub async fn get_previous_block_roots(
+    storage: &impl AsyncStorage,
+    block_number: BlockNumber,
+) -> RootRegistryResult<BlockRoots> {
+    match block_number.0.checked_sub(1) {
+        None => Ok(BlockRoots::EMPTY),
+        Some(previous_block_number) => {
+            get_block_roots(storage, BlockNumber(previous_block_number)).await
+        }
+    }
+}
+
+/// Deletes the recorded roots of the given block. Returns whether the roots were recorded.
+pub async fn delete_block_roots(
+    storage: &mut impl AsyncStorage,
+    block_number: BlockNumber,
+) -> StorageResult<bool> {
+    Ok(storage
+        .delete(&block_roots_key(block_number))
+        .await?
+        .is_some())
+}
@@ -0,0 +1,291 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::commit::commit_and_record_block;
+use crate::block_committer::errors::{BlockCommitmentError, RootRegistryError};
+use crate::block_committer::input::{ConfigImpl, StateDiff};
+use crate::block_committer::prune::Pruner;
+use crate::block_committer::root_registry::{
+    get_block_roots, get_previous_block_roots, record_block_roots, BlockNumber, BlockRoots,
+};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash};
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, class_hash, initial_state_diff, inverse_update_state_diff, key, update_state_diff,
+    value,
+};
+use crate::patricia_merkle_tree::merkle_proof::forest_proof::ContractStorageProof;
+use crate::state_reader::errors::StateReaderError;
+use crate::state_reader::reader::{
+    get_compiled_class_hash_at_block, get_contract_state_at_block, get_storage_value_at_block,
+};
+use crate::storage::map_storage::MapStorage;
+use crate::storage::ref_counted_storage::RefCountedStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageValue};
+
+fn roots(contracts_trie_root_hash: u128, classes_trie_root_hash: u128) -> BlockRoots {
+    BlockRoots {
+        contracts_trie_root_hash: HashOutput(Felt::from(contracts_trie_root_hash)),
+        classes_trie_root_hash: HashOutput(Felt::from(classes_trie_root_hash)),
+    }
+}
+
+#[tokio::test]
+async fn test_record_block_roots() {
+    let mut storage = MapStorage::default();
+    record_block_roots(&mut storage, BlockNumber(7), &roots(1, 2))
+        .await
+        .unwrap();
+    record_block_roots(&mut storage, BlockNumber(8), &roots(3, 4))
+        .await
+        .unwrap();
+    // Recording the roots of a block again overwrites them.
+    record_block_roots(&mut storage, BlockNumber(8), &roots(5, 6))
+        .await
+        .unwrap();
+
+    assert_eq!(
+        get_block_roots(&storage, BlockNumber(7)).await.unwrap(),
+        roots(1, 2)
+    );
+    assert_eq!(
+        get_previous_block_roots(&storage, BlockNumber(9))
+            .await
+            .unwrap(),
+        roots(5, 6)
+    );
+    assert_eq!(
+        get_previous_block_roots(&storage, BlockNumber(0))
+            .await
+            .unwrap(),
+        BlockRoots::EMPTY
+    );
+    assert!(matches!(
+        get_block_roots(&storage, BlockNumber(6)).await,
+        Err(RootRegistryError::MissingBlock(BlockNumber(6)))
+    ));
+}
+
+#[tokio::test]
+async fn test_corrupted_block_roots() {
+    let mut storage = MapStorage::default();
+    record_block_roots(&mut storage, BlockNumber(1), &roots(1, 2))
+        .await
+        .unwrap();
+    let (key, StorageValue(mut value)) = storage.storage.drain().next().unwrap();
+    value.pop();
+    storage.set(key, StorageValue(value)).await.unwrap();
+
+    assert!(matches!(
+        get_block_roots(&storage, BlockNumber(1)).await,
+        Err(RootRegistryError::CorruptedRoots(BlockNumber(1)))
+    ));
+}
+
+#[tokio::test]
+async fn test_read_state_at_block() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = MapStorage::default();
+    for (block_number, state_diff) in [initial_state_diff(), update_state_diff()]
+        .iter()
+        .enumerate()
+    {
+        let block_number = BlockNumber(block_number.try_into().unwrap());
+        let filled_forest =
+            commit_and_record_block(&mut storage, block_number, state_diff, &config)
+                .await
+                .unwrap();
+        assert_eq!(
+            get_block_roots(&storage, block_number).await.unwrap(),
+            BlockRoots {
+                contracts_trie_root_hash: filled_forest.get_contract_root_hash(),
+                classes_trie_root_hash: filled_forest.get_compiled_class_root_hash(),
+            }
+        );
+    }
+
+    for (block_number, expected_value, expected_compiled_class_hash) in [
+        (BlockNumber(0), value(50), 100_u128),
+        (BlockNumber(1), value(0), 100_u128),
+    ] {
+        assert_eq!(
+            get_storage_value_at_block(&storage, block_number, &address(1), &key(5))
+                .await
+                .unwrap(),
+            expected_value
+        );
+        assert_eq!(
+            get_compiled_class_hash_at_block(&storage, block_number, &class_hash(10))
+                .await
+                .unwrap(),
+            CompiledClassHash(Felt::from(expected_compiled_class_hash))
+        );
+        let roots = get_block_roots(&storage, block_number).await.unwrap();
+        let proof =
+            ContractStorageProof::fetch_at_block(&storage, block_number, &address(1), &key(5))
+                .await
+                .unwrap();
+        assert_eq!(
+            proof
+                .verify(roots.contracts_trie_root_hash, &address(1), &key(5))
+                .unwrap()
+                .unwrap_or_default(),
+            expected_value
+        );
+    }
+    assert_eq!(
+        get_contract_state_at_block(&storage, BlockNumber(1), &address(3))
+            .await
+            .unwrap()
+            .class_hash,
+        class_hash(10)
+    );
+    assert_eq!(
+        get_contract_state_at_block(&storage, BlockNumber(0), &address(3))
+            .await
+            .unwrap()
+            .class_hash,
+        ClassHash::default()
+    );
+    assert!(matches!(
+        get_storage_value_at_block(&storage, BlockNumber(2), &address(1), &key(5)).await,
+        Err(StateReaderError::RootRegistry(
+            RootRegistryError::MissingBlock(BlockNumber(2))
+        ))
+    ));
+}
+
+#[tokio::test]
+async fn test_commit_and_record_invalid_block() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = MapStorage::default();
+    let invalid_state_diff = StateDiff {
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(10),
+            CompiledClassHash(Felt::ZERO),
+        )]),
+        ..Default::default()
+    };
+    assert!(matches!(
+        commit_and_record_block(&mut storage, BlockNumber(0), &invalid_state_diff, &config).await,
+        Err(BlockCommitmentError::InvalidStateDiff(_))
+    ));
+    assert!(storage.storage.is_empty());
+}
+
+#[tokio::test]
+async fn test_commit_requires_previous_block() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = MapStorage::default();
+    assert!(matches!(
+        commit_and_record_block(&mut storage, BlockNumber(1), &initial_state_diff(), &config).await,
+        Err(BlockCommitmentError::RootRegistry(
+            RootRegistryError::MissingBlock(BlockNumber(0))
+        ))
+    ));
+    assert!(storage.storage.is_empty());
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_prune_block_roots(#[values(0, 1, 2)] n_retained_roots: u64) {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+    let mut pruner = Pruner::new(n_retained_roots.try_into().unwrap());
+    let state_diffs = [
+        initial_state_diff(),
+        update_state_diff(),
+        inverse_update_state_diff(),
+    ];
+    for (block_number, state_diff) in state_diffs.iter().enumerate() {
+        let block_number = BlockNumber(block_number.try_into().unwrap());
+        let filled_forest =
+            commit_and_record_block(&mut storage, block_number, state_diff, &config)
+                .await
+                .unwrap();
+        pruner
+            .prune(&mut storage, &filled_forest.released_node_references)
+            .await
+            .unwrap();
+        pruner
+            .prune_block_roots(&mut storage, block_number)
+            .await
+            .unwrap();
+    }
+
+    // Exactly the retained blocks can be queried.
+    let last_block_number = u64::try_from(state_diffs.len()).unwrap() - 1;
+    for block_number in 0..=last_block_number {
+        let result =
+            get_storage_value_at_block(&storage, BlockNumber(block_number), &address(2), &key(5))
+                .await;
+        if block_number + n_retained_roots >= last_block_number {
+            assert_eq!(result.unwrap(), value(70));
+        } else {
+            assert!(matches!(
+                result,
+                Err(StateReaderError::RootRegistry(
+                    RootRegistryError::MissingBlock(_)
+                ))
+            ));
+        }
+    }
+    let block_roots_prefix = b"block_roots:";
+    let n_recorded_blocks = storage
+        .into_inner()
+        .storage
+        .keys()
+        .filter(|StorageKey(key)| key.starts_with(block_roots_prefix))
+        .count();
+    assert_eq!(
+        u64::try_from(n_recorded_blocks).unwrap(),
+        (n_retained_roots + 1).min(last_block_number + 1)
+    );
+}
+
+#[tokio::test]
+async fn test_prune_block_roots_deletes_all_expired_blocks() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let mut storage = MapStorage::default();
+    let pruner = Pruner::new(0);
+    let state_diffs = [
+        initial_state_diff(),
+        update_state_diff(),
+        inverse_update_state_diff(),
+    ];
+    for (block_number, state_diff) in state_diffs.iter().enumerate() {
+        commit_and_record_block(
+            &mut storage,
+            BlockNumber(block_number.try_into().unwrap()),
+            state_diff,
+            &config,
+        )
+        .await
+        .unwrap();
+    }
+
+    // Pruning only after the last block deletes the roots of all the previous blocks.
+    assert_eq!(
+        pruner
+            .prune_block_roots(&mut storage, BlockNumber(2))
+            .await
+            .unwrap(),
+        2
+    );
+    for block_number in [0, 1] {
+        assert!(matches!(
+            get_block_roots(&storage, BlockNumber(block_number)).await,
+            Err(RootRegistryError::MissingBlock(_))
+        ));
+    }
+    assert!(get_block_roots(&storage, BlockNumber(2)).await.is_ok());
+    assert_eq!(
+        pruner
+            .prune_block_roots(&mut storage, BlockNumber(2))
+            .await
+            .unwrap(),
+        0
+    );
+}
@@ -3,4 +3,5 @@ pub mod felt;
 pub mod forest_errors;
 pub mod hash;
 pub mod patricia_merkle_tree;
+pub mod state_reader;
 pub mod storage;
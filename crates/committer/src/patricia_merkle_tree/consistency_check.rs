@@ -0,0 +1,180 @@
+use thiserror::Error;
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageValue};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{CompiledClassHash, FilledNode};
+use crate::patricia_merkle_tree::filled_tree::node_serde::{BINARY_BYTES, EDGE_BYTES};
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+use crate::patricia_merkle_tree::traversal::node_db_key;
+use crate::patricia_merkle_tree::types::{NodeIndex, TrieId};
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction, TreeHashFunctionImpl,
+};
+use crate::storage::errors::DeserializationError;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageResult, StorageValue};
+
+#[cfg(test)]
+#[path = "consistency_check_test.rs"]
+pub mod consistency_check_test;
+
+/// The kind of an inconsistency found at a node.
+#[derive(Debug, Error)]
+pub enum InconsistencyKind {
+    #[error("The node is missing from the storage (key {0:?}).")]
+    MissingKey(StorageKey),
+    #[error("The node hashes to {actual:?} rather than to {expected:?}.")]
+    HashMismatch {
+        expected: HashOutput,
+        actual: HashOutput,
+    },
+    #[error(
+        "The inner node is {0} bytes long, expected {BINARY_BYTES} (binary) or {EDGE_BYTES} \
+         (edge)."
+    )]
+    MalformedInnerNode(usize),
+    #[error("The edge cannot be decoded: {0}")]
+    MalformedEdge(DeserializationError),
+    #[error("The edge length {length} is not between 1 and the node height {height}.")]
+    InvalidEdgeLength { length: u8, height: u8 },
+    #[error("The leaf cannot be decoded: {0}")]
+    UndecodableLeaf(DeserializationError),
+}
+
+/// An inconsistency found at the node with the given index of the given trie. The index encodes the
+/// path from the root of the trie to the node.
+#[derive(Debug, Error)]
+#[error("{trie:?}, node index {:#x}: {kind}", index.0)]
+pub struct Inconsistency {
+    pub trie: TrieId,
+    pub index: NodeIndex,
+    pub kind: InconsistencyKind,
+}
+
+/// Walks the contracts trie and the classes trie with the given roots, and the storage tries of all
+/// the contracts in the contracts trie, and returns the inconsistencies found in the storage. The
+/// hash of every node is recomputed from its preimage; the subtree of a node that cannot be read,
+/// decoded or authenticated is not walked. Only failures to access the storage are returned as
+/// errors.
+pub async fn check_storage_consistency(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+) -> StorageResult<Vec<Inconsistency>> {
+    let mut inconsistencies = Vec::new();
+    let contract_states = check_trie::<ContractState>(
+        storage,
+        TrieId::ContractsTrie,
+        contracts_trie_root_hash,
+        &mut inconsistencies,
+    )
+    .await?;
+    check_trie::<CompiledClassHash>(
+        storage,
+        TrieId::ClassesTrie,
+        classes_trie_root_hash,
+        &mut inconsistencies,
+    )
+    .await?;
+    for (index, contract_state) in contract_states {
+        check_trie::<StarknetStorageValue>(
+            storage,
+            TrieId::StorageTrie(ContractAddress(index.to_leaf_felt())),
+            contract_state.storage_root_hash,
+            &mut inconsistencies,
+        )
+        .await?;
+    }
+    Ok(inconsistencies)
+}
+
+/// Walks the given trie, one storage access per frontier of nodes, and appends the inconsistencies
+/// found to the given vector. Returns the leaves that were read successfully.
+async fn check_trie<L: Leaf>(
+    storage: &impl AsyncStorage,
+    trie: TrieId,
+    root_hash: HashOutput,
+    inconsistencies: &mut Vec<Inconsistency>,
+) -> StorageResult<Vec<(NodeIndex, L)>>
+where
+    TreeHashFunctionImpl: TreeHashFunction<L>,
+{
+    let mut leaves = Vec::new();
+    if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+        return Ok(leaves);
+    }
+    let mut frontier = vec![(NodeIndex::ROOT, root_hash)];
+    while !frontier.is_empty() {
+        let db_keys: Vec<StorageKey> = frontier
+            .iter()
+            .map(|(index, hash)| node_db_key::<L>(*hash, index.is_leaf()))
+            .collect();
+        let values = storage.mget(&db_keys).await?;
+        let mut next_frontier = Vec::new();
+        for (((index, hash), value), db_key) in frontier.into_iter().zip(values).zip(db_keys) {
+            match check_node::<L>(index, hash, value, db_key) {
+                Ok(NodeData::Binary(BinaryData {
+                    left_hash,
+                    right_hash,
+                })) => {
+                    let [left_index, right_index] = index.get_children_indices();
+                    next_frontier.push((left_index, left_hash));
+                    next_frontier.push((right_index, right_hash));
+                }
+                Ok(NodeData::Edge(EdgeData {
+                    bottom_hash,
+                    path_to_bottom,
+                })) => next_frontier.push((
+                    path_to_bottom
+                        .bottom_index(index)
+                        .expect("The edge length is checked against the node height."),
+                    bottom_hash,
+                )),
+                Ok(NodeData::Leaf(leaf)) => leaves.push((index, leaf)),
+                Err(kind) => inconsistencies.push(Inconsistency { trie, index, kind }),
+            }
+        }
+        frontier = next_frontier;
+    }
+    Ok(leaves)
+}
+
+/// Decodes the given storage value of the node with the given index and hash, and verifies it
+/// hashes to the given hash.
+fn check_node<L: Leaf>(
+    index: NodeIndex,
+    hash: HashOutput,
+    value: Option<StorageValue>,
+    db_key: StorageKey,
+) -> Result<NodeData<L>, InconsistencyKind>
+where
+    TreeHashFunctionImpl: TreeHashFunction<L>,
+{
+    let value = value.ok_or(InconsistencyKind::MissingKey(db_key))?;
+    let is_leaf = index.is_leaf();
+    if !is_leaf && value.0.len() != BINARY_BYTES && value.0.len() != EDGE_BYTES {
+        return Err(InconsistencyKind::MalformedInnerNode(value.0.len()));
+    }
+    let node = FilledNode::<L>::deserialize(hash, &value, is_leaf).map_err(|error| {
+        if is_leaf {
+            InconsistencyKind::UndecodableLeaf(error)
+        } else {
+            InconsistencyKind::MalformedEdge(error)
+        }
+    })?;
+    if let NodeData::Edge(EdgeData { path_to_bottom, .. }) = &node.data {
+        let length = u8::from(path_to_bottom.length);
+        let height = NodeIndex::BITS - index.bit_length();
+        if length == 0 || length > height {
+            return Err(InconsistencyKind::InvalidEdgeLength { length, height });
+        }
+    }
+    let actual = TreeHashFunctionImpl::compute_node_hash(&node.data);
+    if actual != hash {
+        return Err(InconsistencyKind::HashMismatch {
+            expected: hash,
+            actual,
+        });
+    }
+    Ok(node.data)
+}
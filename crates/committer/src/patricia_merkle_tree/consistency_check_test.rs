@@ -0,0 +1,172 @@
+use crate::block_committer::input::StarknetStorageValue;
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::consistency_check::{
+    check_storage_consistency, Inconsistency, InconsistencyKind,
+};
+use crate::patricia_merkle_tree::filled_tree::node_serde::{BINARY_BYTES, EDGE_BYTES};
+use crate::patricia_merkle_tree::internal_test_utils::{address, create_committed_state, key};
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::traversal::node_db_key;
+use crate::patricia_merkle_tree::types::{NodeIndex, TrieId};
+use crate::state_reader::reader::get_contract_state;
+use crate::storage::errors::{DeserializationError, NodeKind};
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageValue};
+
+/// Checks the given storage, and returns its single inconsistency.
+async fn single_inconsistency(
+    storage: &MapStorage,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+) -> Inconsistency {
+    let mut inconsistencies =
+        check_storage_consistency(storage, contracts_trie_root_hash, classes_trie_root_hash)
+            .await
+            .unwrap();
+    assert_eq!(inconsistencies.len(), 1, "{inconsistencies:?}");
+    inconsistencies.pop().unwrap()
+}
+
+/// Replaces the value stored under the given key.
+async fn corrupt(storage: &mut MapStorage, key: &StorageKey, modify: impl FnOnce(&mut Vec<u8>)) {
+    let StorageValue(mut value) = storage.get(key).await.unwrap().unwrap();
+    modify(&mut value);
+    storage.set(key.clone(), StorageValue(value)).await.unwrap();
+}
+
+#[tokio::test]
+async fn test_consistent_storage() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    assert!(
+        check_storage_consistency(&storage, contracts_trie_root_hash, classes_trie_root_hash)
+            .await
+            .unwrap()
+            .is_empty()
+    );
+    assert!(check_storage_consistency(
+        &MapStorage::default(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE
+    )
+    .await
+    .unwrap()
+    .is_empty());
+}
+
+#[tokio::test]
+async fn test_missing_key() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    // The leaf holding the value of key 5 of contract 1.
+    let leaf_key = node_db_key::<StarknetStorageValue>(HashOutput(Felt::from(50_u128)), true);
+    storage.delete(&leaf_key).await.unwrap();
+
+    let inconsistency =
+        single_inconsistency(&storage, contracts_trie_root_hash, classes_trie_root_hash).await;
+    assert_eq!(inconsistency.trie, TrieId::StorageTrie(address(1)));
+    assert_eq!(
+        inconsistency.index,
+        NodeIndex::from_starknet_storage_key(&key(5))
+    );
+    assert!(matches!(inconsistency.kind, InconsistencyKind::MissingKey(key) if key == leaf_key));
+}
+
+#[tokio::test]
+async fn test_hash_mismatch() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let leaf_key = node_db_key::<StarknetStorageValue>(HashOutput(Felt::from(50_u128)), true);
+    corrupt(&mut storage, &leaf_key, |value| {
+        *value = Felt::from(51_u128).to_bytes_be().to_vec()
+    })
+    .await;
+
+    let inconsistency =
+        single_inconsistency(&storage, contracts_trie_root_hash, classes_trie_root_hash).await;
+    assert_eq!(inconsistency.trie, TrieId::StorageTrie(address(1)));
+    assert!(matches!(
+        inconsistency.kind,
+        InconsistencyKind::HashMismatch { expected, actual }
+            if expected == HashOutput(Felt::from(50_u128))
+                && actual == HashOutput(Felt::from(51_u128))
+    ));
+}
+
+#[tokio::test]
+async fn test_malformed_inner_node() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let root_key = node_db_key::<ContractState>(contracts_trie_root_hash, false);
+    corrupt(&mut storage, &root_key, |value| {
+        value.truncate(BINARY_BYTES - 1);
+    })
+    .await;
+
+    // The storage tries are not reachable from a malformed root.
+    let inconsistency =
+        single_inconsistency(&storage, contracts_trie_root_hash, classes_trie_root_hash).await;
+    assert_eq!(inconsistency.trie, TrieId::ContractsTrie);
+    assert_eq!(inconsistency.index, NodeIndex::ROOT);
+    assert!(matches!(
+        inconsistency.kind,
+        InconsistencyKind::MalformedInnerNode(length) if length == BINARY_BYTES - 1
+    ));
+}
+
+#[tokio::test]
+async fn test_malformed_edge() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    // The storage trie of contract 2 holds a single leaf, so its root is an edge.
+    let storage_root_hash = get_contract_state(&storage, contracts_trie_root_hash, &address(2))
+        .await
+        .unwrap()
+        .storage_root_hash;
+    let root_key = node_db_key::<StarknetStorageValue>(storage_root_hash, false);
+    corrupt(&mut storage, &root_key, |value| {
+        assert_eq!(value.len(), EDGE_BYTES);
+        value[EDGE_BYTES - 1] = u8::MAX;
+    })
+    .await;
+
+    let inconsistency =
+        single_inconsistency(&storage, contracts_trie_root_hash, classes_trie_root_hash).await;
+    assert_eq!(inconsistency.trie, TrieId::StorageTrie(address(2)));
+    assert_eq!(inconsistency.index, NodeIndex::ROOT);
+    assert!(matches!(
+        inconsistency.kind,
+        InconsistencyKind::MalformedEdge(DeserializationError::MalformedNode {
+            key,
+            node_kind: NodeKind::Edge,
+            source,
+        }) if key == root_key && matches!(*source, DeserializationError::EdgePathError(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_undecodable_leaf() {
+    let (mut storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let leaf_hash = storage
+        .storage
+        .keys()
+        .find_map(|StorageKey(key)| key.strip_prefix(b"contract_state:".as_slice()))
+        .map(|suffix| HashOutput(Felt::from_bytes_be_slice(suffix)))
+        .unwrap();
+    let leaf_key = node_db_key::<ContractState>(leaf_hash, true);
+    corrupt(&mut storage, &leaf_key, |value| {
+        *value = b"garbage".to_vec()
+    })
+    .await;
+
+    let inconsistency =
+        single_inconsistency(&storage, contracts_trie_root_hash, classes_trie_root_hash).await;
+    assert_eq!(inconsistency.trie, TrieId::ContractsTrie);
+    assert!(inconsistency.index.is_leaf());
+    assert!(matches!(
+        inconsistency.kind,
+        InconsistencyKind::UndecodableLeaf(_)
+    ));
+}
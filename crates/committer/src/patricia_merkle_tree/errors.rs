@@ -1,6 +1,9 @@
 use std::fmt::Debug;
 use thiserror::Error;
 
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::storage::errors::{DeserializationError, StorageError};
+
 #[derive(Debug, Error)]
 pub enum TypesError<T: Sized + Debug> {
     #[error("Failed to convert type {from:?} to {to}. Reason: {reason}.")]
@@ -9,4 +12,26 @@ pub enum TypesError<T: Sized + Debug> {
         to: &'static str,
         reason: &'static str,
     },
+    #[error("{value:?} is out of range, the maximal value is {max:?}.")]
+    OutOfRange { value: T, max: T },
+    #[error("{descendant:?} is not a descendant of {ancestor:?}.")]
+    NotADescendant { ancestor: T, descendant: T },
 }
+
+#[derive(Debug, Error)]
+pub enum TraversalError {
+    #[error(transparent)]
+    Deserialization(#[from] DeserializationError),
+    #[error(transparent)]
+    StorageRead(#[from] StorageError),
+    #[error("Cannot start the traversal at {0:?}, as it is not a leaf index.")]
+    NonLeafStart(NodeIndex),
+    #[error("The index {0:?} is not a leaf index.")]
+    NonLeafIndex(NodeIndex),
+    #[error("The node at index {0:?} cannot appear at that index.")]
+    InvalidNode(NodeIndex),
+    #[error("The storage read did not return the node at index {0:?}.")]
+    MissingFetchedNode(NodeIndex),
+}
+
+pub type TraversalResult<T> = Result<T, TraversalError>;
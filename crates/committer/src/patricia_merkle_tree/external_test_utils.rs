@@ -72,14 +72,65 @@ pub async fn tree_computation_flow(
     storage: &MapStorage,
     root_hash: HashOutput,
 ) -> StorageTrie {
-    let config = OriginalSkeletonStorageTrieConfig::new(&leaf_modifications, false);
+    let updated_skeleton = create_updated_skeleton(&leaf_modifications, storage, root_hash).await;
+    StorageTrie::create::<TreeHashFunctionImpl>(updated_skeleton.into(), leaf_modifications)
+        .await
+        .expect("Failed to create the filled tree")
+}
+
+/// The input of the filled tree computation, i.e., an updated skeleton and its leaf
+/// modifications, prepared ahead so that the hashing can be measured on its own.
+pub struct TreeHashingInput {
+    updated_skeleton: Arc<UpdatedSkeletonTreeImpl>,
+    leaf_modifications: Arc<LeafModifications<StarknetStorageValue>>,
+}
+
+pub async fn create_tree_hashing_input(
+    leaf_modifications: Arc<LeafModifications<StarknetStorageValue>>,
+    storage: &MapStorage,
+    root_hash: HashOutput,
+) -> TreeHashingInput {
+    let updated_skeleton = create_updated_skeleton(&leaf_modifications, storage, root_hash).await;
+    TreeHashingInput {
+        updated_skeleton: Arc::new(updated_skeleton),
+        leaf_modifications,
+    }
+}
+
+/// Computes the filled tree of the given input with a task per node.
+pub async fn recursive_tree_hashing(input: &TreeHashingInput) -> StorageTrie {
+    StorageTrie::create::<TreeHashFunctionImpl>(
+        Arc::clone(&input.updated_skeleton),
+        Arc::clone(&input.leaf_modifications),
+    )
+    .await
+    .expect("Failed to create the filled tree")
+}
+
+/// Computes the filled tree of the given input bottom-up, layer by layer.
+pub async fn layered_tree_hashing(input: &TreeHashingInput) -> StorageTrie {
+    StorageTrie::create_layer_by_layer::<TreeHashFunctionImpl>(
+        input.updated_skeleton.as_ref(),
+        Arc::clone(&input.leaf_modifications),
+    )
+    .await
+    .expect("Failed to create the filled tree")
+}
+
+pub(crate) async fn create_updated_skeleton(
+    leaf_modifications: &LeafModifications<StarknetStorageValue>,
+    storage: &MapStorage,
+    root_hash: HashOutput,
+) -> UpdatedSkeletonTreeImpl {
+    let config = OriginalSkeletonStorageTrieConfig::new(leaf_modifications, false, None);
     let mut sorted_leaf_indices: Vec<NodeIndex> = leaf_modifications.keys().copied().collect();
     let sorted_leaf_indices = SortedLeafIndices::new(&mut sorted_leaf_indices);
     let mut original_skeleton =
         OriginalSkeletonTreeImpl::create(storage, root_hash, sorted_leaf_indices, &config)
+            .await
             .expect("Failed to create the original skeleton tree");
 
-    let updated_skeleton: UpdatedSkeletonTreeImpl = UpdatedSkeletonTree::create(
+    UpdatedSkeletonTree::create(
         &mut original_skeleton,
         &leaf_modifications
             .iter()
@@ -94,11 +145,7 @@ pub async fn tree_computation_flow(
             })
             .collect(),
     )
-    .expect("Failed to create the updated skeleton tree");
-
-    StorageTrie::create::<TreeHashFunctionImpl>(updated_skeleton.into(), leaf_modifications)
-        .await
-        .expect("Failed to create the filled tree")
+    .expect("Failed to create the updated skeleton tree")
 }
 
 pub async fn single_tree_flow_test(
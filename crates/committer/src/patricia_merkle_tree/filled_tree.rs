@@ -1,5 +1,6 @@
 pub mod errors;
 pub mod forest;
+pub mod layered_tree;
 pub mod node;
 pub mod node_serde;
 pub mod tree;
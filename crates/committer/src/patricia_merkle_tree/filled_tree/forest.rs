@@ -1,42 +1,189 @@
-use crate::block_committer::input::{ContractAddress, StarknetStorageValue};
+use crate::block_committer::input::{Config, ContractAddress, StarknetStorageValue};
 use crate::forest_errors::{ForestError, ForestResult};
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::CompiledClassHash;
 use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, Nonce};
-use crate::patricia_merkle_tree::filled_tree::tree::FilledTree;
 use crate::patricia_merkle_tree::filled_tree::tree::{
     ClassesTrie, ContractsTrie, StorageTrie, StorageTrieMap,
 };
-use crate::patricia_merkle_tree::node_data::leaf::{ContractState, LeafModifications};
+use crate::patricia_merkle_tree::filled_tree::tree::{
+    FilledTree, FilledTreeImpl, FilledTreeResult,
+};
+use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf, LeafModifications};
 use crate::patricia_merkle_tree::types::NodeIndex;
-use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::ForestHashFunction;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    ForestHashFunction, TreeHashFunction, TreeHashFunctionImpl,
+};
 use crate::patricia_merkle_tree::updated_skeleton_tree::skeleton_forest::UpdatedSkeletonForest;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
-use crate::storage::storage_trait::Storage;
+use crate::state_reader::flat_index::{
+    flat_state_entries, flat_state_index_update, rewrite_flat_state_index,
+};
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageResult, StorageValue};
 
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use std::sync::Arc;
 use tokio::task::JoinSet;
 
+/// Describes how the filled tries of a forest are computed.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+pub(crate) enum HashingMode {
+    /// A task per modified node.
+    Recursive,
+    /// Bottom-up, layer by layer, on a thread pool.
+    Layered,
+    /// Same as [HashingMode::Layered], but only the root hashes are kept; the filled tries hold no
+    /// nodes, and serialize to nothing.
+    RootsOnly,
+}
+
+impl HashingMode {
+    pub(crate) fn from_config(config: &impl Config) -> Self {
+        match config.layered_hashing() {
+            true => Self::Layered,
+            false => Self::Recursive,
+        }
+    }
+}
+
 pub struct FilledForest {
     pub storage_tries: StorageTrieMap,
     pub contracts_trie: ContractsTrie,
     pub classes_trie: ClassesTrie,
+    /// The storage keys of the previous nodes which are superseded by the forest's new nodes.
+    /// Reported for inspection only: nodes are keyed by their hashes, so an orphaned node may
+    /// still be held by other positions (e.g., a storage leaf whose value is stored in another
+    /// slot too). Nodes are deleted safely through [crate::block_committer::prune::Pruner].
+    pub orphaned_node_keys: HashSet<StorageKey>,
+    /// The number of positions in the tries in which each previous node is replaced by the
+    /// forest's new nodes. Unlike the orphaned nodes, includes previous nodes which are still
+    /// reachable from other positions. Used to release the references counted by a
+    /// [crate::storage::ref_counted_storage::RefCountedStorage].
+    pub released_node_references: HashMap<StorageKey, u64>,
+    /// The entries of the flat state index (see [crate::state_reader::flat_index]) which are
+    /// written along with the forest's facts. Empty unless the index is enabled.
+    pub flat_state_entries: HashMap<StorageKey, StorageValue>,
+    /// The root of the contracts trie the forest was committed on, which the flat state index
+    /// must be at for the entries to apply.
+    pub previous_contracts_trie_root_hash: HashOutput,
 }
 
 impl FilledForest {
-    pub fn write_to_storage(&self, storage: &mut impl Storage) {
-        // Serialize all trees to one hash map.
-        let new_db_objects = self
-            .storage_tries
+    pub async fn write_to_storage(&self, storage: &mut impl AsyncStorage) -> StorageResult<()> {
+        // Store the new hash map
+        self.write_facts_to_storage(storage, self.serialize()).await
+    }
+
+    /// Writes the given facts of the forest (see [Self::serialize]) to the storage, along with the
+    /// flat state index entries, in a single write. If the index is not at the root the forest was
+    /// committed on, it is rewritten after the facts are written instead.
+    pub(crate) async fn write_facts_to_storage(
+        &self,
+        storage: &mut impl AsyncStorage,
+        mut facts: HashMap<StorageKey, StorageValue>,
+    ) -> StorageResult<()> {
+        let mut rewrite_flat_state_index_after_write = false;
+        if !self.flat_state_entries.is_empty() {
+            match flat_state_index_update(
+                storage,
+                self.previous_contracts_trie_root_hash,
+                self.get_contract_root_hash(),
+                &self.flat_state_entries,
+            )
+            .await?
+            {
+                Some(update) => facts.extend(update),
+                None => rewrite_flat_state_index_after_write = true,
+            }
+        }
+        storage
+            .mset_with_references(facts, &self.node_references())
+            .await?;
+        if rewrite_flat_state_index_after_write {
+            rewrite_flat_state_index(storage, self.get_contract_root_hash()).await?;
+        }
+        Ok(())
+    }
+
+    /// Serializes all trees to one hash map, holding the facts (nodes) of the forest.
+    pub fn serialize(&self) -> HashMap<StorageKey, StorageValue> {
+        self.storage_tries
             .values()
             .flat_map(|tree| tree.serialize().into_iter())
             .chain(self.contracts_trie.serialize())
             .chain(self.classes_trie.serialize())
+            .collect()
+    }
+
+    /// Returns the storage keys of the forest's new nodes.
+    pub fn node_keys(&self) -> HashSet<StorageKey> {
+        self.node_references().into_keys().collect()
+    }
+
+    /// Returns the number of positions in the tries which hold each of the forest's new nodes.
+    pub fn node_references(&self) -> HashMap<StorageKey, u64> {
+        let mut references = HashMap::new();
+        let nodes = self
+            .storage_tries
+            .values()
+            .flat_map(|tree| tree.get_all_nodes().values().map(|node| node.db_key()))
+            .chain(
+                self.contracts_trie
+                    .get_all_nodes()
+                    .values()
+                    .map(|node| node.db_key()),
+            )
+            .chain(
+                self.classes_trie
+                    .get_all_nodes()
+                    .values()
+                    .map(|node| node.db_key()),
+            );
+        for key in nodes {
+            *references.entry(key).or_insert(0) += 1;
+        }
+        references
+    }
+
+    /// Sets the previous nodes replaced by the forest, given their keys (one per position), and
+    /// the orphaned nodes among them: those which are not also new nodes (e.g., due to trivial
+    /// modifications).
+    pub(crate) fn set_previous_node_keys(&mut self, previous_node_keys: Vec<StorageKey>) {
+        let new_node_keys = self.node_keys();
+        let mut released_node_references = HashMap::new();
+        for key in previous_node_keys {
+            *released_node_references.entry(key).or_insert(0) += 1;
+        }
+        self.orphaned_node_keys = released_node_references
+            .keys()
+            .filter(|key| !new_node_keys.contains(key))
+            .cloned()
             .collect();
+        self.released_node_references = released_node_references;
+    }
 
-        // Store the new hash map
-        storage.mset(new_db_objects);
+    /// Sets the flat state index entries of the given storage modifications, and of the new states
+    /// of the modified contracts. Contracts whose leaves are deleted are indexed with the empty
+    /// state.
+    pub(crate) fn set_flat_state_entries(
+        &mut self,
+        storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+    ) {
+        let contracts_trie_nodes = self.contracts_trie.get_all_nodes();
+        let empty_contract_state = ContractState::default();
+        let contract_states = storage_updates.keys().map(|address| {
+            match contracts_trie_nodes
+                .get(&NodeIndex::from_contract_address(address))
+                .map(|node| &node.data)
+            {
+                Some(NodeData::Leaf(contract_state)) => (address, contract_state),
+                Some(NodeData::Binary(_) | NodeData::Edge(_)) | None => {
+                    (address, &empty_contract_state)
+                }
+            }
+        });
+        self.flat_state_entries = flat_state_entries(storage_updates, contract_states);
     }
 
     pub fn get_contract_root_hash(&self) -> HashOutput {
@@ -47,6 +194,15 @@ impl FilledForest {
         self.classes_trie.get_root_hash()
     }
 
+    /// Returns the global state root, which commits to both the contracts trie and the classes
+    /// trie.
+    pub fn get_global_root_hash(&self) -> HashOutput {
+        TreeHashFunctionImpl::compute_global_root_hash(
+            self.get_contract_root_hash(),
+            self.get_compiled_class_root_hash(),
+        )
+    }
+
     pub(crate) async fn create<TH: ForestHashFunction + 'static>(
         mut updated_forest: UpdatedSkeletonForest,
         storage_updates: HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
@@ -54,10 +210,12 @@ impl FilledForest {
         original_contracts_trie_leaves: &HashMap<NodeIndex, ContractState>,
         address_to_class_hash: &HashMap<ContractAddress, ClassHash>,
         address_to_nonce: &HashMap<ContractAddress, Nonce>,
+        hashing_mode: HashingMode,
     ) -> ForestResult<Self> {
-        let classes_trie_task = tokio::spawn(ClassesTrie::create::<TH>(
-            Arc::new(updated_forest.classes_trie),
-            Arc::new(classes_updates),
+        let classes_trie_task = tokio::spawn(Self::create_tree::<CompiledClassHash, TH>(
+            updated_forest.classes_trie,
+            classes_updates,
+            hashing_mode,
         ));
         let mut contracts_trie_modifications = HashMap::new();
         let mut filled_storage_tries = HashMap::new();
@@ -82,6 +240,7 @@ impl FilledForest {
                     .unwrap_or(&original_contract_state.class_hash)),
                 updated_storage_trie,
                 inner_updates,
+                hashing_mode,
             ));
         }
 
@@ -94,15 +253,20 @@ impl FilledForest {
             filled_storage_tries.insert(address, filled_storage_trie);
         }
 
-        let contracts_trie_task = tokio::spawn(ContractsTrie::create::<TH>(
-            Arc::new(updated_forest.contracts_trie),
-            Arc::new(contracts_trie_modifications),
+        let contracts_trie_task = tokio::spawn(Self::create_tree::<ContractState, TH>(
+            updated_forest.contracts_trie,
+            contracts_trie_modifications,
+            hashing_mode,
         ));
 
         Ok(Self {
             storage_tries: filled_storage_tries,
             contracts_trie: contracts_trie_task.await??,
             classes_trie: classes_trie_task.await??,
+            orphaned_node_keys: HashSet::new(),
+            released_node_references: HashMap::new(),
+            flat_state_entries: HashMap::new(),
+            previous_contracts_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
         })
     }
 
@@ -112,10 +276,14 @@ impl FilledForest {
         new_class_hash: ClassHash,
         updated_storage_trie: UpdatedSkeletonTreeImpl,
         inner_updates: LeafModifications<StarknetStorageValue>,
+        hashing_mode: HashingMode,
     ) -> ForestResult<(ContractAddress, ContractState, StorageTrie)> {
-        let filled_storage_trie =
-            StorageTrie::create::<TH>(Arc::new(updated_storage_trie), Arc::new(inner_updates))
-                .await?;
+        let filled_storage_trie = Self::create_tree::<StarknetStorageValue, TH>(
+            updated_storage_trie,
+            inner_updates,
+            hashing_mode,
+        )
+        .await?;
         let new_root_hash = filled_storage_trie.get_root_hash();
         Ok((
             contract_address,
@@ -127,4 +295,25 @@ impl FilledForest {
             filled_storage_trie,
         ))
     }
+
+    /// Computes a filled tree in the given mode.
+    async fn create_tree<L: Leaf + 'static, TH: TreeHashFunction<L> + 'static>(
+        updated_skeleton: UpdatedSkeletonTreeImpl,
+        leaf_modifications: LeafModifications<L>,
+        hashing_mode: HashingMode,
+    ) -> FilledTreeResult<FilledTreeImpl<L>, L> {
+        let leaf_modifications = Arc::new(leaf_modifications);
+        match hashing_mode {
+            HashingMode::Recursive => {
+                FilledTreeImpl::create::<TH>(Arc::new(updated_skeleton), leaf_modifications).await
+            }
+            HashingMode::Layered => {
+                FilledTreeImpl::create_layer_by_layer::<TH>(&updated_skeleton, leaf_modifications)
+                    .await
+            }
+            HashingMode::RootsOnly => {
+                FilledTreeImpl::create_root_only::<TH>(&updated_skeleton, leaf_modifications).await
+            }
+        }
+    }
 }
@@ -0,0 +1,183 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use rayon::prelude::*;
+
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::errors::FilledTreeError;
+use crate::patricia_merkle_tree::filled_tree::node::FilledNode;
+use crate::patricia_merkle_tree::filled_tree::tree::{FilledTreeImpl, FilledTreeResult};
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::{Leaf, LeafModifications};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::errors::UpdatedSkeletonTreeError;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunction;
+use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
+use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTree;
+
+#[cfg(test)]
+#[path = "layered_tree_test.rs"]
+pub mod layered_tree_test;
+
+impl<L: Leaf + 'static> FilledTreeImpl<L> {
+    /// Computes the filled tree bottom-up, layer by layer: all modified nodes of a layer are hashed
+    /// in parallel on the rayon thread pool, given the hashes of the deeper layers, which are read
+    /// without locking. The hashing runs on a blocking thread, so it does not stall the async
+    /// runtime. Produces the same tree as [crate::patricia_merkle_tree::filled_tree::tree::FilledTree::create],
+    /// without spawning a task per node.
+    pub(crate) async fn create_layer_by_layer<'a, TH: TreeHashFunction<L> + 'static>(
+        updated_skeleton: &impl UpdatedSkeletonTree<'a>,
+        leaf_modifications: Arc<LeafModifications<L>>,
+    ) -> FilledTreeResult<Self, L> {
+        Self::create_layer_by_layer_impl::<TH>(updated_skeleton, leaf_modifications, true).await
+    }
+
+    /// Same as [Self::create_layer_by_layer], but only computes the root hash: the modified nodes
+    /// are hashed and dropped, so the returned tree has no nodes.
+    pub(crate) async fn create_root_only<'a, TH: TreeHashFunction<L> + 'static>(
+        updated_skeleton: &impl UpdatedSkeletonTree<'a>,
+        leaf_modifications: Arc<LeafModifications<L>>,
+    ) -> FilledTreeResult<Self, L> {
+        Self::create_layer_by_layer_impl::<TH>(updated_skeleton, leaf_modifications, false).await
+    }
+
+    async fn create_layer_by_layer_impl<'a, TH: TreeHashFunction<L> + 'static>(
+        updated_skeleton: &impl UpdatedSkeletonTree<'a>,
+        leaf_modifications: Arc<LeafModifications<L>>,
+        retain_nodes: bool,
+    ) -> FilledTreeResult<Self, L> {
+        if leaf_modifications.is_empty() {
+            return Self::create_unmodified(updated_skeleton);
+        }
+
+        if updated_skeleton.is_empty() {
+            return Ok(Self::create_empty());
+        }
+
+        // Group the modified inner nodes by their depth. The hashes of unmodified subtrees are
+        // already known.
+        let mut layers: Vec<Vec<(NodeIndex, UpdatedSkeletonNode)>> =
+            vec![Vec::new(); usize::from(NodeIndex::BITS)];
+        let mut leaf_indices = Vec::new();
+        let mut hashes = HashMap::new();
+        for (index, node) in updated_skeleton.get_nodes() {
+            match node {
+                UpdatedSkeletonNode::UnmodifiedSubTree(hash) => {
+                    hashes.insert(index, hash);
+                }
+                UpdatedSkeletonNode::Leaf => leaf_indices.push(index),
+                UpdatedSkeletonNode::Binary | UpdatedSkeletonNode::Edge(_) => {
+                    layers[usize::from(index.bit_length() - 1)].push((index, node))
+                }
+            }
+        }
+
+        // Leaves are created before hashing, as their creation may be asynchronous.
+        let mut leaves = Vec::with_capacity(leaf_indices.len());
+        for index in leaf_indices {
+            let leaf_data = L::create(&index, Arc::clone(&leaf_modifications)).await?;
+            if leaf_data.is_empty() {
+                return Err(FilledTreeError::<L>::DeletedLeafInSkeleton(index));
+            }
+            leaves.push((index, NodeData::Leaf(leaf_data)));
+        }
+
+        tokio::task::spawn_blocking(move || {
+            Self::hash_layers::<TH>(leaves, layers, hashes, retain_nodes)
+        })
+        .await?
+    }
+
+    /// Hashes the given leaves, then the given layers of modified inner nodes from the deepest up,
+    /// and returns the resulting tree. Blocks until the hashing, done on the rayon thread pool, is
+    /// over.
+    fn hash_layers<TH: TreeHashFunction<L>>(
+        leaves: Vec<(NodeIndex, NodeData<L>)>,
+        layers: Vec<Vec<(NodeIndex, UpdatedSkeletonNode)>>,
+        mut hashes: HashMap<NodeIndex, HashOutput>,
+        retain_nodes: bool,
+    ) -> FilledTreeResult<Self, L> {
+        let mut tree_map = HashMap::new();
+        Self::hash_layer::<TH>(leaves, &mut hashes, retain_nodes.then_some(&mut tree_map));
+
+        for layer in layers.into_iter().rev() {
+            let layer_data = layer
+                .into_par_iter()
+                .map(|(index, node)| Ok((index, Self::get_inner_node_data(index, node, &hashes)?)))
+                .collect::<FilledTreeResult<Vec<_>, L>>()?;
+            Self::hash_layer::<TH>(
+                layer_data,
+                &mut hashes,
+                retain_nodes.then_some(&mut tree_map),
+            );
+        }
+
+        let root_hash = *hashes
+            .get(&NodeIndex::ROOT)
+            .ok_or(FilledTreeError::<L>::MissingRoot)?;
+        Ok(Self {
+            tree_map,
+            root_hash,
+        })
+    }
+
+    /// Returns the data of the given modified inner node, given the hashes of the deeper layers.
+    fn get_inner_node_data(
+        index: NodeIndex,
+        node: UpdatedSkeletonNode,
+        hashes: &HashMap<NodeIndex, HashOutput>,
+    ) -> FilledTreeResult<NodeData<L>, L> {
+        let get_hash = |index: NodeIndex| {
+            hashes
+                .get(&index)
+                .copied()
+                .ok_or(FilledTreeError::<L>::MissingNode(index))
+        };
+        match node {
+            UpdatedSkeletonNode::Binary => {
+                let [left_index, right_index] = index.get_children_indices();
+                Ok(NodeData::Binary(BinaryData {
+                    left_hash: get_hash(left_index)?,
+                    right_hash: get_hash(right_index)?,
+                }))
+            }
+            UpdatedSkeletonNode::Edge(path_to_bottom) => {
+                let bottom_index = NodeIndex::compute_bottom_index(index, &path_to_bottom)
+                    .map_err(UpdatedSkeletonTreeError::from)?;
+                Ok(NodeData::Edge(EdgeData {
+                    bottom_hash: get_hash(bottom_index)?,
+                    path_to_bottom,
+                }))
+            }
+            UpdatedSkeletonNode::Leaf | UpdatedSkeletonNode::UnmodifiedSubTree(_) => {
+                unreachable!("Only modified inner nodes are grouped into layers.")
+            }
+        }
+    }
+
+    /// Hashes the given nodes of a layer in parallel, and adds their hashes to the output. The
+    /// nodes themselves are added to the tree map, if given.
+    fn hash_layer<TH: TreeHashFunction<L>>(
+        layer_data: Vec<(NodeIndex, NodeData<L>)>,
+        hashes: &mut HashMap<NodeIndex, HashOutput>,
+        tree_map: Option<&mut HashMap<NodeIndex, FilledNode<L>>>,
+    ) {
+        let hashed_nodes: Vec<(NodeIndex, HashOutput, NodeData<L>)> = layer_data
+            .into_par_iter()
+            .map(|(index, data)| (index, TH::compute_node_hash(&data), data))
+            .collect();
+        match tree_map {
+            Some(tree_map) => {
+                for (index, hash, data) in hashed_nodes {
+                    hashes.insert(index, hash);
+                    tree_map.insert(index, FilledNode { hash, data });
+                }
+            }
+            None => hashes.extend(
+                hashed_nodes
+                    .into_iter()
+                    .map(|(index, hash, _)| (index, hash)),
+            ),
+        }
+    }
+}
@@ -0,0 +1,105 @@
+use std::sync::Arc;
+
+use ethnum::U256;
+use rstest::rstest;
+
+use crate::block_committer::input::StarknetStorageValue;
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::external_test_utils::{
+    create_updated_skeleton, tree_computation_flow,
+};
+use crate::patricia_merkle_tree::filled_tree::tree::{FilledTree, StorageTrie};
+use crate::patricia_merkle_tree::merkle_proof::proof::proof_test::create_storage_trie;
+use crate::patricia_merkle_tree::node_data::leaf::LeafModifications;
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
+use crate::storage::map_storage::MapStorage;
+
+fn leaf_modifications(modifications: &[(U256, u128)]) -> LeafModifications<StarknetStorageValue> {
+    modifications
+        .iter()
+        .map(|(key, value)| {
+            (
+                NodeIndex::FIRST_LEAF + NodeIndex::new(*key).unwrap(),
+                StarknetStorageValue(Felt::from(*value)),
+            )
+        })
+        .collect()
+}
+
+/// Same as [tree_computation_flow], but hashes the filled tree bottom-up, layer by layer.
+async fn layered_tree_computation_flow(
+    leaf_modifications: Arc<LeafModifications<StarknetStorageValue>>,
+    storage: &MapStorage,
+    root_hash: HashOutput,
+) -> StorageTrie {
+    let updated_skeleton = create_updated_skeleton(&leaf_modifications, storage, root_hash).await;
+    StorageTrie::create_layer_by_layer::<TreeHashFunctionImpl>(
+        &updated_skeleton,
+        leaf_modifications,
+    )
+    .await
+    .unwrap()
+}
+
+#[rstest]
+#[case::single_leaf(&[(U256::ZERO, 1)])]
+#[case::siblings(&[(U256::ZERO, 1), (U256::ONE, 2)])]
+#[case::far_apart(&[(U256::ZERO, 1), (U256::ONE << 250, 2), (U256::from(0xDEADBEEF_u128), 3)])]
+#[case::many(&(0..100_u128).map(|i| (U256::from(i * 7919), i + 1)).collect::<Vec<_>>())]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_layered_tree_on_empty_tree(#[case] modifications: &[(U256, u128)]) {
+    let leaf_modifications = Arc::new(leaf_modifications(modifications));
+    let storage = MapStorage::default();
+    let expected = tree_computation_flow(
+        Arc::clone(&leaf_modifications),
+        &storage,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )
+    .await;
+    let actual =
+        layered_tree_computation_flow(leaf_modifications, &storage, HashOutput::ROOT_OF_EMPTY_TREE)
+            .await;
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::no_modifications(&[])]
+#[case::update(&[(U256::ONE, 5)])]
+#[case::insert_and_update(&[(U256::from(2_u128), 5), (U256::ONE << 250, 6)])]
+#[case::delete(&[(U256::ONE, 0)])]
+#[case::delete_and_insert(&[(U256::ZERO, 0), (U256::from(0xDEADBEEF_u128), 7)])]
+#[case::delete_all(&[(U256::ZERO, 0), (U256::ONE, 0), (U256::ONE << 250, 0)])]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_layered_tree_on_existing_tree(#[case] modifications: &[(U256, u128)]) {
+    let (storage, root_hash) =
+        create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2), (U256::ONE << 250, 3)]).await;
+    let leaf_modifications = Arc::new(leaf_modifications(modifications));
+    let expected =
+        tree_computation_flow(Arc::clone(&leaf_modifications), &storage, root_hash).await;
+    let actual = layered_tree_computation_flow(leaf_modifications, &storage, root_hash).await;
+    assert_eq!(actual, expected);
+}
+
+#[rstest]
+#[case::no_modifications(&[])]
+#[case::insert_and_update(&[(U256::from(2_u128), 5), (U256::ONE << 250, 6)])]
+#[case::delete_all(&[(U256::ZERO, 0), (U256::ONE, 0), (U256::ONE << 250, 0)])]
+#[tokio::test(flavor = "multi_thread")]
+async fn test_root_only_tree(#[case] modifications: &[(U256, u128)]) {
+    let (storage, root_hash) =
+        create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2), (U256::ONE << 250, 3)]).await;
+    let leaf_modifications = Arc::new(leaf_modifications(modifications));
+    let expected =
+        tree_computation_flow(Arc::clone(&leaf_modifications), &storage, root_hash).await;
+    let updated_skeleton = create_updated_skeleton(&leaf_modifications, &storage, root_hash).await;
+    let actual = StorageTrie::create_root_only::<TreeHashFunctionImpl>(
+        &updated_skeleton,
+        leaf_modifications,
+    )
+    .await
+    .unwrap();
+    assert_eq!(actual.get_root_hash(), expected.get_root_hash());
+    assert!(actual.serialize().is_empty());
+}
@@ -8,7 +8,7 @@ use crate::patricia_merkle_tree::node_data::leaf::Leaf;
 
 // TODO(Nimrod, 1/6/2024): Use the ClassHash defined in starknet-types-core when available.
 
-#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, Hash)]
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq, PartialOrd, Ord, Hash)]
 pub struct ClassHash(pub Felt);
 
 impl_from_hex_for_felt_wrapper!(ClassHash);
@@ -20,8 +20,6 @@ impl_from_hex_for_felt_wrapper!(Nonce);
 #[derive(Clone, Copy, Debug, Default, Eq, PartialEq)]
 pub struct CompiledClassHash(pub Felt);
 
-impl_from_hex_for_felt_wrapper!(CompiledClassHash);
-
 #[derive(Clone, Debug, PartialEq, Eq)]
 /// A node in a Patricia-Merkle tree which was modified during an update.
 pub struct FilledNode<L: Leaf> {
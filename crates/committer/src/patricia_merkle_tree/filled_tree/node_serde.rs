@@ -5,19 +5,23 @@ use crate::patricia_merkle_tree::node_data::inner_node::{
     BinaryData, EdgeData, EdgePathLength, NodeData, PathToBottom,
 };
 use crate::patricia_merkle_tree::node_data::leaf::Leaf;
+use crate::patricia_merkle_tree::traversal::node_db_key;
 use crate::storage::db_object::DBObject;
-use crate::storage::errors::DeserializationError;
+use crate::storage::errors::{DeserializationError, NodeKind};
 use crate::storage::storage_trait::{StarknetPrefix, StorageKey, StorageValue};
 use ethnum::U256;
 use serde::{Deserialize, Serialize};
 
+#[cfg(test)]
+#[path = "node_serde_test.rs"]
+pub mod node_serde_test;
+
 // Const describe the size of the serialized node.
 pub(crate) const SERIALIZE_HASH_BYTES: usize = 32;
 pub(crate) const BINARY_BYTES: usize = 2 * SERIALIZE_HASH_BYTES;
 pub(crate) const EDGE_LENGTH_BYTES: usize = 1;
 pub(crate) const EDGE_PATH_BYTES: usize = 32;
 pub(crate) const EDGE_BYTES: usize = SERIALIZE_HASH_BYTES + EDGE_PATH_BYTES + EDGE_LENGTH_BYTES;
-#[allow(dead_code)]
 pub(crate) const STORAGE_LEAF_SIZE: usize = SERIALIZE_HASH_BYTES;
 
 /// Temporary struct to serialize the leaf CompiledClass.
@@ -87,21 +91,37 @@ impl<L: Leaf> DBObject for FilledNode<L> {
 }
 
 impl<L: Leaf> FilledNode<L> {
-    /// Deserializes filled nodes.
+    /// Deserializes filled nodes. Failures carry the storage key of the node.
     pub(crate) fn deserialize(
         node_hash: HashOutput,
         value: &StorageValue,
         is_leaf: bool,
     ) -> Result<Self, DeserializationError> {
+        let key = || node_db_key::<L>(node_hash, is_leaf);
         if is_leaf {
+            let leaf = L::deserialize(value).map_err(|error| match error {
+                DeserializationError::UnexpectedValueSize { expected, actual } => {
+                    DeserializationError::UnexpectedNodeSize {
+                        key: key(),
+                        node_kind: NodeKind::Leaf,
+                        expected: vec![expected],
+                        actual,
+                    }
+                }
+                error => DeserializationError::MalformedNode {
+                    key: key(),
+                    node_kind: NodeKind::Leaf,
+                    source: Box::new(error),
+                },
+            })?;
             return Ok(Self {
                 hash: node_hash,
-                data: NodeData::Leaf(L::deserialize(value)?),
+                data: NodeData::Leaf(leaf),
             });
         }
 
         if value.0.len() == BINARY_BYTES {
-            Ok(Self {
+            return Ok(Self {
                 hash: node_hash,
                 data: NodeData::Binary(BinaryData {
                     left_hash: HashOutput(Felt::from_bytes_be_slice(
@@ -111,33 +131,38 @@ impl<L: Leaf> FilledNode<L> {
                         &value.0[SERIALIZE_HASH_BYTES..],
                     )),
                 }),
-            })
-        } else {
-            assert_eq!(
-                value.0.len(),
-                EDGE_BYTES,
-                "Unexpected inner node storage value length {}, expected to be {} or {}.",
-                value.0.len(),
-                EDGE_BYTES,
-                BINARY_BYTES
-            );
-            Ok(Self {
-                hash: node_hash,
-                data: NodeData::Edge(EdgeData {
-                    bottom_hash: HashOutput(Felt::from_bytes_be_slice(
-                        &value.0[..SERIALIZE_HASH_BYTES],
-                    )),
-                    path_to_bottom: PathToBottom::new(
-                        U256::from_be_bytes(
-                            value.0[SERIALIZE_HASH_BYTES..SERIALIZE_HASH_BYTES + EDGE_PATH_BYTES]
-                                .try_into()
-                                .expect("Slice with incorrect length."),
-                        )
-                        .into(),
-                        EdgePathLength::new(value.0[EDGE_BYTES - 1])?,
-                    )?,
-                }),
-            })
+            });
         }
+        let unexpected_size = || DeserializationError::UnexpectedNodeSize {
+            key: key(),
+            node_kind: NodeKind::Inner,
+            expected: vec![BINARY_BYTES, EDGE_BYTES],
+            actual: value.0.len(),
+        };
+        if value.0.len() != EDGE_BYTES {
+            return Err(unexpected_size());
+        }
+        let path: [u8; EDGE_PATH_BYTES] = value.0
+            [SERIALIZE_HASH_BYTES..SERIALIZE_HASH_BYTES + EDGE_PATH_BYTES]
+            .try_into()
+            .map_err(|_| unexpected_size())?;
+        let malformed_edge = |error: DeserializationError| DeserializationError::MalformedNode {
+            key: key(),
+            node_kind: NodeKind::Edge,
+            source: Box::new(error),
+        };
+        let length = EdgePathLength::new(value.0[EDGE_BYTES - 1])
+            .map_err(|error| malformed_edge(error.into()))?;
+        let path_to_bottom = PathToBottom::new(U256::from_be_bytes(path).into(), length)
+            .map_err(|error| malformed_edge(error.into()))?;
+        Ok(Self {
+            hash: node_hash,
+            data: NodeData::Edge(EdgeData {
+                bottom_hash: HashOutput(Felt::from_bytes_be_slice(
+                    &value.0[..SERIALIZE_HASH_BYTES],
+                )),
+                path_to_bottom,
+            }),
+        })
     }
 }
@@ -0,0 +1,68 @@
+use rstest::rstest;
+
+use crate::block_committer::input::StarknetStorageValue;
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::FilledNode;
+use crate::patricia_merkle_tree::filled_tree::node_serde::{
+    BINARY_BYTES, EDGE_BYTES, STORAGE_LEAF_SIZE,
+};
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::traversal::node_db_key;
+use crate::storage::errors::{DeserializationError, NodeKind};
+use crate::storage::storage_trait::StorageValue;
+
+const NODE_HASH: HashOutput = HashOutput(Felt::ONE);
+
+#[rstest]
+#[case::short_inner_node(vec![0; BINARY_BYTES - 1], false, NodeKind::Inner, vec![BINARY_BYTES, EDGE_BYTES])]
+#[case::long_inner_node(vec![0; EDGE_BYTES + 1], false, NodeKind::Inner, vec![BINARY_BYTES, EDGE_BYTES])]
+#[case::long_storage_leaf(vec![0; STORAGE_LEAF_SIZE + 1], true, NodeKind::Leaf, vec![STORAGE_LEAF_SIZE])]
+fn test_unexpected_node_size(
+    #[case] value: Vec<u8>,
+    #[case] is_leaf: bool,
+    #[case] expected_node_kind: NodeKind,
+    #[case] expected_sizes: Vec<usize>,
+) {
+    let actual_size = value.len();
+    let result =
+        FilledNode::<StarknetStorageValue>::deserialize(NODE_HASH, &StorageValue(value), is_leaf);
+    assert!(matches!(
+        result,
+        Err(DeserializationError::UnexpectedNodeSize { key, node_kind, expected, actual })
+            if key == node_db_key::<StarknetStorageValue>(NODE_HASH, is_leaf)
+                && node_kind == expected_node_kind
+                && expected == expected_sizes
+                && actual == actual_size
+    ));
+}
+
+#[rstest]
+// The length byte exceeds the maximal edge length.
+#[case::illegal_length(u8::MAX, 0)]
+// The path is longer than the length.
+#[case::mismatched_length(1, 2)]
+fn test_malformed_edge(#[case] length: u8, #[case] path: u8) {
+    let mut value = vec![0; EDGE_BYTES];
+    value[EDGE_BYTES - 2] = path;
+    value[EDGE_BYTES - 1] = length;
+    let result =
+        FilledNode::<StarknetStorageValue>::deserialize(NODE_HASH, &StorageValue(value), false);
+    assert!(matches!(
+        result,
+        Err(DeserializationError::MalformedNode { key, node_kind: NodeKind::Edge, .. })
+            if key == node_db_key::<StarknetStorageValue>(NODE_HASH, false)
+    ));
+}
+
+#[test]
+fn test_undecodable_leaf() {
+    let result =
+        FilledNode::<ContractState>::deserialize(NODE_HASH, &StorageValue(b"{}".to_vec()), true);
+    assert!(matches!(
+        result,
+        Err(DeserializationError::MalformedNode { key, node_kind: NodeKind::Leaf, source })
+            if key == node_db_key::<ContractState>(NODE_HASH, true)
+                && matches!(*source, DeserializationError::NonExistingKey(_))
+    ));
+}
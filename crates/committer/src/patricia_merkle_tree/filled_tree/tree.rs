@@ -17,6 +17,7 @@ use crate::patricia_merkle_tree::node_data::leaf::ContractState;
 use crate::patricia_merkle_tree::node_data::leaf::Leaf;
 use crate::patricia_merkle_tree::node_data::leaf::LeafModifications;
 use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::errors::UpdatedSkeletonTreeError;
 use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunction;
 use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTree;
@@ -162,7 +163,8 @@ impl<L: Leaf + 'static> FilledTreeImpl<L> {
                 Ok(hash_value)
             }
             UpdatedSkeletonNode::Edge(path_to_bottom) => {
-                let bottom_node_index = NodeIndex::compute_bottom_index(index, path_to_bottom);
+                let bottom_node_index = NodeIndex::compute_bottom_index(index, path_to_bottom)
+                    .map_err(UpdatedSkeletonTreeError::from)?;
                 let bottom_hash = Self::compute_filled_tree_rec::<TH>(
                     Arc::clone(&updated_skeleton),
                     bottom_node_index,
@@ -192,8 +194,8 @@ impl<L: Leaf + 'static> FilledTreeImpl<L> {
         }
     }
 
-    fn create_unmodified<'a>(
-        updated_skeleton: &Arc<impl UpdatedSkeletonTree<'a>>,
+    pub(crate) fn create_unmodified<'a>(
+        updated_skeleton: &impl UpdatedSkeletonTree<'a>,
     ) -> Result<Self, FilledTreeError<L>> {
         let root_node = updated_skeleton.get_node(NodeIndex::ROOT)?;
         let UpdatedSkeletonNode::UnmodifiedSubTree(root_hash) = root_node else {
@@ -205,7 +207,7 @@ impl<L: Leaf + 'static> FilledTreeImpl<L> {
         })
     }
 
-    fn create_empty() -> Self {
+    pub(crate) fn create_empty() -> Self {
         Self {
             tree_map: HashMap::new(),
             root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
@@ -222,7 +224,7 @@ impl<L: Leaf + 'static> FilledTree<L> for FilledTreeImpl<L> {
         //   1. Create a map containing the tree structure without hash values.
         //   2. Fill in the hash values.
         if leaf_modifications.is_empty() {
-            return Self::create_unmodified(&updated_skeleton);
+            return Self::create_unmodified(updated_skeleton.as_ref());
         }
 
         if updated_skeleton.is_empty() {
@@ -249,8 +251,8 @@ impl<L: Leaf + 'static> FilledTree<L> for FilledTreeImpl<L> {
         // This function iterates over each node in the tree, using the node's `db_key` as the hashmap key
         // and the result of the node's `serialize` method as the value.
         self.get_all_nodes()
-            .iter()
-            .map(|(_, node)| (node.db_key(), node.serialize()))
+            .values()
+            .map(|node| (node.db_key(), node.serialize()))
             .collect()
     }
 
@@ -10,7 +10,9 @@ use crate::patricia_merkle_tree::node_data::inner_node::{
     BinaryData, EdgeData, EdgePathLength, NodeData, PathToBottom,
 };
 use crate::patricia_merkle_tree::node_data::leaf::SkeletonLeaf;
-use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeImpl;
+use crate::patricia_merkle_tree::original_skeleton_tree::tree::{
+    OriginalSkeletonTree, OriginalSkeletonTreeImpl,
+};
 use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
 use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
 use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
@@ -184,14 +186,15 @@ async fn test_delete_leaf_from_empty_tree() {
 
     let mut indices = [NodeIndex::FIRST_LEAF];
     // Create an empty original skeleton tree with a single leaf modified.
-    let mut original_skeleton_tree = OriginalSkeletonTreeImpl::create_impl(
+    let mut original_skeleton_tree = OriginalSkeletonTreeImpl::create(
         &MapStorage {
             storage: HashMap::new(),
         },
         HashOutput::ROOT_OF_EMPTY_TREE,
         SortedLeafIndices::new(&mut indices),
-        &OriginalSkeletonMockTrieConfig::new(&storage_modifications, false),
+        &OriginalSkeletonMockTrieConfig::new(&storage_modifications, false, None),
     )
+    .await
     .unwrap();
 
     // Create an updated skeleton tree with a single leaf that is deleted.
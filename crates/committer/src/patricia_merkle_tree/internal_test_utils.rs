@@ -1,30 +1,44 @@
 use std::sync::Arc;
 
+use crate::block_committer::commit::commit_state_diff;
+use crate::block_committer::input::{
+    ConfigImpl, ContractAddress, StarknetStorageKey, StarknetStorageValue, StateDiff,
+};
 use crate::felt::Felt;
 use crate::generate_trie_config;
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::external_test_utils::get_random_u256;
+use crate::patricia_merkle_tree::filled_tree::node::{
+    ClassHash, CompiledClassHash, FilledNode, Nonce,
+};
 use crate::patricia_merkle_tree::filled_tree::tree::FilledTreeImpl;
 use crate::patricia_merkle_tree::node_data::errors::LeafResult;
 use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
 use crate::patricia_merkle_tree::node_data::inner_node::{EdgePathLength, PathToBottom};
 use crate::patricia_merkle_tree::node_data::leaf::SkeletonLeaf;
 use crate::patricia_merkle_tree::node_data::leaf::{Leaf, LeafModifications};
-use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeletonTreeConfig;
+use crate::patricia_merkle_tree::original_skeleton_tree::config::{
+    verify_node_hash, OriginalSkeletonTreeConfig,
+};
 use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
 use crate::patricia_merkle_tree::original_skeleton_tree::node::OriginalSkeletonNode;
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeResult;
-use crate::patricia_merkle_tree::types::{NodeIndex, SubTreeHeight};
+use crate::patricia_merkle_tree::types::{NodeIndex, SubTreeHeight, TrieId};
 use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
     HashFunction, TreeHashFunction, TreeHashFunctionImpl,
 };
 use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
+use crate::state_reader::diff::compute_state_diff;
 use crate::storage::db_object::{DBObject, Deserializable};
-use crate::storage::storage_trait::StorageValue;
+use crate::storage::file_storage::FileStorage;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{Storage, StorageKey, StorageValue};
 use ethnum::U256;
 use rand::rngs::ThreadRng;
 use rstest::{fixture, rstest};
+use std::collections::HashMap;
+use tempfile::TempDir;
 
 #[derive(Debug, PartialEq, Clone, Copy, Default, Eq)]
 pub(crate) struct MockLeaf(pub(crate) Felt);
@@ -126,7 +140,7 @@ impl NodeIndex {
 }
 
 pub(crate) fn small_tree_index_to_full(index: U256, height: SubTreeHeight) -> NodeIndex {
-    NodeIndex::from_subtree_index(NodeIndex::new(index), height)
+    NodeIndex::from_subtree_index(NodeIndex::new(index).unwrap(), height)
 }
 
 #[rstest]
@@ -174,3 +188,175 @@ pub(crate) fn as_fully_indexed(
         .map(|index| small_tree_index_to_full(index, SubTreeHeight::new(subtree_height)))
         .collect()
 }
+
+/// Creates a file storage in a new temporary directory, holding the given entries. The directory
+/// is deleted when the returned handle is dropped.
+pub(crate) fn create_file_storage(
+    entries: HashMap<StorageKey, StorageValue>,
+) -> (TempDir, FileStorage) {
+    let storage_dir = TempDir::new().unwrap();
+    let mut storage = FileStorage::create(storage_dir.path().join("storage")).unwrap();
+    storage.mset(entries);
+    storage.flush().unwrap();
+    (storage_dir, storage)
+}
+
+/// Commits the given state diff on top of the tries with the given roots, and writes the new facts
+/// to the storage. Returns the new roots of the contracts trie and the classes trie.
+pub(crate) async fn commit_to_storage(
+    storage: &mut MapStorage,
+    state_diff: &StateDiff,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+) -> (HashOutput, HashOutput) {
+    let filled_forest = commit_state_diff(
+        storage,
+        state_diff,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &ConfigImpl::new(false, log::LevelFilter::Debug),
+    )
+    .await
+    .unwrap();
+    filled_forest.write_to_storage(storage).await.unwrap();
+    (
+        filled_forest.get_contract_root_hash(),
+        filled_forest.get_compiled_class_root_hash(),
+    )
+}
+
+/// Returns the facts (nodes) reachable from the given roots, by committing the state they hold to an
+/// empty storage.
+pub(crate) async fn reachable_facts(
+    storage: &MapStorage,
+    contracts_trie_root_hash: HashOutput,
+    classes_trie_root_hash: HashOutput,
+) -> HashMap<StorageKey, StorageValue> {
+    let state_diff = compute_state_diff(
+        storage,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        contracts_trie_root_hash,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        classes_trie_root_hash,
+    )
+    .await
+    .unwrap();
+    let mut reachable_storage = MapStorage::default();
+    let roots = commit_to_storage(
+        &mut reachable_storage,
+        &state_diff,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )
+    .await;
+    assert_eq!(roots, (contracts_trie_root_hash, classes_trie_root_hash));
+    reachable_storage.storage
+}
+
+/// Returns the address of the test contract with the given number. The numbers are offset past the
+/// reserved addresses (see [crate::block_committer::input::RESERVED_CONTRACT_ADDRESSES]), so that
+/// every test contract may be deployed.
+pub(crate) fn address(number: u128) -> ContractAddress {
+    ContractAddress(Felt::from(number + 0x10))
+}
+
+pub(crate) fn key(value: u128) -> StarknetStorageKey {
+    StarknetStorageKey(Felt::from(value))
+}
+
+pub(crate) fn class_hash(value: u128) -> ClassHash {
+    ClassHash(Felt::from(value))
+}
+
+/// Returns a state diff with two contracts (with storage) and two classes.
+pub(crate) fn initial_state_diff() -> StateDiff {
+    StateDiff {
+        address_to_class_hash: HashMap::from([
+            (address(1), class_hash(10)),
+            (address(2), class_hash(11)),
+        ]),
+        address_to_nonce: HashMap::from([(address(1), Nonce(Felt::ONE))]),
+        class_hash_to_compiled_class_hash: HashMap::from([
+            (class_hash(10), CompiledClassHash(Felt::from(100_u128))),
+            (class_hash(11), CompiledClassHash(Felt::from(101_u128))),
+        ]),
+        storage_updates: HashMap::from([
+            (
+                address(1),
+                HashMap::from([
+                    (key(5), StarknetStorageValue(Felt::from(50_u128))),
+                    (key(6), StarknetStorageValue(Felt::from(60_u128))),
+                ]),
+            ),
+            (
+                address(2),
+                HashMap::from([(key(5), StarknetStorageValue(Felt::from(70_u128)))]),
+            ),
+        ]),
+    }
+}
+
+pub(crate) fn value(value: u128) -> StarknetStorageValue {
+    StarknetStorageValue(Felt::from(value))
+}
+
+/// A diff on top of the [initial_state_diff], in which every entry changes the state: it deploys a new
+/// contract, replaces the class of a contract and the compiled class of a class, bumps a nonce, and
+/// updates, adds and deletes storage values. It declares no new class, so its inverse is a valid
+/// state diff.
+pub(crate) fn update_state_diff() -> StateDiff {
+    StateDiff {
+        address_to_class_hash: HashMap::from([
+            (address(2), class_hash(10)),
+            (address(3), class_hash(10)),
+        ]),
+        address_to_nonce: HashMap::from([(address(1), Nonce(Felt::TWO))]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(11),
+            CompiledClassHash(Felt::from(111_u128)),
+        )]),
+        storage_updates: HashMap::from([
+            (
+                address(1),
+                HashMap::from([(key(5), value(0)), (key(7), value(80))]),
+            ),
+            (address(3), HashMap::from([(key(5), value(70))])),
+        ]),
+    }
+}
+
+/// The diff which reverts [update_state_diff] on top of the initial state.
+pub(crate) fn inverse_update_state_diff() -> StateDiff {
+    StateDiff {
+        address_to_class_hash: HashMap::from([
+            (address(2), class_hash(11)),
+            (address(3), class_hash(0)),
+        ]),
+        address_to_nonce: HashMap::from([(address(1), Nonce(Felt::ONE))]),
+        class_hash_to_compiled_class_hash: HashMap::from([(
+            class_hash(11),
+            CompiledClassHash(Felt::from(101_u128)),
+        )]),
+        storage_updates: HashMap::from([
+            (
+                address(1),
+                HashMap::from([(key(5), value(50)), (key(7), value(0))]),
+            ),
+            (address(3), HashMap::from([(key(5), value(0))])),
+        ]),
+    }
+}
+
+/// Commits the [initial_state_diff] to an empty storage. Returns the storage and the roots of the
+/// contracts trie and the classes trie.
+pub(crate) async fn create_committed_state() -> (MapStorage, HashOutput, HashOutput) {
+    let mut storage = MapStorage::default();
+    let (contracts_trie_root_hash, classes_trie_root_hash) = commit_to_storage(
+        &mut storage,
+        &initial_state_diff(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )
+    .await;
+    (storage, contracts_trie_root_hash, classes_trie_root_hash)
+}
@@ -0,0 +1,5 @@
+pub mod errors;
+pub mod forest_proof;
+pub mod multiproof;
+pub mod proof;
+pub mod verify;
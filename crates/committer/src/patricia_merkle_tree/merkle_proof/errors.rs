@@ -0,0 +1,33 @@
+use ethnum::U256;
+use thiserror::Error;
+
+use crate::block_committer::errors::RootRegistryError;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::{TraversalError, TypesError};
+use crate::patricia_merkle_tree::types::NodeIndex;
+
+#[derive(Debug, Error)]
+pub enum MerkleProofError {
+    #[error("Cannot prove the index {0:?}, as it is not a leaf index.")]
+    NonLeafIndex(NodeIndex),
+    #[error(transparent)]
+    Traversal(#[from] TraversalError),
+    #[error("Hash mismatch at index {index:?}: expected {expected:?}, computed {actual:?}.")]
+    HashMismatch {
+        index: NodeIndex,
+        expected: HashOutput,
+        actual: HashOutput,
+    },
+    #[error("The proof ends before reaching the leaf, at index {0:?}.")]
+    IncompleteProof(NodeIndex),
+    #[error("The proof has {0} redundant node(s) after its last node.")]
+    TrailingNodes(usize),
+    #[error("The proof node at index {0:?} cannot appear at that index.")]
+    InvalidNode(NodeIndex),
+    #[error(transparent)]
+    RootRegistry(#[from] RootRegistryError),
+    #[error(transparent)]
+    Types(#[from] TypesError<U256>),
+}
+
+pub type MerkleProofResult<T> = Result<T, MerkleProofError>;
@@ -0,0 +1,326 @@
+use std::collections::HashSet;
+
+use serde::Serialize;
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey, StarknetStorageValue};
+use crate::block_committer::root_registry::{get_block_roots, BlockNumber};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash};
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofResult;
+use crate::patricia_merkle_tree::merkle_proof::proof::{fetch_storage_proof, MerkleProof};
+use crate::patricia_merkle_tree::merkle_proof::verify::verify_proof;
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction, TreeHashFunctionImpl,
+};
+use crate::storage::storage_trait::AsyncStorage;
+
+#[cfg(test)]
+#[path = "forest_proof_test.rs"]
+pub mod forest_proof_test;
+
+/// Fetches from storage the Merkle proof of the given contract, in the contracts trie with the
+/// given root.
+pub async fn fetch_contract_proof(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+    address: &ContractAddress,
+) -> MerkleProofResult<MerkleProof<ContractState>> {
+    MerkleProof::fetch(
+        storage,
+        contracts_trie_root_hash,
+        NodeIndex::try_from_contract_address(address)?,
+    )
+    .await
+}
+
+/// Fetches from storage the Merkle proof of the given class, in the classes trie with the given
+/// root.
+pub async fn fetch_class_proof(
+    storage: &impl AsyncStorage,
+    classes_trie_root_hash: HashOutput,
+    class_hash: &ClassHash,
+) -> MerkleProofResult<MerkleProof<CompiledClassHash>> {
+    MerkleProof::fetch(
+        storage,
+        classes_trie_root_hash,
+        NodeIndex::try_from_class_hash(class_hash)?,
+    )
+    .await
+}
+
+/// A proof of a contract storage slot. Consists of the proof of the contract state in the
+/// contracts trie, and the proof of the slot in the storage trie of the contract (whose root is
+/// part of the contract state).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ContractStorageProof {
+    pub contract_proof: MerkleProof<ContractState>,
+    pub storage_proof: MerkleProof<StarknetStorageValue>,
+}
+
+impl ContractStorageProof {
+    /// Fetches from storage the proof of the given storage key of the given contract, given the
+    /// root of the contracts trie.
+    pub async fn fetch(
+        storage: &impl AsyncStorage,
+        contracts_trie_root_hash: HashOutput,
+        address: &ContractAddress,
+        key: &StarknetStorageKey,
+    ) -> MerkleProofResult<Self> {
+        let contract_proof =
+            fetch_contract_proof(storage, contracts_trie_root_hash, address).await?;
+        let storage_proof =
+            fetch_storage_proof(storage, storage_root_hash(contract_proof.leaf()), key).await?;
+        Ok(Self {
+            contract_proof,
+            storage_proof,
+        })
+    }
+
+    /// Fetches from storage the proof of the given storage key of the given contract as of the given
+    /// block (see [crate::block_committer::root_registry]).
+    pub async fn fetch_at_block(
+        storage: &impl AsyncStorage,
+        block_number: BlockNumber,
+        address: &ContractAddress,
+        key: &StarknetStorageKey,
+    ) -> MerkleProofResult<Self> {
+        let roots = get_block_roots(storage, block_number).await?;
+        Self::fetch(storage, roots.contracts_trie_root_hash, address, key).await
+    }
+
+    /// Verifies both layers of the proof against the given root of the contracts trie. Returns the
+    /// proven value, or None if the key is not in the storage trie of the contract.
+    pub fn verify(
+        &self,
+        contracts_trie_root_hash: HashOutput,
+        address: &ContractAddress,
+        key: &StarknetStorageKey,
+    ) -> MerkleProofResult<Option<StarknetStorageValue>> {
+        let contract_state = verify_proof::<ContractState, TreeHashFunctionImpl>(
+            contracts_trie_root_hash,
+            NodeIndex::try_from_contract_address(address)?,
+            &self.contract_proof.nodes,
+        )?;
+        verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+            storage_root_hash(contract_state.as_ref()),
+            NodeIndex::try_from_starknet_storage_key(key)?,
+            &self.storage_proof.nodes,
+        )
+    }
+}
+
+/// Proofs of classes, contracts and contract storage slots, over the classes trie and the contracts
+/// trie of the same state. Serializes to the JSON shape of the Starknet RPC `getStorageProof`
+/// result (without the `global_roots` field, which is up to the caller).
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct ForestProof {
+    pub classes_proofs: Vec<(ClassHash, MerkleProof<CompiledClassHash>)>,
+    /// The proofs of the requested contracts, followed by the proofs of the other contracts whose
+    /// storage is requested (which prove their storage roots).
+    pub contracts_proofs: Vec<(ContractAddress, MerkleProof<ContractState>)>,
+    /// The storage proofs of each contract, in the order of the request.
+    pub contracts_storage_proofs: Vec<(ContractAddress, StorageProofs)>,
+}
+
+pub type StorageProofs = Vec<(StarknetStorageKey, MerkleProof<StarknetStorageValue>)>;
+
+impl ForestProof {
+    /// Fetches from storage the proofs of the given classes, contracts and contract storage keys.
+    pub async fn fetch(
+        storage: &impl AsyncStorage,
+        contracts_trie_root_hash: HashOutput,
+        classes_trie_root_hash: HashOutput,
+        class_hashes: &[ClassHash],
+        contract_addresses: &[ContractAddress],
+        contracts_storage_keys: &[(ContractAddress, Vec<StarknetStorageKey>)],
+    ) -> MerkleProofResult<Self> {
+        let mut classes_proofs = Vec::new();
+        for class_hash in class_hashes {
+            let proof = fetch_class_proof(storage, classes_trie_root_hash, class_hash).await?;
+            classes_proofs.push((*class_hash, proof));
+        }
+
+        let mut contracts_proofs = Vec::new();
+        for address in contract_addresses {
+            let proof = fetch_contract_proof(storage, contracts_trie_root_hash, address).await?;
+            contracts_proofs.push((*address, proof));
+        }
+
+        let mut contracts_storage_proofs = Vec::new();
+        for (address, keys) in contracts_storage_keys {
+            // The storage root is proven by the contract proof, which is fetched unless it was
+            // already requested.
+            let contract_proof = match contracts_proofs
+                .iter()
+                .find(|(proven_address, _)| proven_address == address)
+            {
+                Some((_, contract_proof)) => contract_proof,
+                None => {
+                    let contract_proof =
+                        fetch_contract_proof(storage, contracts_trie_root_hash, address).await?;
+                    contracts_proofs.push((*address, contract_proof));
+                    &contracts_proofs[contracts_proofs.len() - 1].1
+                }
+            };
+            let storage_root_hash = storage_root_hash(contract_proof.leaf());
+            let mut storage_proofs = Vec::new();
+            for key in keys {
+                let proof = fetch_storage_proof(storage, storage_root_hash, key).await?;
+                storage_proofs.push((*key, proof));
+            }
+            contracts_storage_proofs.push((*address, storage_proofs));
+        }
+
+        Ok(Self {
+            classes_proofs,
+            contracts_proofs,
+            contracts_storage_proofs,
+        })
+    }
+
+    /// Fetches from storage the proofs of the given classes, contracts and contract storage keys, as
+    /// of the given block (see [crate::block_committer::root_registry]).
+    pub async fn fetch_at_block(
+        storage: &impl AsyncStorage,
+        block_number: BlockNumber,
+        class_hashes: &[ClassHash],
+        contract_addresses: &[ContractAddress],
+        contracts_storage_keys: &[(ContractAddress, Vec<StarknetStorageKey>)],
+    ) -> MerkleProofResult<Self> {
+        let roots = get_block_roots(storage, block_number).await?;
+        Self::fetch(
+            storage,
+            roots.contracts_trie_root_hash,
+            roots.classes_trie_root_hash,
+            class_hashes,
+            contract_addresses,
+            contracts_storage_keys,
+        )
+        .await
+    }
+}
+
+/// Returns the storage root of the given contract state; the root of the empty tree if the contract
+/// does not exist.
+fn storage_root_hash(contract_state: Option<&ContractState>) -> HashOutput {
+    contract_state.map_or(HashOutput::ROOT_OF_EMPTY_TREE, |contract_state| {
+        contract_state.storage_root_hash
+    })
+}
+
+#[derive(Serialize)]
+struct RpcNodeHashToNode {
+    node_hash: String,
+    node: RpcNode,
+}
+
+#[derive(Serialize)]
+#[serde(untagged)]
+enum RpcNode {
+    Binary {
+        left: String,
+        right: String,
+    },
+    Edge {
+        path: String,
+        length: u8,
+        child: String,
+    },
+}
+
+#[derive(Serialize)]
+struct RpcContractLeafData {
+    nonce: String,
+    class_hash: String,
+    storage_root: String,
+}
+
+#[derive(Serialize)]
+struct RpcContractsProof {
+    nodes: Vec<RpcNodeHashToNode>,
+    contract_leaves_data: Vec<RpcContractLeafData>,
+}
+
+#[derive(Serialize)]
+struct RpcStorageProof {
+    classes_proof: Vec<RpcNodeHashToNode>,
+    contracts_proof: RpcContractsProof,
+    contracts_storage_proofs: Vec<Vec<RpcNodeHashToNode>>,
+}
+
+/// Converts the inner nodes of the given proofs to the RPC node mapping, without duplicates. Leaves
+/// are omitted, as the RPC represents them by their hash only.
+fn to_rpc_nodes<'a, L: Leaf + 'a, TH: TreeHashFunction<L>>(
+    proofs: impl IntoIterator<Item = &'a MerkleProof<L>>,
+) -> Vec<RpcNodeHashToNode> {
+    let mut visited_hashes = HashSet::new();
+    let mut rpc_nodes = Vec::new();
+    for node in proofs.into_iter().flat_map(|proof| proof.nodes.iter()) {
+        let rpc_node = match node {
+            NodeData::Binary(BinaryData {
+                left_hash,
+                right_hash,
+            }) => RpcNode::Binary {
+                left: left_hash.0.to_hex(),
+                right: right_hash.0.to_hex(),
+            },
+            NodeData::Edge(EdgeData {
+                bottom_hash,
+                path_to_bottom,
+            }) => RpcNode::Edge {
+                path: format!("{:#x}", path_to_bottom.path.0),
+                length: path_to_bottom.length.into(),
+                child: bottom_hash.0.to_hex(),
+            },
+            NodeData::Leaf(_) => continue,
+        };
+        let node_hash = TH::compute_node_hash(node);
+        if visited_hashes.insert(node_hash) {
+            rpc_nodes.push(RpcNodeHashToNode {
+                node_hash: node_hash.0.to_hex(),
+                node: rpc_node,
+            });
+        }
+    }
+    rpc_nodes
+}
+
+impl Serialize for ForestProof {
+    fn serialize<S: serde::Serializer>(&self, serializer: S) -> Result<S::Ok, S::Error> {
+        RpcStorageProof {
+            classes_proof: to_rpc_nodes::<_, TreeHashFunctionImpl>(
+                self.classes_proofs.iter().map(|(_, proof)| proof),
+            ),
+            contracts_proof: RpcContractsProof {
+                nodes: to_rpc_nodes::<_, TreeHashFunctionImpl>(
+                    self.contracts_proofs.iter().map(|(_, proof)| proof),
+                ),
+                contract_leaves_data: self
+                    .contracts_proofs
+                    .iter()
+                    .map(|(_, proof)| {
+                        let contract_state = proof.leaf().cloned().unwrap_or_default();
+                        RpcContractLeafData {
+                            nonce: contract_state.nonce.0.to_hex(),
+                            class_hash: contract_state.class_hash.0.to_hex(),
+                            storage_root: contract_state.storage_root_hash.0.to_hex(),
+                        }
+                    })
+                    .collect(),
+            },
+            contracts_storage_proofs: self
+                .contracts_storage_proofs
+                .iter()
+                .map(|(_, storage_proofs)| {
+                    to_rpc_nodes::<_, TreeHashFunctionImpl>(
+                        storage_proofs.iter().map(|(_, proof)| proof),
+                    )
+                })
+                .collect(),
+        }
+        .serialize(serializer)
+    }
+}
@@ -0,0 +1,361 @@
+use ethnum::U256;
+use rstest::rstest;
+use serde_json::{json, Value};
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey, StarknetStorageValue};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TypesError;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, class_hash, create_committed_state, key,
+};
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use crate::patricia_merkle_tree::merkle_proof::forest_proof::{ContractStorageProof, ForestProof};
+use crate::patricia_merkle_tree::merkle_proof::proof::MerkleProof;
+use crate::patricia_merkle_tree::merkle_proof::verify::verify_proof;
+use crate::patricia_merkle_tree::node_data::inner_node::{
+    BinaryData, EdgeData, EdgePathLength, NodeData, PathToBottom,
+};
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
+use crate::storage::map_storage::MapStorage;
+
+#[rstest]
+#[case::existing_slot(1, 6, Some(60))]
+#[case::slot_of_other_contract(2, 5, Some(70))]
+#[case::missing_slot(2, 6, None)]
+#[case::missing_contract(3, 5, None)]
+#[tokio::test]
+async fn test_contract_storage_proof(
+    #[case] contract: u128,
+    #[case] storage_key: u128,
+    #[case] expected_value: Option<u128>,
+) {
+    let (storage, contracts_trie_root_hash, _) = create_committed_state().await;
+    let (address, key) = (address(contract), key(storage_key));
+    let proof = ContractStorageProof::fetch(&storage, contracts_trie_root_hash, &address, &key)
+        .await
+        .unwrap();
+    assert_eq!(
+        proof
+            .verify(contracts_trie_root_hash, &address, &key)
+            .unwrap(),
+        expected_value.map(|value| StarknetStorageValue(Felt::from(value)))
+    );
+    assert_eq!(proof.contract_proof.leaf().is_some(), contract != 3);
+}
+
+#[tokio::test]
+async fn test_forest_proof() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let proof = ForestProof::fetch(
+        &storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &[class_hash(10), class_hash(12)],
+        &[address(1), address(3)],
+        &[(address(1), vec![key(5), key(6), key(7)])],
+    )
+    .await
+    .unwrap();
+
+    let class_values: Vec<_> = proof
+        .classes_proofs
+        .iter()
+        .map(|(class_hash, class_proof)| {
+            verify_proof::<CompiledClassHash, TreeHashFunctionImpl>(
+                classes_trie_root_hash,
+                NodeIndex::from_class_hash(class_hash),
+                &class_proof.nodes,
+            )
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        class_values,
+        vec![Some(CompiledClassHash(Felt::from(100_u128))), None]
+    );
+
+    let contract_states: Vec<_> = proof
+        .contracts_proofs
+        .iter()
+        .map(|(address, contract_proof)| {
+            verify_proof::<ContractState, TreeHashFunctionImpl>(
+                contracts_trie_root_hash,
+                NodeIndex::from_contract_address(address),
+                &contract_proof.nodes,
+            )
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(contract_states[0].as_ref().unwrap().nonce, Nonce(Felt::ONE));
+    assert_eq!(contract_states[1], None);
+
+    let [(_, storage_proofs)] = &proof.contracts_storage_proofs[..] else {
+        panic!("Expected storage proofs of a single contract.");
+    };
+    let storage_root_hash = contract_states[0].as_ref().unwrap().storage_root_hash;
+    let storage_values: Vec<_> = storage_proofs
+        .iter()
+        .map(|(key, storage_proof)| {
+            verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+                storage_root_hash,
+                NodeIndex::from_starknet_storage_key(key),
+                &storage_proof.nodes,
+            )
+            .unwrap()
+        })
+        .collect();
+    assert_eq!(
+        storage_values,
+        vec![
+            Some(StarknetStorageValue(Felt::from(50_u128))),
+            Some(StarknetStorageValue(Felt::from(60_u128))),
+            None
+        ]
+    );
+}
+
+/// All the proofs of the empty forest are empty, and prove that nothing is in the tries.
+#[tokio::test]
+async fn test_forest_proof_of_empty_forest() {
+    let storage = MapStorage::default();
+    let proof = ForestProof::fetch(
+        &storage,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &[class_hash(10)],
+        &[address(1)],
+        &[(address(2), vec![key(5)])],
+    )
+    .await
+    .unwrap();
+    assert!(proof.classes_proofs[0].1.nodes.is_empty());
+    assert!(proof
+        .contracts_proofs
+        .iter()
+        .all(|(_, contract_proof)| contract_proof.nodes.is_empty()));
+    assert!(proof.contracts_storage_proofs[0].1[0].1.nodes.is_empty());
+
+    let contract_storage_proof = ContractStorageProof::fetch(
+        &storage,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &address(2),
+        &key(5),
+    )
+    .await
+    .unwrap();
+    assert_eq!(
+        contract_storage_proof
+            .verify(HashOutput::ROOT_OF_EMPTY_TREE, &address(2), &key(5))
+            .unwrap(),
+        None
+    );
+}
+
+/// Addresses and keys of at least 2^251 are not in the tries, and can neither be fetched nor
+/// verified.
+#[rstest]
+#[case::address(ContractAddress(Felt::MAX), key(5))]
+#[case::storage_key(address(1), StarknetStorageKey(Felt::MAX))]
+#[tokio::test]
+async fn test_contract_storage_proof_out_of_range(
+    #[case] address: ContractAddress,
+    #[case] key: StarknetStorageKey,
+) {
+    let (storage, contracts_trie_root_hash, _) = create_committed_state().await;
+    assert!(matches!(
+        ContractStorageProof::fetch(&storage, contracts_trie_root_hash, &address, &key).await,
+        Err(MerkleProofError::Types(TypesError::OutOfRange { .. }))
+    ));
+    let proof = ContractStorageProof {
+        contract_proof: MerkleProof { nodes: Vec::new() },
+        storage_proof: MerkleProof { nodes: Vec::new() },
+    };
+    assert!(matches!(
+        proof.verify(HashOutput::ROOT_OF_EMPTY_TREE, &address, &key),
+        Err(MerkleProofError::Types(TypesError::OutOfRange { .. }))
+    ));
+}
+
+#[tokio::test]
+async fn test_forest_proof_rpc_serialization() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let proof = ForestProof::fetch(
+        &storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &[class_hash(10), class_hash(11)],
+        &[address(1), address(3)],
+        &[(address(1), vec![key(5), key(6)])],
+    )
+    .await
+    .unwrap();
+    let json = serde_json::to_value(&proof).unwrap();
+
+    // Both classes share the root node, which should appear once.
+    let classes_proof = json["classes_proof"].as_array().unwrap();
+    assert_eq!(
+        classes_proof[0]["node_hash"],
+        Value::from(classes_trie_root_hash.0.to_hex())
+    );
+    assert_eq!(
+        classes_proof
+            .iter()
+            .filter(|node| node["node_hash"] == classes_proof[0]["node_hash"])
+            .count(),
+        1
+    );
+    for node in classes_proof
+        .iter()
+        .chain(json["contracts_proof"]["nodes"].as_array().unwrap())
+        .chain(json["contracts_storage_proofs"][0].as_array().unwrap())
+    {
+        let node = node["node"].as_object().unwrap();
+        let mut fields: Vec<_> = node.keys().map(String::as_str).collect();
+        fields.sort();
+        assert!(fields == ["left", "right"] || fields == ["child", "length", "path"]);
+    }
+
+    let contract_leaves_data = json["contracts_proof"]["contract_leaves_data"]
+        .as_array()
+        .unwrap();
+    assert_eq!(contract_leaves_data.len(), 2);
+    assert_eq!(contract_leaves_data[0]["nonce"], Value::from("0x1"));
+    assert_eq!(contract_leaves_data[0]["class_hash"], Value::from("0xa"));
+    assert_eq!(contract_leaves_data[1]["class_hash"], Value::from("0x0"));
+    assert_eq!(
+        json["contracts_storage_proofs"].as_array().unwrap().len(),
+        1
+    );
+}
+
+#[tokio::test]
+async fn test_forest_proof_of_storage_of_unrequested_contract() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let proof = ForestProof::fetch(
+        &storage,
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &[],
+        &[address(1)],
+        &[(address(2), vec![key(5)]), (address(1), vec![key(5)])],
+    )
+    .await
+    .unwrap();
+
+    // The storage root of the second contract is proven by an additional contract proof; the proof
+    // of the first contract is not repeated.
+    let proven_addresses: Vec<_> = proof
+        .contracts_proofs
+        .iter()
+        .map(|(address, _)| *address)
+        .collect();
+    assert_eq!(proven_addresses, vec![address(1), address(2)]);
+    let (_, contract_proof) = &proof.contracts_proofs[1];
+    let contract_state = verify_proof::<ContractState, TreeHashFunctionImpl>(
+        contracts_trie_root_hash,
+        NodeIndex::from_contract_address(&address(2)),
+        &contract_proof.nodes,
+    )
+    .unwrap()
+    .unwrap();
+    let (_, storage_proofs) = &proof.contracts_storage_proofs[0];
+    assert_eq!(
+        verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+            contract_state.storage_root_hash,
+            NodeIndex::from_starknet_storage_key(&key(5)),
+            &storage_proofs[0].1.nodes,
+        )
+        .unwrap(),
+        Some(StarknetStorageValue(Felt::from(70_u128)))
+    );
+}
+
+#[test]
+fn test_forest_proof_rpc_json() {
+    fn hash(value: u128) -> HashOutput {
+        HashOutput(Felt::from(value))
+    }
+    fn binary_node<L: Leaf>(left: u128, right: u128) -> NodeData<L> {
+        NodeData::Binary(BinaryData {
+            left_hash: hash(left),
+            right_hash: hash(right),
+        })
+    }
+    let contract_state = ContractState {
+        nonce: Nonce(Felt::from(7_u128)),
+        storage_root_hash: hash(9),
+        class_hash: ClassHash(Felt::from(8_u128)),
+    };
+    let proof = ForestProof {
+        classes_proofs: vec![(
+            class_hash(1),
+            MerkleProof {
+                nodes: vec![
+                    binary_node(1, 2),
+                    NodeData::Leaf(CompiledClassHash(Felt::from(3_u128))),
+                ],
+            },
+        )],
+        contracts_proofs: vec![(
+            address(5),
+            MerkleProof {
+                nodes: vec![
+                    NodeData::Edge(EdgeData {
+                        bottom_hash: hash(4),
+                        path_to_bottom: PathToBottom::new(
+                            U256::ONE.into(),
+                            EdgePathLength::new(2).unwrap(),
+                        )
+                        .unwrap(),
+                    }),
+                    NodeData::Leaf(contract_state),
+                ],
+            },
+        )],
+        contracts_storage_proofs: vec![(
+            address(5),
+            vec![(
+                key(1),
+                MerkleProof {
+                    nodes: vec![binary_node(1, 2)],
+                },
+            )],
+        )],
+    };
+
+    // The classes trie is hashed with Poseidon, and the other tries with Pedersen; edge hashes are
+    // the hash of the child and the path, plus the length.
+    let classes_binary_node_hash =
+        "0x5d44a3decb2b2e0cc71071f7b802f45dd792d064f0fc7316c46514f70f9891a";
+    let binary_node_hash = "0x5bb9440e27889a364bcb678b1f679ecd1347acdedcbf36e83494f857cc58026";
+    let edge_node_hash = "0x7c404440d435a820036debff190ec903f5d750c8f5ecbf6dfce404e7d65f2ae";
+    assert_eq!(
+        serde_json::to_value(&proof).unwrap(),
+        json!({
+            "classes_proof": [
+                {"node_hash": classes_binary_node_hash, "node": {"left": "0x1", "right": "0x2"}},
+            ],
+            "contracts_proof": {
+                "nodes": [
+                    {
+                        "node_hash": edge_node_hash,
+                        "node": {"path": "0x1", "length": 2, "child": "0x4"},
+                    },
+                ],
+                "contract_leaves_data": [
+                    {"nonce": "0x7", "class_hash": "0x8", "storage_root": "0x9"},
+                ],
+            },
+            "contracts_storage_proofs": [
+                [{"node_hash": binary_node_hash, "node": {"left": "0x1", "right": "0x2"}}],
+            ],
+        })
+    );
+}
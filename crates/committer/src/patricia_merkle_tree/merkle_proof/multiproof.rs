@@ -0,0 +1,107 @@
+use std::collections::HashMap;
+
+use crate::block_committer::input::{StarknetStorageKey, StarknetStorageValue};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::merkle_proof::errors::{MerkleProofError, MerkleProofResult};
+use crate::patricia_merkle_tree::merkle_proof::proof::{next_on_path, MerkleProof};
+use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
+use crate::patricia_merkle_tree::node_data::leaf::Leaf;
+use crate::patricia_merkle_tree::traversal::fetch_paths;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
+use crate::storage::storage_trait::AsyncStorage;
+
+#[cfg(test)]
+#[path = "multiproof_test.rs"]
+pub mod multiproof_test;
+
+/// A Merkle proof of multiple leaves in a Patricia-Merkle tree: the preimages of all nodes on the
+/// paths from the root towards the leaves, keyed by their hash. Nodes shared by several paths
+/// appear once.
+#[derive(Clone, Debug, Default, PartialEq, Eq)]
+pub struct MultiProof<L: Leaf> {
+    /// The binary and edge nodes.
+    pub inner_nodes: HashMap<HashOutput, NodeData<L>>,
+    /// The leaves. They are kept apart from the inner nodes, as the hash of a leaf may equal the
+    /// hash of an inner node (e.g., a storage value is its own hash).
+    pub leaves: HashMap<HashOutput, L>,
+}
+
+impl<L: Leaf> MultiProof<L> {
+    /// Fetches from storage the proof of the leaves at the given indices, in the tree with the
+    /// given root (see [fetch_paths]).
+    pub async fn fetch(
+        storage: &impl AsyncStorage,
+        root_hash: HashOutput,
+        leaf_indices: &[NodeIndex],
+    ) -> MerkleProofResult<Self> {
+        if let Some(index) = leaf_indices.iter().find(|index| !index.is_leaf()) {
+            return Err(MerkleProofError::NonLeafIndex(*index));
+        }
+        let mut multiproof = Self {
+            inner_nodes: HashMap::new(),
+            leaves: HashMap::new(),
+        };
+        let mut leaf_indices = leaf_indices.to_vec();
+        leaf_indices.sort();
+        leaf_indices.dedup();
+        fetch_paths::<L>(
+            storage,
+            root_hash,
+            SortedLeafIndices::new(&mut leaf_indices),
+            |_, node| match node.data {
+                NodeData::Leaf(leaf) => {
+                    multiproof.leaves.insert(node.hash, leaf);
+                }
+                data => {
+                    multiproof.inner_nodes.insert(node.hash, data);
+                }
+            },
+        )
+        .await?;
+        Ok(multiproof)
+    }
+
+    /// Extracts the proof of a single leaf, given the root of the tree. Fails if the proof of the
+    /// leaf is not contained in this proof.
+    pub fn get_proof(
+        &self,
+        root_hash: HashOutput,
+        leaf_index: NodeIndex,
+    ) -> MerkleProofResult<MerkleProof<L>> {
+        if !leaf_index.is_leaf() {
+            return Err(MerkleProofError::NonLeafIndex(leaf_index));
+        }
+        let mut nodes = Vec::new();
+        if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+            return Ok(MerkleProof { nodes });
+        }
+        let (mut index, mut hash) = (NodeIndex::ROOT, root_hash);
+        loop {
+            let node = if index.is_leaf() {
+                self.leaves.get(&hash).cloned().map(NodeData::Leaf)
+            } else {
+                self.inner_nodes.get(&hash).cloned()
+            }
+            .ok_or(MerkleProofError::IncompleteProof(index))?;
+            let next = next_on_path(&node, index, leaf_index);
+            nodes.push(node);
+            match next {
+                Some(next) => (index, hash) = next,
+                None => return Ok(MerkleProof { nodes }),
+            }
+        }
+    }
+}
+
+/// Fetches from storage the proof of the given keys, in the storage trie with the given root.
+pub async fn fetch_storage_multiproof(
+    storage: &impl AsyncStorage,
+    root_hash: HashOutput,
+    keys: &[StarknetStorageKey],
+) -> MerkleProofResult<MultiProof<StarknetStorageValue>> {
+    let leaf_indices = keys
+        .iter()
+        .map(NodeIndex::try_from_starknet_storage_key)
+        .collect::<Result<Vec<_>, _>>()?;
+    MultiProof::fetch(storage, root_hash, &leaf_indices).await
+}
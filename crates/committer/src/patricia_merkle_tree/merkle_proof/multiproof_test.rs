@@ -0,0 +1,156 @@
+use std::collections::{HashMap, HashSet};
+use std::sync::Arc;
+
+use ethnum::U256;
+use rstest::rstest;
+
+use crate::block_committer::input::{StarknetStorageKey, StarknetStorageValue};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::external_test_utils::tree_computation_flow;
+use crate::patricia_merkle_tree::filled_tree::tree::FilledTree;
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use crate::patricia_merkle_tree::merkle_proof::multiproof::{fetch_storage_multiproof, MultiProof};
+use crate::patricia_merkle_tree::merkle_proof::proof::fetch_storage_proof;
+use crate::patricia_merkle_tree::merkle_proof::proof::proof_test::create_storage_trie;
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, NodeData};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction, TreeHashFunctionImpl,
+};
+use crate::storage::map_storage::MapStorage;
+
+fn storage_key(key: U256) -> StarknetStorageKey {
+    StarknetStorageKey(Felt::from_bytes_be(&key.to_be_bytes()))
+}
+
+#[rstest]
+#[case::single_key(vec![U256::ZERO])]
+#[case::siblings(vec![U256::ZERO, U256::ONE])]
+#[case::present_and_absent(vec![U256::from(2_u128), U256::from(6_u128), U256::from(9_u128)])]
+#[case::far_apart(vec![U256::ONE, U256::ONE << 100, (U256::ONE << 250) + 3_u128])]
+#[case::duplicates(vec![U256::from(6_u128), U256::from(6_u128), U256::ZERO])]
+#[tokio::test]
+async fn test_multiproof_matches_single_proofs(#[case] keys: Vec<U256>) {
+    let (storage, root_hash) = create_storage_trie(&[
+        (U256::ZERO, 1),
+        (U256::ONE, 2),
+        (U256::from(6_u128), 3),
+        (U256::from(8_u128), 4),
+        (U256::ONE << 100, 5),
+    ])
+    .await;
+    let keys: Vec<StarknetStorageKey> = keys.into_iter().map(storage_key).collect();
+    let multiproof = fetch_storage_multiproof(&storage, root_hash, &keys)
+        .await
+        .unwrap();
+
+    let mut expected_hashes = HashSet::new();
+    for key in keys.iter() {
+        let proof = fetch_storage_proof(&storage, root_hash, key).await.unwrap();
+        assert_eq!(
+            multiproof
+                .get_proof(root_hash, NodeIndex::from_starknet_storage_key(key))
+                .unwrap(),
+            proof
+        );
+        expected_hashes.extend(
+            proof
+                .nodes
+                .iter()
+                .map(TreeHashFunctionImpl::compute_node_hash),
+        );
+    }
+    // Nodes shared by several paths are fetched once, and no other nodes are fetched.
+    assert_eq!(
+        multiproof
+            .inner_nodes
+            .keys()
+            .chain(multiproof.leaves.keys())
+            .copied()
+            .collect::<HashSet<_>>(),
+        expected_hashes
+    );
+}
+
+#[tokio::test]
+async fn test_multiproof_of_empty_tree() {
+    let multiproof = fetch_storage_multiproof(
+        &MapStorage::default(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &[storage_key(U256::ONE)],
+    )
+    .await
+    .unwrap();
+    assert!(multiproof.inner_nodes.is_empty() && multiproof.leaves.is_empty());
+    assert!(multiproof
+        .get_proof(HashOutput::ROOT_OF_EMPTY_TREE, NodeIndex::FIRST_LEAF + 1)
+        .unwrap()
+        .nodes
+        .is_empty());
+}
+
+#[tokio::test]
+async fn test_get_proof_of_unproven_leaf() {
+    let (storage, root_hash) = create_storage_trie(&[(U256::ZERO, 1), (U256::ONE << 200, 2)]).await;
+    let multiproof =
+        MultiProof::<StarknetStorageValue>::fetch(&storage, root_hash, &[NodeIndex::FIRST_LEAF])
+            .await
+            .unwrap();
+    assert!(matches!(
+        multiproof.get_proof(
+            root_hash,
+            NodeIndex::from_starknet_storage_key(&storage_key(U256::ONE << 200))
+        ),
+        Err(MerkleProofError::IncompleteProof(_))
+    ));
+}
+
+#[tokio::test]
+async fn test_multiproof_with_leaf_colliding_with_inner_node() {
+    // The leaves 0 and 1 are the children of a binary node, whose hash is also the value (and thus
+    // the hash) of the leaf 6.
+    let (value_0, value_1) = (Felt::ONE, Felt::TWO);
+    let binary_node_hash = TreeHashFunctionImpl::compute_node_hash(
+        &NodeData::<StarknetStorageValue>::Binary(BinaryData {
+            left_hash: HashOutput(value_0),
+            right_hash: HashOutput(value_1),
+        }),
+    );
+    let leaves: HashMap<NodeIndex, StarknetStorageValue> = [
+        (U256::ZERO, value_0),
+        (U256::ONE, value_1),
+        (U256::from(6_u128), binary_node_hash.0),
+    ]
+    .into_iter()
+    .map(|(key, value)| {
+        (
+            NodeIndex::FIRST_LEAF + NodeIndex::new(key).unwrap(),
+            StarknetStorageValue(value),
+        )
+    })
+    .collect();
+    let filled_tree = tree_computation_flow(
+        Arc::new(leaves),
+        &MapStorage::default(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )
+    .await;
+    let (storage, root_hash) = (
+        MapStorage::from(filled_tree.serialize()),
+        filled_tree.get_root_hash(),
+    );
+
+    let keys = [storage_key(U256::ZERO), storage_key(U256::from(6_u128))];
+    let multiproof = fetch_storage_multiproof(&storage, root_hash, &keys)
+        .await
+        .unwrap();
+    for key in keys.iter() {
+        assert_eq!(
+            multiproof
+                .get_proof(root_hash, NodeIndex::from_starknet_storage_key(key))
+                .unwrap(),
+            fetch_storage_proof(&storage, root_hash, key).await.unwrap()
+        );
+    }
+}
@@ -0,0 +1,105 @@
+use crate::block_committer::input::{StarknetStorageKey, StarknetStorageValue};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::merkle_proof::errors::{MerkleProofError, MerkleProofResult};
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::Leaf;
+use crate::patricia_merkle_tree::traversal::fetch_node;
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::storage::storage_trait::AsyncStorage;
+
+#[cfg(test)]
+#[path = "proof_test.rs"]
+pub mod proof_test;
+
+/// A Merkle proof of a single leaf in a Patricia-Merkle tree: the preimages of the nodes on the
+/// path from the root towards the leaf, ordered from the root down.
+/// If the leaf is in the tree, the path ends with the leaf itself. Otherwise, it ends with an edge
+/// node whose path diverges from the path to the leaf, or is empty if the tree is empty.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct MerkleProof<L: Leaf> {
+    pub nodes: Vec<NodeData<L>>,
+}
+
+impl<L: Leaf> MerkleProof<L> {
+    /// Returns the proven leaf, or None if this is a proof of non-inclusion.
+    pub fn leaf(&self) -> Option<&L> {
+        match self.nodes.last() {
+            Some(NodeData::Leaf(leaf)) => Some(leaf),
+            _ => None,
+        }
+    }
+
+    /// Fetches from storage the Merkle proof of the leaf at the given index, in the tree with the
+    /// given root.
+    pub async fn fetch(
+        storage: &impl AsyncStorage,
+        root_hash: HashOutput,
+        leaf_index: NodeIndex,
+    ) -> MerkleProofResult<Self> {
+        if !leaf_index.is_leaf() {
+            return Err(MerkleProofError::NonLeafIndex(leaf_index));
+        }
+        let mut nodes = Vec::new();
+        if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+            return Ok(Self { nodes });
+        }
+        let (mut index, mut hash) = (NodeIndex::ROOT, root_hash);
+        loop {
+            let node = fetch_node::<L>(storage, hash, index).await?;
+            let next = next_on_path(&node.data, index, leaf_index);
+            nodes.push(node.data);
+            match next {
+                Some(next) => (index, hash) = next,
+                None => return Ok(Self { nodes }),
+            }
+        }
+    }
+}
+
+/// Given a node on the path from the root towards the given leaf, located at the given index,
+/// returns the index and the hash of the next node on the path. Returns None if the path ends at
+/// the node, i.e., the node is the leaf or an edge node that diverges from the path.
+pub(crate) fn next_on_path<L: Leaf>(
+    node: &NodeData<L>,
+    index: NodeIndex,
+    leaf_index: NodeIndex,
+) -> Option<(NodeIndex, HashOutput)> {
+    match node {
+        NodeData::Binary(BinaryData {
+            left_hash,
+            right_hash,
+        }) => {
+            let [left_index, right_index] = index.get_children_indices();
+            if left_index.is_ancestor_of(&leaf_index) {
+                Some((left_index, *left_hash))
+            } else {
+                Some((right_index, *right_hash))
+            }
+        }
+        NodeData::Edge(EdgeData {
+            bottom_hash,
+            path_to_bottom,
+        }) => {
+            // An edge which descends below the leaves diverges from the path to any leaf.
+            let bottom_index = path_to_bottom.bottom_index(index).ok()?;
+            bottom_index
+                .is_ancestor_of(&leaf_index)
+                .then_some((bottom_index, *bottom_hash))
+        }
+        NodeData::Leaf(_) => None,
+    }
+}
+
+/// Fetches from storage the Merkle proof of the given key, in the storage trie with the given root.
+pub async fn fetch_storage_proof(
+    storage: &impl AsyncStorage,
+    root_hash: HashOutput,
+    key: &StarknetStorageKey,
+) -> MerkleProofResult<MerkleProof<StarknetStorageValue>> {
+    MerkleProof::fetch(
+        storage,
+        root_hash,
+        NodeIndex::try_from_starknet_storage_key(key)?,
+    )
+    .await
+}
@@ -0,0 +1,139 @@
+use std::collections::HashMap;
+use std::sync::Arc;
+
+use ethnum::U256;
+use rstest::rstest;
+
+use crate::block_committer::input::{StarknetStorageKey, StarknetStorageValue};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::external_test_utils::tree_computation_flow;
+use crate::patricia_merkle_tree::filled_tree::tree::FilledTree;
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use crate::patricia_merkle_tree::merkle_proof::proof::{fetch_storage_proof, MerkleProof};
+use crate::patricia_merkle_tree::node_data::inner_node::NodeDataDiscriminants;
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::storage::map_storage::MapStorage;
+
+/// Creates a storage trie with the given leaves (key, value) on top of an empty tree, and returns
+/// a storage holding it along with its root hash.
+pub(crate) async fn create_storage_trie(leaves: &[(U256, u128)]) -> (MapStorage, HashOutput) {
+    let leaf_modifications = leaves
+        .iter()
+        .map(|(key, value)| {
+            (
+                NodeIndex::FIRST_LEAF + NodeIndex::new(*key).unwrap(),
+                StarknetStorageValue(Felt::from(*value)),
+            )
+        })
+        .collect();
+    let filled_tree = tree_computation_flow(
+        Arc::new(leaf_modifications),
+        &MapStorage::default(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )
+    .await;
+    (
+        MapStorage::from(filled_tree.serialize()),
+        filled_tree.get_root_hash(),
+    )
+}
+
+/// The tree has the following structure:
+///                 binary
+///              /          \
+///     edge (l=249)     edge (l=250)
+///            |               |
+///         binary         leaf 2^250
+///        /      \
+///    leaf 0    leaf 1
+#[rstest]
+#[case::left_leaf(
+    U256::ZERO,
+    vec![
+        NodeDataDiscriminants::Binary,
+        NodeDataDiscriminants::Edge,
+        NodeDataDiscriminants::Binary,
+        NodeDataDiscriminants::Leaf,
+    ],
+    Some(1)
+)]
+#[case::right_leaf(
+    U256::ONE << 250,
+    vec![NodeDataDiscriminants::Binary, NodeDataDiscriminants::Edge, NodeDataDiscriminants::Leaf],
+    Some(3)
+)]
+#[case::diverges_from_left_edge(
+    U256::from(2_u128),
+    vec![NodeDataDiscriminants::Binary, NodeDataDiscriminants::Edge],
+    None
+)]
+#[case::diverges_from_right_edge(
+    (U256::ONE << 250) + 1_u128,
+    vec![NodeDataDiscriminants::Binary, NodeDataDiscriminants::Edge],
+    None
+)]
+#[tokio::test]
+async fn test_fetch_storage_proof(
+    #[case] key: U256,
+    #[case] expected_nodes: Vec<NodeDataDiscriminants>,
+    #[case] expected_value: Option<u128>,
+) {
+    let (storage, root_hash) =
+        create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2), (U256::ONE << 250, 3)]).await;
+    let proof = fetch_storage_proof(
+        &storage,
+        root_hash,
+        &StarknetStorageKey(Felt::from_bytes_be(&key.to_be_bytes())),
+    )
+    .await
+    .unwrap();
+    let actual_nodes: Vec<NodeDataDiscriminants> = proof
+        .nodes
+        .iter()
+        .map(NodeDataDiscriminants::from)
+        .collect();
+    assert_eq!(actual_nodes, expected_nodes);
+    assert_eq!(
+        proof.leaf(),
+        expected_value
+            .map(|value| StarknetStorageValue(Felt::from(value)))
+            .as_ref()
+    );
+}
+
+#[tokio::test]
+async fn test_fetch_proof_from_empty_tree() {
+    let proof = fetch_storage_proof(
+        &MapStorage::from(HashMap::new()),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &StarknetStorageKey(Felt::from(7_u128)),
+    )
+    .await
+    .unwrap();
+    assert!(proof.nodes.is_empty());
+    assert!(proof.leaf().is_none());
+}
+
+#[tokio::test]
+async fn test_fetch_proof_of_inner_node() {
+    let (storage, root_hash) = create_storage_trie(&[(U256::ZERO, 1)]).await;
+    for index in [NodeIndex::ROOT, NodeIndex::FIRST_LEAF >> 1] {
+        assert!(matches!(
+            MerkleProof::<StarknetStorageValue>::fetch(&storage, root_hash, index).await,
+            Err(MerkleProofError::NonLeafIndex(actual_index)) if actual_index == index
+        ));
+    }
+}
+
+#[tokio::test]
+async fn test_fetch_proof_with_missing_node() {
+    let (_, root_hash) = create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2)]).await;
+    assert!(fetch_storage_proof(
+        &MapStorage::default(),
+        root_hash,
+        &StarknetStorageKey(Felt::ZERO)
+    )
+    .await
+    .is_err());
+}
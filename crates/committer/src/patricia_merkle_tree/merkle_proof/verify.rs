@@ -0,0 +1,108 @@
+use crate::block_committer::input::{StarknetStorageKey, StarknetStorageValue};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::merkle_proof::errors::{MerkleProofError, MerkleProofResult};
+use crate::patricia_merkle_tree::merkle_proof::proof::MerkleProof;
+use crate::patricia_merkle_tree::node_data::inner_node::{BinaryData, EdgeData, NodeData};
+use crate::patricia_merkle_tree::node_data::leaf::Leaf;
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction, TreeHashFunctionImpl,
+};
+
+#[cfg(test)]
+#[path = "verify_test.rs"]
+pub mod verify_test;
+
+/// Verifies that the given node preimages, ordered from the root down, form a Merkle path from the
+/// given root towards the leaf at the given index (see [MerkleProof]). Returns the proven leaf, or
+/// None if the path proves the leaf is not in the tree.
+/// The hashes are recomputed using `TH`; no storage access is required.
+pub fn verify_proof<L: Leaf, TH: TreeHashFunction<L>>(
+    root_hash: HashOutput,
+    leaf_index: NodeIndex,
+    nodes: &[NodeData<L>],
+) -> MerkleProofResult<Option<L>> {
+    if !leaf_index.is_leaf() {
+        return Err(MerkleProofError::NonLeafIndex(leaf_index));
+    }
+    if nodes.is_empty() {
+        if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+            return Ok(None);
+        }
+        return Err(MerkleProofError::IncompleteProof(NodeIndex::ROOT));
+    }
+    let (mut index, mut expected_hash) = (NodeIndex::ROOT, root_hash);
+    for (position, node) in nodes.iter().enumerate() {
+        let actual_hash = TH::compute_node_hash(node);
+        if actual_hash != expected_hash {
+            return Err(MerkleProofError::HashMismatch {
+                index,
+                expected: expected_hash,
+                actual: actual_hash,
+            });
+        }
+        let n_trailing_nodes = nodes.len() - position - 1;
+        // The proven result, if the path ends at this node.
+        let result = match node {
+            NodeData::Binary(BinaryData {
+                left_hash,
+                right_hash,
+            }) => {
+                if index.is_leaf() {
+                    return Err(MerkleProofError::InvalidNode(index));
+                }
+                let [left_index, right_index] = index.get_children_indices();
+                (index, expected_hash) = if left_index.is_ancestor_of(&leaf_index) {
+                    (left_index, *left_hash)
+                } else {
+                    (right_index, *right_hash)
+                };
+                None
+            }
+            NodeData::Edge(EdgeData {
+                bottom_hash,
+                path_to_bottom,
+            }) => {
+                if index.is_leaf() || u8::from(path_to_bottom.length) == 0 {
+                    return Err(MerkleProofError::InvalidNode(index));
+                }
+                let bottom_index = path_to_bottom
+                    .bottom_index(index)
+                    .map_err(|_| MerkleProofError::InvalidNode(index))?;
+                if !bottom_index.is_ancestor_of(&leaf_index) {
+                    // The path diverges from the path to the leaf.
+                    Some(None)
+                } else {
+                    (index, expected_hash) = (bottom_index, *bottom_hash);
+                    None
+                }
+            }
+            NodeData::Leaf(leaf) => {
+                if !index.is_leaf() {
+                    return Err(MerkleProofError::InvalidNode(index));
+                }
+                Some(Some(leaf.clone()))
+            }
+        };
+        match result {
+            Some(result) if n_trailing_nodes == 0 => return Ok(result),
+            Some(_) => return Err(MerkleProofError::TrailingNodes(n_trailing_nodes)),
+            None => continue,
+        }
+    }
+    Err(MerkleProofError::IncompleteProof(index))
+}
+
+/// Verifies the given Merkle proof of the given key, against the storage trie with the given root.
+/// Returns the proven value, or None if the key is not in the trie.
+pub fn verify_storage_proof(
+    root_hash: HashOutput,
+    key: &StarknetStorageKey,
+    proof: &MerkleProof<StarknetStorageValue>,
+) -> MerkleProofResult<Option<StarknetStorageValue>> {
+    verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+        root_hash,
+        NodeIndex::try_from_starknet_storage_key(key)?,
+        &proof.nodes,
+    )
+}
@@ -0,0 +1,147 @@
+use ethnum::U256;
+use rstest::rstest;
+
+use crate::block_committer::input::{StarknetStorageKey, StarknetStorageValue};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::merkle_proof::errors::MerkleProofError;
+use crate::patricia_merkle_tree::merkle_proof::proof::fetch_storage_proof;
+use crate::patricia_merkle_tree::merkle_proof::proof::proof_test::create_storage_trie;
+use crate::patricia_merkle_tree::merkle_proof::verify::{verify_proof, verify_storage_proof};
+use crate::patricia_merkle_tree::node_data::inner_node::{
+    BinaryData, EdgeData, EdgePathLength, NodeData, PathToBottom,
+};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction, TreeHashFunctionImpl,
+};
+use crate::storage::map_storage::MapStorage;
+
+const LEAVES: [(u128, u128); 4] = [(0, 1), (1, 2), (6, 3), (1 << 100, 4)];
+
+fn storage_key(key: U256) -> StarknetStorageKey {
+    StarknetStorageKey(Felt::from_bytes_be(&key.to_be_bytes()))
+}
+
+async fn create_trie() -> (MapStorage, HashOutput) {
+    create_storage_trie(&LEAVES.map(|(key, value)| (U256::from(key), value))).await
+}
+
+#[rstest]
+#[case::leaf(U256::ZERO, Some(1))]
+#[case::sibling_leaf(U256::ONE, Some(2))]
+#[case::leaf_under_edge(U256::from(6_u128), Some(3))]
+#[case::far_leaf(U256::ONE << 100, Some(4))]
+#[case::absent_under_binary(U256::from(7_u128), None)]
+#[case::absent_under_edge(U256::from(2_u128), None)]
+#[case::absent_far(U256::ONE << 250, None)]
+#[tokio::test]
+async fn test_verify_fetched_proof(#[case] key: U256, #[case] expected_value: Option<u128>) {
+    let (storage, root_hash) = create_trie().await;
+    let key = storage_key(key);
+    let proof = fetch_storage_proof(&storage, root_hash, &key)
+        .await
+        .unwrap();
+    assert_eq!(
+        verify_storage_proof(root_hash, &key, &proof).unwrap(),
+        expected_value.map(|value| StarknetStorageValue(Felt::from(value)))
+    );
+}
+
+#[test]
+fn test_verify_empty_tree_proof() {
+    let key = storage_key(U256::from(5_u128));
+    assert_eq!(
+        verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+            HashOutput::ROOT_OF_EMPTY_TREE,
+            NodeIndex::from_starknet_storage_key(&key),
+            &[],
+        )
+        .unwrap(),
+        None
+    );
+    assert!(matches!(
+        verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+            HashOutput(Felt::ONE),
+            NodeIndex::from_starknet_storage_key(&key),
+            &[],
+        ),
+        Err(MerkleProofError::IncompleteProof(NodeIndex::ROOT))
+    ));
+}
+
+#[tokio::test]
+async fn test_verify_tampered_proof() {
+    let (storage, root_hash) = create_trie().await;
+    let key = storage_key(U256::ZERO);
+    let proof = fetch_storage_proof(&storage, root_hash, &key)
+        .await
+        .unwrap();
+
+    // Wrong root.
+    let wrong_root = HashOutput(root_hash.0 + Felt::ONE);
+    assert!(matches!(
+        verify_storage_proof(wrong_root, &key, &proof),
+        Err(MerkleProofError::HashMismatch { index: NodeIndex::ROOT, expected, .. })
+            if expected == wrong_root
+    ));
+
+    // Wrong leaf value.
+    let mut tampered_proof = proof.clone();
+    *tampered_proof.nodes.last_mut().unwrap() =
+        NodeData::Leaf(StarknetStorageValue(Felt::from(2_u128)));
+    assert!(matches!(
+        verify_storage_proof(root_hash, &key, &tampered_proof),
+        Err(MerkleProofError::HashMismatch { index, .. })
+            if index == NodeIndex::from_starknet_storage_key(&key)
+    ));
+
+    // Proof of a different key.
+    assert!(matches!(
+        verify_storage_proof(root_hash, &storage_key(U256::ONE), &proof),
+        Err(MerkleProofError::HashMismatch { .. })
+    ));
+
+    // Missing leaf.
+    let mut truncated_proof = proof.clone();
+    truncated_proof.nodes.pop();
+    assert!(matches!(
+        verify_storage_proof(root_hash, &key, &truncated_proof),
+        Err(MerkleProofError::IncompleteProof(index))
+            if index == NodeIndex::from_starknet_storage_key(&key)
+    ));
+
+    // Redundant node.
+    let mut extended_proof = proof.clone();
+    extended_proof.nodes.push(proof.nodes[0].clone());
+    assert!(matches!(
+        verify_storage_proof(root_hash, &key, &extended_proof),
+        Err(MerkleProofError::TrailingNodes(1))
+    ));
+}
+
+#[rstest]
+// An edge from the root to a leaf, which is too long for the child of the root.
+#[case::too_long_edge(EdgePathLength::MAX.into())]
+#[case::empty_edge(0)]
+fn test_verify_invalid_edge(#[case] length: u8) {
+    // The edge is the left child of the root.
+    let edge: NodeData<StarknetStorageValue> = NodeData::Edge(EdgeData {
+        bottom_hash: HashOutput(Felt::ONE),
+        path_to_bottom: PathToBottom::new(U256::ZERO.into(), EdgePathLength::new(length).unwrap())
+            .unwrap(),
+    });
+    let root = NodeData::Binary(BinaryData {
+        left_hash: TreeHashFunctionImpl::compute_node_hash(&edge),
+        right_hash: HashOutput(Felt::ONE),
+    });
+    let root_hash = TreeHashFunctionImpl::compute_node_hash(&root);
+    assert!(matches!(
+        verify_proof::<StarknetStorageValue, TreeHashFunctionImpl>(
+            root_hash,
+            NodeIndex::FIRST_LEAF,
+            &[root, edge],
+        ),
+        Err(MerkleProofError::InvalidNode(index)) if index == NodeIndex::from(2_u128)
+    ));
+}
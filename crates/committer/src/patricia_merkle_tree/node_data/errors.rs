@@ -1,7 +1,7 @@
 use std::fmt::Debug;
 use thiserror::Error;
 
-use crate::patricia_merkle_tree::node_data::inner_node::{EdgePath, EdgePathLength};
+use crate::patricia_merkle_tree::node_data::inner_node::{EdgePath, EdgePathLength, PathToBottom};
 use crate::patricia_merkle_tree::types::NodeIndex;
 
 #[derive(Debug, Error)]
@@ -16,6 +16,11 @@ pub enum PathToBottomError {
         path: EdgePath,
         length: EdgePathLength,
     },
+    #[error("The path {path_to_bottom:?} from index {root_index:?} descends below the leaves.")]
+    BottomOutOfRange {
+        root_index: NodeIndex,
+        path_to_bottom: PathToBottom,
+    },
 }
 
 #[derive(Debug, Error)]
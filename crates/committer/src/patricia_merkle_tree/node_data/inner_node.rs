@@ -105,7 +105,7 @@ pub struct PathToBottom {
     _fake_field: (),
 }
 
-type PathToBottomResult = Result<PathToBottom, PathToBottomError>;
+type PathToBottomResult<T = PathToBottom> = Result<T, PathToBottomError>;
 
 impl PathToBottom {
     /// Creates a new [PathToBottom] instance.
@@ -142,7 +142,9 @@ impl PathToBottom {
         _fake_field: (),
     };
 
-    pub(crate) fn bottom_index(&self, root_index: NodeIndex) -> NodeIndex {
+    /// Returns the index of the bottom of the path from the given index, or an error if the path
+    /// descends below the leaves.
+    pub(crate) fn bottom_index(&self, root_index: NodeIndex) -> PathToBottomResult<NodeIndex> {
         NodeIndex::compute_bottom_index(root_index, self)
     }
 
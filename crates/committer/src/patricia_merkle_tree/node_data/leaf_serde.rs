@@ -1,11 +1,12 @@
-use std::collections::HashMap;
-
 use serde_json::Value;
 
 use crate::block_committer::input::StarknetStorageValue;
 use crate::felt::Felt;
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::filled_tree::node_serde::{
+    LeafCompiledClassToSerialize, STORAGE_LEAF_SIZE,
+};
 use crate::patricia_merkle_tree::node_data::leaf::ContractState;
 use crate::patricia_merkle_tree::types::SubTreeHeight;
 use crate::storage::db_object::{DBObject, Deserializable};
@@ -59,6 +60,12 @@ impl DBObject for ContractState {
 
 impl Deserializable for StarknetStorageValue {
     fn deserialize(value: &StorageValue) -> Result<Self, DeserializationError> {
+        if value.0.len() != STORAGE_LEAF_SIZE {
+            return Err(DeserializationError::UnexpectedValueSize {
+                expected: STORAGE_LEAF_SIZE,
+                actual: value.0.len(),
+            });
+        }
         Ok(Self(Felt::from_bytes_be_slice(&value.0)))
     }
 
@@ -70,13 +77,8 @@ impl Deserializable for StarknetStorageValue {
 impl Deserializable for CompiledClassHash {
     fn deserialize(value: &StorageValue) -> Result<Self, DeserializationError> {
         let json_str = std::str::from_utf8(&value.0)?;
-        let map: HashMap<String, String> = serde_json::from_str(json_str)?;
-        let hash_as_hex =
-            map.get("compiled_class_hash")
-                .ok_or(DeserializationError::NonExistingKey(
-                    "compiled_class_hash".to_string(),
-                ))?;
-        Ok(Self::from_hex(hash_as_hex)?)
+        let leaf: LeafCompiledClassToSerialize = serde_json::from_str(json_str)?;
+        Ok(Self(leaf.compiled_class_hash))
     }
 
     fn prefix() -> Vec<u8> {
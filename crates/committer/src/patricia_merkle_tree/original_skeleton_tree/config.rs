@@ -1,9 +1,12 @@
 use crate::block_committer::input::StarknetStorageValue;
-use crate::patricia_merkle_tree::filled_tree::node::CompiledClassHash;
+use crate::patricia_merkle_tree::filled_tree::node::{CompiledClassHash, FilledNode};
 use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf, LeafModifications};
 use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeResult;
-use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::types::{NodeIndex, TrieId};
+use crate::patricia_merkle_tree::updated_skeleton_tree::hash_function::{
+    TreeHashFunction, TreeHashFunctionImpl,
+};
 
 /// Configures the creation of an original skeleton tree.
 pub(crate) trait OriginalSkeletonTreeConfig<L: Leaf> {
@@ -17,6 +20,59 @@ pub(crate) trait OriginalSkeletonTreeConfig<L: Leaf> {
         index: &NodeIndex,
         previous_leaf: &L,
     ) -> OriginalSkeletonTreeResult<bool>;
+
+    /// Verifies that the given node, read from storage at the given index, hashes to the hash it
+    /// is stored under. Does nothing unless verification on read is configured (see
+    /// [crate::block_committer::input::Config::verify_on_read]).
+    fn verify_node(
+        &self,
+        index: &NodeIndex,
+        node: &FilledNode<L>,
+    ) -> OriginalSkeletonTreeResult<()>;
+}
+
+/// A configuration may be borrowed, e.g., to create a single tree with
+/// [crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeImpl::create_many].
+impl<L: Leaf, C: OriginalSkeletonTreeConfig<L>> OriginalSkeletonTreeConfig<L> for &C {
+    fn compare_modified_leaves(&self) -> bool {
+        (*self).compare_modified_leaves()
+    }
+
+    fn compare_leaf(
+        &self,
+        index: &NodeIndex,
+        previous_leaf: &L,
+    ) -> OriginalSkeletonTreeResult<bool> {
+        (*self).compare_leaf(index, previous_leaf)
+    }
+
+    fn verify_node(
+        &self,
+        index: &NodeIndex,
+        node: &FilledNode<L>,
+    ) -> OriginalSkeletonTreeResult<()> {
+        (*self).verify_node(index, node)
+    }
+}
+
+/// Recomputes the hash of the given node, located at the given index of the given trie, from its
+/// data, and compares it to the hash the node is stored under.
+pub(crate) fn verify_node_hash<L: Leaf>(
+    trie: TrieId,
+    index: &NodeIndex,
+    node: &FilledNode<L>,
+) -> OriginalSkeletonTreeResult<()>
+where
+    TreeHashFunctionImpl: TreeHashFunction<L>,
+{
+    if TreeHashFunctionImpl::compute_node_hash(&node.data) != node.hash {
+        return Err(OriginalSkeletonTreeError::NodeHashMismatch {
+            trie,
+            index: *index,
+            expected: node.hash,
+        });
+    }
+    Ok(())
 }
 
 #[macro_export]
@@ -25,17 +81,23 @@ macro_rules! generate_trie_config {
         pub(crate) struct $struct_name<'a> {
             modifications: &'a LeafModifications<$leaf_type>,
             compare_modified_leaves: bool,
+            verified_trie: Option<TrieId>,
         }
 
         impl<'a> $struct_name<'a> {
             #[allow(dead_code)]
+            /// Creates a configuration of a trie with the given modifications. If `verified_trie`
+            /// is given, the nodes read from storage are verified, and attributed to that trie on
+            /// failure.
             pub(crate) fn new(
                 modifications: &'a LeafModifications<$leaf_type>,
                 compare_modified_leaves: bool,
+                verified_trie: Option<TrieId>,
             ) -> Self {
                 Self {
                     modifications,
                     compare_modified_leaves,
+                    verified_trie,
                 }
             }
         }
@@ -56,6 +118,17 @@ macro_rules! generate_trie_config {
                     .ok_or(OriginalSkeletonTreeError::ReadModificationsError(*index))?;
                 Ok(new_leaf == previous_leaf)
             }
+
+            fn verify_node(
+                &self,
+                index: &NodeIndex,
+                node: &FilledNode<$leaf_type>,
+            ) -> OriginalSkeletonTreeResult<()> {
+                match self.verified_trie {
+                    Some(trie) => verify_node_hash(trie, index, node),
+                    None => Ok(()),
+                }
+            }
         }
     };
 }
@@ -64,7 +137,9 @@ generate_trie_config!(OriginalSkeletonStorageTrieConfig, StarknetStorageValue);
 
 generate_trie_config!(OriginalSkeletonClassesTrieConfig, CompiledClassHash);
 
-pub(crate) struct OriginalSkeletonContractsTrieConfig;
+pub(crate) struct OriginalSkeletonContractsTrieConfig {
+    verify_on_read: bool,
+}
 
 impl OriginalSkeletonTreeConfig<ContractState> for OriginalSkeletonContractsTrieConfig {
     fn compare_modified_leaves(&self) -> bool {
@@ -78,10 +153,21 @@ impl OriginalSkeletonTreeConfig<ContractState> for OriginalSkeletonContractsTrie
     ) -> OriginalSkeletonTreeResult<bool> {
         Ok(false)
     }
+
+    fn verify_node(
+        &self,
+        index: &NodeIndex,
+        node: &FilledNode<ContractState>,
+    ) -> OriginalSkeletonTreeResult<()> {
+        if !self.verify_on_read {
+            return Ok(());
+        }
+        verify_node_hash(TrieId::ContractsTrie, index, node)
+    }
 }
 
 impl OriginalSkeletonContractsTrieConfig {
-    pub(crate) fn new() -> Self {
-        Self
+    pub(crate) fn new(verify_on_read: bool) -> Self {
+        Self { verify_on_read }
     }
 }
@@ -1,5 +1,6 @@
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::FilledNode;
+use crate::patricia_merkle_tree::node_data::errors::PathToBottomError;
 use crate::patricia_merkle_tree::node_data::inner_node::BinaryData;
 use crate::patricia_merkle_tree::node_data::inner_node::EdgeData;
 use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
@@ -9,16 +10,16 @@ use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeleto
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeImpl;
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeResult;
 use crate::patricia_merkle_tree::original_skeleton_tree::utils::split_leaves;
+use crate::patricia_merkle_tree::traversal::node_db_key;
 use crate::patricia_merkle_tree::types::SortedLeafIndices;
 use crate::patricia_merkle_tree::types::SubTreeHeight;
 use crate::patricia_merkle_tree::{
     original_skeleton_tree::node::OriginalSkeletonNode, types::NodeIndex,
 };
 use crate::storage::errors::StorageError;
-use crate::storage::storage_trait::create_db_key;
-use crate::storage::storage_trait::StarknetPrefix;
-use crate::storage::storage_trait::Storage;
+use crate::storage::storage_trait::AsyncStorage;
 use crate::storage::storage_trait::StorageKey;
+use crate::storage::storage_trait::StorageValue;
 use log::warn;
 use std::borrow::Borrow;
 use std::collections::HashMap;
@@ -39,7 +40,7 @@ macro_rules! log_trivial_modification {
 }
 
 #[derive(Debug, PartialEq)]
-struct SubTree<'a> {
+pub(crate) struct SubTree<'a> {
     pub sorted_leaf_indices: SortedLeafIndices<'a>,
     pub root_index: NodeIndex,
     pub root_hash: HashOutput,
@@ -61,12 +62,13 @@ impl<'a> SubTree<'a> {
     /// Returns the bottom subtree which is referred from `self` by the given path. When creating
     /// the bottom subtree some indices that were modified under `self` are not modified under the
     /// bottom subtree (leaves that were previously empty). These indices are returned as well.
-    fn get_bottom_subtree(
+    /// Fails if the path descends below the leaves.
+    pub(crate) fn get_bottom_subtree(
         &self,
         path_to_bottom: &PathToBottom,
         bottom_hash: HashOutput,
-    ) -> (Self, Vec<&NodeIndex>) {
-        let bottom_index = path_to_bottom.bottom_index(self.root_index);
+    ) -> Result<(Self, Vec<&NodeIndex>), PathToBottomError> {
+        let bottom_index = path_to_bottom.bottom_index(self.root_index)?;
         let bottom_height = self.get_height() - SubTreeHeight::new(path_to_bottom.length.into());
         let leftmost_in_subtree = bottom_index << bottom_height.into();
         let rightmost_in_subtree =
@@ -82,17 +84,21 @@ impl<'a> SubTree<'a> {
             .chain(self.sorted_leaf_indices.get_indices()[rightmost_index..].iter())
             .collect();
 
-        (
+        Ok((
             Self {
                 sorted_leaf_indices: bottom_leaves,
                 root_index: bottom_index,
                 root_hash: bottom_hash,
             },
             previously_empty_leaf_indices,
-        )
+        ))
     }
 
-    fn get_children_subtrees(&self, left_hash: HashOutput, right_hash: HashOutput) -> (Self, Self) {
+    pub(crate) fn get_children_subtrees(
+        &self,
+        left_hash: HashOutput,
+        right_hash: HashOutput,
+    ) -> (Self, Self) {
         let [left_leaves, right_leaves] = self.split_leaves();
         let left_root_index = self.root_index * 2.into();
         (
@@ -115,25 +121,29 @@ impl<'a> SubTree<'a> {
 }
 
 impl<'a> OriginalSkeletonTreeImpl<'a> {
-    /// Fetches the Patricia witnesses, required to build the original skeleton tree from storage.
-    /// Given a list of subtrees, traverses towards their leaves and fetches all non-empty,
-    /// unmodified nodes. If `compare_modified_leaves` is set, function logs out a warning when
-    /// encountering a trivial modification. Fills the previous leaf values if it is not none.
-    fn fetch_nodes<L: Leaf>(
+    /// Handles the fetched roots of a layer of subtrees: adds the skeleton nodes they determine,
+    /// and returns the subtrees of the next layer that should be fetched. If `previous_node_keys`
+    /// is given, adds to it the storage keys of the nodes which are superseded by the update: the
+    /// modified nodes, and the unmodified edges under them.
+    fn handle_layer<L: Leaf>(
         &mut self,
-        subtrees: Vec<SubTree<'a>>,
-        storage: &impl Storage,
+        subtrees: &[SubTree<'a>],
+        filled_roots: Vec<FilledNode<L>>,
         config: &impl OriginalSkeletonTreeConfig<L>,
         mut previous_leaves: Option<&mut HashMap<NodeIndex, L>>,
-    ) -> OriginalSkeletonTreeResult<()> {
-        if subtrees.is_empty() {
-            return Ok(());
-        }
+        mut previous_node_keys: Option<&mut Vec<StorageKey>>,
+    ) -> OriginalSkeletonTreeResult<Vec<SubTree<'a>>> {
         let should_fetch_modified_leaves =
             config.compare_modified_leaves() || previous_leaves.is_some();
         let mut next_subtrees = Vec::new();
-        let filled_roots = Self::calculate_subtrees_roots::<L>(&subtrees, storage)?;
         for (filled_root, subtree) in filled_roots.into_iter().zip(subtrees.iter()) {
+            if let Some(ref mut keys) = previous_node_keys {
+                // An unmodified edge is rebuilt by the update too: it is either recreated under
+                // its modified parent, or merged into the parent's edge if its sibling is deleted.
+                if !subtree.is_unmodified() || matches!(filled_root.data, NodeData::Edge(_)) {
+                    keys.push(node_db_key::<L>(subtree.root_hash, subtree.is_leaf()));
+                }
+            }
             match filled_root.data {
                 // Binary node.
                 NodeData::Binary(BinaryData {
@@ -152,15 +162,17 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
                     let (left_subtree, right_subtree) =
                         subtree.get_children_subtrees(left_hash, right_hash);
 
-                    self.handle_subtree(
+                    self.handle_subtree::<L>(
                         &mut next_subtrees,
                         left_subtree,
                         should_fetch_modified_leaves,
+                        previous_node_keys.as_deref_mut(),
                     );
-                    self.handle_subtree(
+                    self.handle_subtree::<L>(
                         &mut next_subtrees,
                         right_subtree,
                         should_fetch_modified_leaves,
+                        previous_node_keys.as_deref_mut(),
                     )
                 }
                 // Edge node.
@@ -174,14 +186,14 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
                     );
                     if subtree.is_unmodified() {
                         self.nodes.insert(
-                            path_to_bottom.bottom_index(subtree.root_index),
+                            path_to_bottom.bottom_index(subtree.root_index)?,
                             OriginalSkeletonNode::UnmodifiedSubTree(bottom_hash),
                         );
                         continue;
                     }
                     // Parse bottom.
                     let (bottom_subtree, previously_empty_leaves_indices) =
-                        subtree.get_bottom_subtree(&path_to_bottom, bottom_hash);
+                        subtree.get_bottom_subtree(&path_to_bottom, bottom_hash)?;
                     if let Some(ref mut leaves) = previous_leaves {
                         leaves.extend(
                             previously_empty_leaves_indices
@@ -195,10 +207,11 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
                         config,
                     )?;
 
-                    self.handle_subtree(
+                    self.handle_subtree::<L>(
                         &mut next_subtrees,
                         bottom_subtree,
                         should_fetch_modified_leaves,
+                        previous_node_keys.as_deref_mut(),
                     );
                 }
                 // Leaf node.
@@ -220,104 +233,121 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
                 }
             }
         }
-        self.fetch_nodes::<L>(next_subtrees, storage, config, previous_leaves)
+        Ok(next_subtrees)
     }
 
-    //TODO(Aviv, 17/07/2024): Split between storage prefix implementation and function logic.
-    fn calculate_subtrees_roots<L: Leaf>(
-        subtrees: &[SubTree<'a>],
-        storage: &impl Storage,
-    ) -> OriginalSkeletonTreeResult<Vec<FilledNode<L>>> {
-        let mut subtrees_roots = vec![];
-        let db_keys: Vec<StorageKey> = subtrees
-            .iter()
-            .map(|subtree| {
-                create_db_key(
-                    if subtree.is_leaf() {
-                        L::prefix()
-                    } else {
-                        StarknetPrefix::InnerNode.to_storage_prefix()
-                    },
-                    &subtree.root_hash.0.to_bytes_be(),
-                )
-            })
-            .collect();
+    /// Creates the original skeletons of several trees with the same leaf type (e.g., the storage
+    /// tries of the modified contracts), given the root hash, modified leaves and configuration of
+    /// each tree. Rather than traversing the trees one after another, the nodes of all trees are
+    /// fetched together, layer by layer, with a single storage access per layer.
+    /// If `get_previous_leaves` is set, also returns the previous leaves of the modified leaves of
+    /// each tree (otherwise, the returned leaves are empty).
+    /// If `previous_node_keys` is given, adds to it the storage keys of the modified nodes of all
+    /// trees, i.e., the previous versions of the nodes that are superseded by the update.
+    pub(crate) async fn create_many<L: Leaf, C: OriginalSkeletonTreeConfig<L>>(
+        storage: &impl AsyncStorage,
+        trees: &[(HashOutput, SortedLeafIndices<'a>, C)],
+        get_previous_leaves: bool,
+        mut previous_node_keys: Option<&mut Vec<StorageKey>>,
+    ) -> OriginalSkeletonTreeResult<Vec<(Self, HashMap<NodeIndex, L>)>> {
+        let mut skeleton_trees = Vec::with_capacity(trees.len());
+        let mut pending_subtrees = Vec::with_capacity(trees.len());
+        for (root_hash, sorted_leaf_indices, config) in trees {
+            let mut previous_leaves = HashMap::new();
+            if sorted_leaf_indices.is_empty() {
+                skeleton_trees.push((Self::create_unmodified(*root_hash), previous_leaves));
+                pending_subtrees.push(Vec::new());
+                continue;
+            }
+            if *root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+                if get_previous_leaves {
+                    previous_leaves.extend(
+                        sorted_leaf_indices
+                            .get_indices()
+                            .iter()
+                            .map(|idx| (*idx, L::default())),
+                    );
+                } else {
+                    Self::log_warning_for_empty_leaves(sorted_leaf_indices.get_indices(), config)?;
+                }
+                skeleton_trees.push((Self::create_empty(*sorted_leaf_indices), previous_leaves));
+                pending_subtrees.push(Vec::new());
+                continue;
+            }
+            skeleton_trees.push((
+                Self {
+                    nodes: HashMap::new(),
+                    sorted_leaf_indices: *sorted_leaf_indices,
+                },
+                previous_leaves,
+            ));
+            pending_subtrees.push(vec![SubTree {
+                sorted_leaf_indices: *sorted_leaf_indices,
+                root_index: NodeIndex::ROOT,
+                root_hash: *root_hash,
+            }]);
+        }
 
-        let db_vals = storage.mget(&db_keys);
-        for ((subtree, optional_val), db_key) in
-            subtrees.iter().zip(db_vals.iter()).zip(db_keys.into_iter())
-        {
-            let val = optional_val.ok_or(StorageError::MissingKey(db_key))?;
-            subtrees_roots.push(FilledNode::deserialize(
-                subtree.root_hash,
-                val,
-                subtree.is_leaf(),
-            )?)
+        // Traverse all trees layer by layer, fetching each layer with a single storage access.
+        while pending_subtrees.iter().any(|subtrees| !subtrees.is_empty()) {
+            let layer_sizes: Vec<usize> = pending_subtrees.iter().map(Vec::len).collect();
+            let layer: Vec<SubTree<'a>> = pending_subtrees.into_iter().flatten().collect();
+            let mut db_entries = Self::fetch_subtrees_roots::<L>(&layer, storage)
+                .await?
+                .into_iter();
+            let mut layer_subtrees = layer.as_slice();
+            pending_subtrees = Vec::with_capacity(trees.len());
+            for (((skeleton_tree, previous_leaves), (_, _, config)), layer_size) in
+                skeleton_trees.iter_mut().zip(trees).zip(layer_sizes)
+            {
+                let (tree_subtrees, rest) = layer_subtrees.split_at(layer_size);
+                layer_subtrees = rest;
+                let filled_roots = Self::deserialize_subtrees_roots(
+                    tree_subtrees,
+                    db_entries.by_ref().take(layer_size),
+                    config,
+                )?;
+                pending_subtrees.push(skeleton_tree.handle_layer(
+                    tree_subtrees,
+                    filled_roots,
+                    config,
+                    get_previous_leaves.then_some(previous_leaves),
+                    previous_node_keys.as_deref_mut(),
+                )?);
+            }
         }
-        Ok(subtrees_roots)
+        Ok(skeleton_trees)
     }
 
-    pub(crate) fn create_impl<L: Leaf>(
-        storage: &impl Storage,
-        root_hash: HashOutput,
-        sorted_leaf_indices: SortedLeafIndices<'a>,
-        config: &impl OriginalSkeletonTreeConfig<L>,
-    ) -> OriginalSkeletonTreeResult<Self> {
-        if sorted_leaf_indices.is_empty() {
-            return Ok(Self::create_unmodified(root_hash));
-        }
-        if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
-            OriginalSkeletonTreeImpl::log_warning_for_empty_leaves(
-                sorted_leaf_indices.get_indices(),
-                config,
-            )?;
-            return Ok(Self::create_empty(sorted_leaf_indices));
-        }
-        let main_subtree = SubTree {
-            sorted_leaf_indices,
-            root_index: NodeIndex::ROOT,
-            root_hash,
-        };
-        let mut skeleton_tree = Self {
-            nodes: HashMap::new(),
-            sorted_leaf_indices,
-        };
-        skeleton_tree.fetch_nodes::<L>(vec![main_subtree], storage, config, None)?;
-        Ok(skeleton_tree)
+    /// Reads the stored roots of the given subtrees, with a single storage access. Returns the
+    /// storage key and the stored value (if any) of each root.
+    async fn fetch_subtrees_roots<L: Leaf>(
+        subtrees: &[SubTree<'a>],
+        storage: &impl AsyncStorage,
+    ) -> OriginalSkeletonTreeResult<Vec<(StorageKey, Option<StorageValue>)>> {
+        let db_keys: Vec<StorageKey> = subtrees
+            .iter()
+            .map(|subtree| node_db_key::<L>(subtree.root_hash, subtree.is_leaf()))
+            .collect();
+        let db_vals = storage.mget(&db_keys).await?;
+        Ok(db_keys.into_iter().zip(db_vals).collect())
     }
 
-    pub(crate) fn create_and_get_previous_leaves_impl<L: Leaf>(
-        storage: &impl Storage,
-        root_hash: HashOutput,
-        sorted_leaf_indices: SortedLeafIndices<'a>,
+    /// Deserializes the given stored roots of the given subtrees, and verifies them by the given
+    /// configuration.
+    fn deserialize_subtrees_roots<L: Leaf>(
+        subtrees: &[SubTree<'a>],
+        db_entries: impl Iterator<Item = (StorageKey, Option<StorageValue>)>,
         config: &impl OriginalSkeletonTreeConfig<L>,
-    ) -> OriginalSkeletonTreeResult<(Self, HashMap<NodeIndex, L>)> {
-        if sorted_leaf_indices.is_empty() {
-            let unmodified = Self::create_unmodified(root_hash);
-            return Ok((unmodified, HashMap::new()));
-        }
-        if root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
-            return Ok((
-                Self::create_empty(sorted_leaf_indices),
-                sorted_leaf_indices
-                    .get_indices()
-                    .iter()
-                    .map(|idx| (*idx, L::default()))
-                    .collect(),
-            ));
+    ) -> OriginalSkeletonTreeResult<Vec<FilledNode<L>>> {
+        let mut subtrees_roots = vec![];
+        for (subtree, (db_key, optional_val)) in subtrees.iter().zip(db_entries) {
+            let val = optional_val.ok_or(StorageError::MissingKey(db_key))?;
+            let filled_root = FilledNode::deserialize(subtree.root_hash, &val, subtree.is_leaf())?;
+            config.verify_node(&subtree.root_index, &filled_root)?;
+            subtrees_roots.push(filled_root)
         }
-        let main_subtree = SubTree {
-            sorted_leaf_indices,
-            root_index: NodeIndex::ROOT,
-            root_hash,
-        };
-        let mut skeleton_tree = Self {
-            nodes: HashMap::new(),
-            sorted_leaf_indices,
-        };
-        let mut leaves = HashMap::new();
-        skeleton_tree.fetch_nodes::<L>(vec![main_subtree], storage, config, Some(&mut leaves))?;
-        Ok((skeleton_tree, leaves))
+        Ok(subtrees_roots)
     }
 
     fn create_unmodified(root_hash: HashOutput) -> Self {
@@ -338,12 +368,14 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
     }
 
     /// Handles a subtree referred by an edge or a binary node. Decides whether we deserialize the
-    /// referred subtree or not.
-    fn handle_subtree(
+    /// referred subtree or not. The key of a modified leaf that is not deserialized is added to
+    /// `previous_node_keys`, if given.
+    fn handle_subtree<L: Leaf>(
         &mut self,
         next_subtrees: &mut Vec<SubTree<'a>>,
         subtree: SubTree<'a>,
         should_fetch_modified_leaves: bool,
+        previous_node_keys: Option<&mut Vec<StorageKey>>,
     ) {
         if !subtree.is_leaf() || (should_fetch_modified_leaves && !subtree.is_unmodified()) {
             next_subtrees.push(subtree);
@@ -353,6 +385,9 @@ impl<'a> OriginalSkeletonTreeImpl<'a> {
                 subtree.root_index,
                 OriginalSkeletonNode::UnmodifiedSubTree(subtree.root_hash),
             );
+        } else if let Some(keys) = previous_node_keys {
+            // Modified leaf.
+            keys.push(node_db_key::<L>(subtree.root_hash, true));
         }
     }
 
@@ -3,23 +3,31 @@ use crate::block_committer::input::StarknetStorageValue;
 use crate::felt::Felt;
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::create_file_storage;
 use crate::patricia_merkle_tree::internal_test_utils::OriginalSkeletonMockTrieConfig;
 use crate::patricia_merkle_tree::internal_test_utils::{small_tree_index_to_full, MockLeaf};
+use crate::patricia_merkle_tree::merkle_proof::proof::proof_test::create_storage_trie;
+use crate::patricia_merkle_tree::node_data::errors::PathToBottomError;
 use crate::patricia_merkle_tree::node_data::inner_node::EdgePath;
 use crate::patricia_merkle_tree::node_data::inner_node::{EdgePathLength, PathToBottom};
 use crate::patricia_merkle_tree::node_data::leaf::{ContractState, LeafModifications};
+use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeletonStorageTrieConfig;
 use crate::patricia_merkle_tree::original_skeleton_tree::create_tree::SubTree;
+use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
 use crate::patricia_merkle_tree::original_skeleton_tree::node::OriginalSkeletonNode;
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTree;
 use crate::patricia_merkle_tree::types::SubTreeHeight;
 use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
 use crate::storage::db_object::DBObject;
 use crate::storage::map_storage::MapStorage;
-use crate::storage::storage_trait::{create_db_key, StarknetPrefix, StorageKey, StorageValue};
+use crate::storage::storage_trait::{
+    create_db_key, AsyncStorage, StarknetPrefix, StorageKey, StorageResult, StorageValue,
+};
 use ethnum::U256;
 use pretty_assertions::assert_eq;
 use rstest::rstest;
 use std::collections::HashMap;
+use std::sync::atomic::{AtomicUsize, Ordering};
 
 #[rstest]
 // This test assumes for simplicity that hash is addition (i.e hash(a,b) = a + b).
@@ -196,29 +204,193 @@ use std::collections::HashMap;
     ),
     SubTreeHeight::new(4),
 )]
-fn test_create_tree(
+#[tokio::test]
+async fn test_create_tree(
     #[case] storage: MapStorage,
     #[case] leaf_modifications: LeafModifications<MockLeaf>,
     #[case] root_hash: HashOutput,
     #[case] expected_skeleton_nodes: HashMap<NodeIndex, OriginalSkeletonNode>,
     #[case] subtree_height: SubTreeHeight,
     #[values(true, false)] compare_modified_leaves: bool,
+    #[values(false, true)] use_file_storage: bool,
 ) {
     let leaf_modifications: LeafModifications<MockLeaf> = leaf_modifications
         .into_iter()
         .map(|(idx, leaf)| (NodeIndex::from_subtree_index(idx, subtree_height), leaf))
         .collect();
-    let config = OriginalSkeletonMockTrieConfig::new(&leaf_modifications, compare_modified_leaves);
+    let config =
+        OriginalSkeletonMockTrieConfig::new(&leaf_modifications, compare_modified_leaves, None);
     let mut sorted_leaf_indices: Vec<NodeIndex> = leaf_modifications.keys().copied().collect();
     let sorted_leaf_indices = SortedLeafIndices::new(&mut sorted_leaf_indices);
-    let skeleton_tree = OriginalSkeletonTreeImpl::create::<MockLeaf>(
+    let skeleton_tree = if use_file_storage {
+        let (_storage_dir, file_storage) = create_file_storage(storage.storage);
+        OriginalSkeletonTreeImpl::create::<MockLeaf>(
+            &file_storage,
+            root_hash,
+            sorted_leaf_indices,
+            &config,
+        )
+        .await
+    } else {
+        OriginalSkeletonTreeImpl::create::<MockLeaf>(
+            &storage,
+            root_hash,
+            sorted_leaf_indices,
+            &config,
+        )
+        .await
+    }
+    .unwrap();
+    assert_eq!(&skeleton_tree.nodes, &expected_skeleton_nodes);
+}
+
+/// An edge which descends below the leaves (e.g., a corrupted stored node) fails the creation.
+#[rstest]
+#[case::unmodified_bottom(NodeIndex::from(3) << 250)]
+#[case::modified_bottom(NodeIndex::FIRST_LEAF)]
+#[tokio::test]
+async fn test_create_tree_with_edge_below_leaves(#[case] modified_leaf: NodeIndex) {
+    // The left child of the root is an edge of the maximal length, which is one too many, and
+    // the right child is an edge to the leftmost leaf under it.
+    let storage = MapStorage {
+        storage: HashMap::from([
+            create_binary_entry(256, 257),
+            create_edge_entry(5, 0, SubTreeHeight::ACTUAL_HEIGHT.into()),
+            create_edge_entry(7, 0, 250),
+            create_mock_leaf_entry(7),
+        ]),
+    };
+    let leaf_modifications = LeafModifications::from([(modified_leaf, MockLeaf(Felt::ONE))]);
+    let config = OriginalSkeletonMockTrieConfig::new(&leaf_modifications, false, None);
+    let mut sorted_leaf_indices = vec![modified_leaf];
+    let result = OriginalSkeletonTreeImpl::create::<MockLeaf>(
         &storage,
-        root_hash,
-        sorted_leaf_indices,
+        HashOutput(Felt::from(513_u128)),
+        SortedLeafIndices::new(&mut sorted_leaf_indices),
         &config,
     )
-    .unwrap();
-    assert_eq!(&skeleton_tree.nodes, &expected_skeleton_nodes);
+    .await;
+    assert!(matches!(
+        result,
+        Err(OriginalSkeletonTreeError::PathToBottom(PathToBottomError::BottomOutOfRange {
+            root_index, ..
+        })) if root_index == NodeIndex::from(2)
+    ));
+}
+
+/// A storage which counts its `mget` calls.
+struct MgetCountingStorage {
+    storage: MapStorage,
+    mget_calls: AtomicUsize,
+}
+
+impl MgetCountingStorage {
+    fn new(storage: MapStorage) -> Self {
+        Self {
+            storage,
+            mget_calls: AtomicUsize::new(0),
+        }
+    }
+
+    fn take_mget_calls(&self) -> usize {
+        self.mget_calls.swap(0, Ordering::SeqCst)
+    }
+}
+
+impl AsyncStorage for MgetCountingStorage {
+    async fn get(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        AsyncStorage::get(&self.storage, key).await
+    }
+
+    async fn set(
+        &mut self,
+        key: StorageKey,
+        value: StorageValue,
+    ) -> StorageResult<Option<StorageValue>> {
+        AsyncStorage::set(&mut self.storage, key, value).await
+    }
+
+    async fn mget(&self, keys: &[StorageKey]) -> StorageResult<Vec<Option<StorageValue>>> {
+        self.mget_calls.fetch_add(1, Ordering::SeqCst);
+        AsyncStorage::mget(&self.storage, keys).await
+    }
+
+    async fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) -> StorageResult<()> {
+        AsyncStorage::mset(&mut self.storage, key_to_value).await
+    }
+
+    async fn delete(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        AsyncStorage::delete(&mut self.storage, key).await
+    }
+}
+
+#[rstest]
+#[tokio::test]
+async fn test_create_many(#[values(false, true)] get_previous_leaves: bool) {
+    let (mut storage, first_root_hash) =
+        create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2), (U256::ONE << 100, 3)]).await;
+    let (second_storage, second_root_hash) = create_storage_trie(&[(U256::from(7_u128), 4)]).await;
+    storage.storage.extend(second_storage.storage);
+    let storage = MgetCountingStorage::new(storage);
+
+    let as_leaf_indices = |keys: &[U256]| -> Vec<NodeIndex> {
+        keys.iter()
+            .map(|key| NodeIndex::FIRST_LEAF + NodeIndex::new(*key).unwrap())
+            .collect()
+    };
+    let mut trees_leaf_indices = [
+        as_leaf_indices(&[U256::ONE, U256::from(5_u128)]),
+        as_leaf_indices(&[U256::from(7_u128), U256::ONE << 200]),
+        Vec::new(),
+        as_leaf_indices(&[U256::from(3_u128)]),
+    ];
+    let leaf_modifications: LeafModifications<StarknetStorageValue> = trees_leaf_indices
+        .iter()
+        .flatten()
+        .map(|index| (*index, StarknetStorageValue(Felt::from(9_u128))))
+        .collect();
+    let root_hashes = [
+        first_root_hash,
+        second_root_hash,
+        first_root_hash,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    ];
+    let trees: Vec<_> = root_hashes
+        .into_iter()
+        .zip(trees_leaf_indices.iter_mut())
+        .map(|(root_hash, leaf_indices)| {
+            (
+                root_hash,
+                SortedLeafIndices::new(leaf_indices),
+                OriginalSkeletonStorageTrieConfig::new(&leaf_modifications, false, None),
+            )
+        })
+        .collect();
+
+    // Create each tree separately, for reference.
+    let mut expected_skeleton_trees = Vec::new();
+    let mut max_mget_calls = 0;
+    for tree in &trees {
+        expected_skeleton_trees.extend(
+            OriginalSkeletonTreeImpl::create_many(
+                &storage,
+                std::slice::from_ref(tree),
+                get_previous_leaves,
+                None,
+            )
+            .await
+            .unwrap(),
+        );
+        max_mget_calls = max_mget_calls.max(storage.take_mget_calls());
+    }
+
+    let skeleton_trees =
+        OriginalSkeletonTreeImpl::create_many(&storage, &trees, get_previous_leaves, None)
+            .await
+            .unwrap();
+    assert_eq!(skeleton_trees, expected_skeleton_trees);
+    // All trees are fetched together, layer by layer.
+    assert_eq!(storage.take_mget_calls(), max_mget_calls);
 }
 
 /// case::single_right_child
@@ -395,8 +567,9 @@ fn test_get_bottom_subtree(
     };
 
     // Get the bottom subtree.
-    let (subtree, previously_empty_leaf_indices) =
-        tree.get_bottom_subtree(&path_to_bottom, HashOutput(Felt::TWO));
+    let (subtree, previously_empty_leaf_indices) = tree
+        .get_bottom_subtree(&path_to_bottom, HashOutput(Felt::TWO))
+        .unwrap();
 
     let expected_root_index = small_tree_index_to_full(expected_root_index, height);
 
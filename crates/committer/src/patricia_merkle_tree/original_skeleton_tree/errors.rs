@@ -3,7 +3,9 @@ use std::fmt::Debug;
 use thiserror::Error;
 
 use crate::{
-    patricia_merkle_tree::types::NodeIndex,
+    hash::hash_trait::HashOutput,
+    patricia_merkle_tree::node_data::errors::PathToBottomError,
+    patricia_merkle_tree::types::{NodeIndex, TrieId},
     storage::errors::{DeserializationError, StorageError},
 };
 
@@ -18,6 +20,17 @@ pub enum OriginalSkeletonTreeError {
          original skeleton tree."
     )]
     StorageRead(#[from] StorageError),
+    #[error(transparent)]
+    PathToBottom(#[from] PathToBottomError),
     #[error("Failed to read the modified leaf at index {0:?}")]
     ReadModificationsError(NodeIndex),
+    #[error(
+        "The node at index {index:?} of the {trie:?} does not hash to {expected:?}, which it is \
+         stored under."
+    )]
+    NodeHashMismatch {
+        trie: TrieId,
+        index: NodeIndex,
+        expected: HashOutput,
+    },
 }
@@ -6,15 +6,23 @@ use crate::forest_errors::ForestResult;
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::CompiledClassHash;
 use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::node_data::leaf::Leaf;
 use crate::patricia_merkle_tree::node_data::leaf::LeafModifications;
 use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeletonClassesTrieConfig;
 use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeletonContractsTrieConfig;
 use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeletonStorageTrieConfig;
-use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTree;
+use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeletonTreeConfig;
+use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeImpl;
+use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeResult;
 use crate::patricia_merkle_tree::types::NodeIndex;
 use crate::patricia_merkle_tree::types::SortedLeafIndices;
-use crate::storage::storage_trait::Storage;
+use crate::patricia_merkle_tree::types::TrieId;
+use crate::state_reader::flat_index::{flat_storage_value_key, is_flat_state_index_at};
+use crate::storage::db_object::Deserializable;
+use crate::storage::storage_trait::AsyncStorage;
+use crate::storage::storage_trait::StorageKey;
+use log::warn;
 use std::collections::HashMap;
 
 #[cfg(test)]
@@ -26,14 +34,17 @@ pub(crate) struct OriginalSkeletonForest<'a> {
     pub(crate) classes_trie: OriginalSkeletonTreeImpl<'a>,
     pub(crate) contracts_trie: OriginalSkeletonTreeImpl<'a>,
     pub(crate) storage_tries: HashMap<ContractAddress, OriginalSkeletonTreeImpl<'a>>,
+    /// The storage keys of the modified nodes in all tries, i.e., the previous versions of the
+    /// nodes that are superseded by the update. May contain duplicates.
+    pub(crate) previous_node_keys: Vec<StorageKey>,
 }
 
 impl<'a> OriginalSkeletonForest<'a> {
     /// Creates an original skeleton forest that includes the storage tries of the modified contracts,
     /// the classes trie and the contracts trie. Additionally, returns the original contract states that
     /// are needed to compute the contract state tree.
-    pub(crate) fn create(
-        storage: impl Storage,
+    pub(crate) async fn create(
+        storage: &impl AsyncStorage,
         contracts_trie_root_hash: HashOutput,
         classes_trie_root_hash: HashOutput,
         storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
@@ -44,62 +55,155 @@ impl<'a> OriginalSkeletonForest<'a> {
     where
         Self: std::marker::Sized,
     {
-        let (contracts_trie, original_contracts_trie_leaves) = Self::create_contracts_trie(
+        let (forest, previous_leaves) = Self::create_impl(
+            storage,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            storage_updates,
+            classes_updates,
+            forest_sorted_indices,
+            config,
+            false,
+        )
+        .await?;
+        Ok((forest, previous_leaves.contracts_trie_leaves))
+    }
+
+    /// Same as [Self::create], but also returns the previous leaves of the modified leaves in the
+    /// storage tries and in the classes trie.
+    pub(crate) async fn create_and_get_previous_leaves(
+        storage: &impl AsyncStorage,
+        contracts_trie_root_hash: HashOutput,
+        classes_trie_root_hash: HashOutput,
+        storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+        classes_updates: &LeafModifications<CompiledClassHash>,
+        forest_sorted_indices: &ForestSortedIndices<'a>,
+        config: &impl Config,
+    ) -> ForestResult<(Self, ForestPreviousLeaves)> {
+        Self::create_impl(
+            storage,
             contracts_trie_root_hash,
-            &storage,
+            classes_trie_root_hash,
+            storage_updates,
+            classes_updates,
+            forest_sorted_indices,
+            config,
+            true,
+        )
+        .await
+    }
+
+    #[allow(clippy::too_many_arguments)]
+    async fn create_impl(
+        storage: &impl AsyncStorage,
+        contracts_trie_root_hash: HashOutput,
+        classes_trie_root_hash: HashOutput,
+        storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+        classes_updates: &LeafModifications<CompiledClassHash>,
+        forest_sorted_indices: &ForestSortedIndices<'a>,
+        config: &impl Config,
+        get_previous_leaves: bool,
+    ) -> ForestResult<(Self, ForestPreviousLeaves)> {
+        let mut previous_node_keys = Vec::new();
+        // The flat state index is used only if it holds the state at the given root.
+        let use_flat_state_index = config.flat_state_index()
+            && is_flat_state_index_at(storage, contracts_trie_root_hash)
+                .await
+                .map_err(OriginalSkeletonTreeError::StorageRead)?;
+        let (contracts_trie, contracts_trie_leaves) = Self::create_contracts_trie(
+            contracts_trie_root_hash,
+            storage,
+            config,
             forest_sorted_indices.contracts_trie_sorted_indices,
-        )?;
-        let storage_tries = Self::create_storage_tries(
+            &mut previous_node_keys,
+        )
+        .await?;
+        let (storage_tries, storage_tries_leaves) = Self::create_storage_tries(
             storage_updates,
-            &original_contracts_trie_leaves,
-            &storage,
+            &contracts_trie_leaves,
+            storage,
             config,
+            use_flat_state_index,
             &forest_sorted_indices.storage_tries_sorted_indices,
-        )?;
-        let classes_trie = Self::create_classes_trie(
+            get_previous_leaves,
+            &mut previous_node_keys,
+        )
+        .await?;
+        let (classes_trie, classes_trie_leaves) = Self::create_classes_trie(
             classes_updates,
             classes_trie_root_hash,
-            &storage,
+            storage,
             config,
             forest_sorted_indices.classes_trie_sorted_indices,
-        )?;
+            get_previous_leaves,
+            &mut previous_node_keys,
+        )
+        .await?;
 
         Ok((
             Self {
                 classes_trie,
                 contracts_trie,
                 storage_tries,
+                previous_node_keys,
+            },
+            ForestPreviousLeaves {
+                contracts_trie_leaves,
+                storage_tries_leaves,
+                classes_trie_leaves,
             },
-            original_contracts_trie_leaves,
         ))
     }
 
     /// Creates the contracts trie original skeleton.
     /// Also returns the previous contracts state of the modified contracts.
-    fn create_contracts_trie(
+    async fn create_contracts_trie(
         contracts_trie_root_hash: HashOutput,
-        storage: &impl Storage,
+        storage: &impl AsyncStorage,
+        config: &impl Config,
         contracts_trie_sorted_indices: SortedLeafIndices<'a>,
+        previous_node_keys: &mut Vec<StorageKey>,
     ) -> ForestResult<(
         OriginalSkeletonTreeImpl<'a>,
         HashMap<NodeIndex, ContractState>,
     )> {
-        Ok(OriginalSkeletonTreeImpl::create_and_get_previous_leaves(
+        Ok(Self::create_single_tree(
             storage,
             contracts_trie_root_hash,
             contracts_trie_sorted_indices,
-            &OriginalSkeletonContractsTrieConfig::new(),
-        )?)
+            OriginalSkeletonContractsTrieConfig::new(config.verify_on_read()),
+            true,
+            previous_node_keys,
+        )
+        .await?)
     }
 
-    fn create_storage_tries(
+    #[allow(clippy::too_many_arguments)]
+    async fn create_storage_tries(
         actual_storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
         original_contracts_trie_leaves: &HashMap<NodeIndex, ContractState>,
-        storage: &impl Storage,
+        storage: &impl AsyncStorage,
         config: &impl Config,
+        use_flat_state_index: bool,
         storage_tries_sorted_indices: &HashMap<ContractAddress, SortedLeafIndices<'a>>,
-    ) -> ForestResult<HashMap<ContractAddress, OriginalSkeletonTreeImpl<'a>>> {
-        let mut storage_tries = HashMap::new();
+        get_previous_leaves: bool,
+        previous_node_keys: &mut Vec<StorageKey>,
+    ) -> ForestResult<(
+        HashMap<ContractAddress, OriginalSkeletonTreeImpl<'a>>,
+        HashMap<ContractAddress, HashMap<NodeIndex, StarknetStorageValue>>,
+    )> {
+        // With the flat state index, modified storage values are compared to their previous values
+        // in the index, so the skeleton builder need not descend to the modified leaves.
+        let compare_modified_leaves =
+            config.warn_on_trivial_modifications() && !use_flat_state_index;
+        if config.warn_on_trivial_modifications() && use_flat_state_index {
+            Self::warn_on_trivial_storage_modifications(storage, actual_storage_updates).await?;
+        }
+
+        // The storage tries are independent; their skeletons are built together, so that each
+        // layer of all tries is fetched with a single storage access.
+        let mut addresses = Vec::with_capacity(actual_storage_updates.len());
+        let mut trees = Vec::with_capacity(actual_storage_updates.len());
         for (address, updates) in actual_storage_updates {
             let sorted_leaf_indices = storage_tries_sorted_indices
                 .get(address)
@@ -109,39 +213,133 @@ impl<'a> OriginalSkeletonForest<'a> {
                 .ok_or(ForestError::MissingContractCurrentState(*address))?;
             let config = OriginalSkeletonStorageTrieConfig::new(
                 updates,
-                config.warn_on_trivial_modifications(),
+                compare_modified_leaves,
+                config
+                    .verify_on_read()
+                    .then_some(TrieId::StorageTrie(*address)),
             );
-
-            let original_skeleton = OriginalSkeletonTreeImpl::create(
-                storage,
+            addresses.push(*address);
+            trees.push((
                 contract_state.storage_root_hash,
                 *sorted_leaf_indices,
-                &config,
-            )?;
-            storage_tries.insert(*address, original_skeleton);
+                config,
+            ));
+        }
+
+        let mut storage_tries = HashMap::new();
+        let mut storage_tries_leaves = HashMap::new();
+        let skeleton_trees = OriginalSkeletonTreeImpl::create_many(
+            storage,
+            &trees,
+            get_previous_leaves,
+            Some(previous_node_keys),
+        )
+        .await?;
+        for (address, (original_skeleton, previous_leaves)) in
+            addresses.into_iter().zip(skeleton_trees)
+        {
+            storage_tries.insert(address, original_skeleton);
+            if get_previous_leaves {
+                storage_tries_leaves.insert(address, previous_leaves);
+            }
         }
-        Ok(storage_tries)
+        Ok((storage_tries, storage_tries_leaves))
     }
 
-    fn create_classes_trie(
+    /// Logs out a warning for each storage modification which sets the previous value, as read
+    /// from the flat state index with a single storage access.
+    async fn warn_on_trivial_storage_modifications(
+        storage: &impl AsyncStorage,
+        actual_storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+    ) -> OriginalSkeletonTreeResult<()> {
+        let modifications: Vec<(&NodeIndex, &StarknetStorageValue)> = actual_storage_updates
+            .values()
+            .flat_map(|updates| updates.iter())
+            .collect();
+        let index_keys: Vec<StorageKey> = actual_storage_updates
+            .iter()
+            .flat_map(|(address, updates)| {
+                updates
+                    .keys()
+                    .map(|leaf_index| flat_storage_value_key(address, leaf_index))
+            })
+            .collect();
+        let previous_values = storage.mget(&index_keys).await?;
+        for ((leaf_index, value), previous_value) in modifications.into_iter().zip(previous_values)
+        {
+            let previous_value = match previous_value {
+                Some(previous_value) => StarknetStorageValue::deserialize(&previous_value)?,
+                None => StarknetStorageValue::default(),
+            };
+            if *value == previous_value {
+                warn!(
+                    "Encountered a trivial modification at index {:?}, with value {:?}",
+                    leaf_index, value
+                );
+            }
+        }
+        Ok(())
+    }
+
+    async fn create_classes_trie(
         actual_classes_updates: &LeafModifications<CompiledClassHash>,
         classes_trie_root_hash: HashOutput,
-        storage: &impl Storage,
+        storage: &impl AsyncStorage,
         config: &impl Config,
         contracts_trie_sorted_indices: SortedLeafIndices<'a>,
-    ) -> ForestResult<OriginalSkeletonTreeImpl<'a>> {
+        get_previous_leaves: bool,
+        previous_node_keys: &mut Vec<StorageKey>,
+    ) -> ForestResult<(
+        OriginalSkeletonTreeImpl<'a>,
+        HashMap<NodeIndex, CompiledClassHash>,
+    )> {
         let config = OriginalSkeletonClassesTrieConfig::new(
             actual_classes_updates,
             config.warn_on_trivial_modifications(),
+            config.verify_on_read().then_some(TrieId::ClassesTrie),
         );
-
-        Ok(OriginalSkeletonTreeImpl::create(
+        Ok(Self::create_single_tree(
             storage,
             classes_trie_root_hash,
             contracts_trie_sorted_indices,
-            &config,
-        )?)
+            config,
+            get_previous_leaves,
+            previous_node_keys,
+        )
+        .await?)
     }
+
+    /// Creates the original skeleton of a single tree, and adds the storage keys of its modified
+    /// nodes to `previous_node_keys`.
+    async fn create_single_tree<L: Leaf>(
+        storage: &impl AsyncStorage,
+        root_hash: HashOutput,
+        sorted_leaf_indices: SortedLeafIndices<'a>,
+        config: impl OriginalSkeletonTreeConfig<L>,
+        get_previous_leaves: bool,
+        previous_node_keys: &mut Vec<StorageKey>,
+    ) -> OriginalSkeletonTreeResult<(OriginalSkeletonTreeImpl<'a>, HashMap<NodeIndex, L>)> {
+        let (skeleton_tree, previous_leaves) = OriginalSkeletonTreeImpl::create_many(
+            storage,
+            &[(root_hash, sorted_leaf_indices, config)],
+            get_previous_leaves,
+            Some(previous_node_keys),
+        )
+        .await?
+        .pop()
+        .expect("A skeleton is created for each given tree.");
+        Ok((skeleton_tree, previous_leaves))
+    }
+}
+
+/// Holds the previous leaves of the modified leaves in the Starknet forest, grouped by tree.
+/// Unless requested, only the previous contract states are fetched.
+#[derive(Debug, Default, PartialEq)]
+pub(crate) struct ForestPreviousLeaves {
+    pub(crate) contracts_trie_leaves: HashMap<NodeIndex, ContractState>,
+    pub(crate) storage_tries_leaves:
+        HashMap<ContractAddress, HashMap<NodeIndex, StarknetStorageValue>>,
+    pub(crate) classes_trie_leaves: HashMap<NodeIndex, CompiledClassHash>,
 }
 
 /// Holds all the indices of the modified leaves in the Starknet forest grouped by tree and sorted.
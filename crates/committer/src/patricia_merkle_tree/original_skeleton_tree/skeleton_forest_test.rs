@@ -10,6 +10,7 @@ use crate::block_committer::input::{
 use crate::felt::Felt;
 use crate::hash::hash_trait::HashOutput;
 use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::create_file_storage;
 use crate::patricia_merkle_tree::node_data::leaf::ContractState;
 use crate::patricia_merkle_tree::original_skeleton_tree::create_tree::create_tree_test::{
     create_32_bytes_entry, create_binary_entry, create_binary_skeleton_node, create_edge_entry,
@@ -252,6 +253,7 @@ macro_rules! compare_skeleton_tree {
                 }
             )
             ]),
+        previous_node_keys: Vec::new(),
         },
         create_contract_leaves(&[
             (7, 29 + 248),
@@ -262,13 +264,15 @@ macro_rules! compare_skeleton_tree {
         vec![6, 7, 0],
         vec![7, 6, 0],
 )]
-fn test_create_original_skeleton_forest(
+#[tokio::test]
+async fn test_create_original_skeleton_forest(
     #[case] input: Input<ConfigImpl>,
     #[case] expected_forest: OriginalSkeletonForest<'_>,
     #[case] expected_original_contracts_trie_leaves: HashMap<ContractAddress, ContractState>,
     #[case] expected_storage_tries_sorted_indices: HashMap<u128, Vec<u128>>,
     #[case] expected_contracts_trie_sorted_indices: Vec<u128>,
     #[case] expected_classes_trie_sorted_indices: Vec<u128>,
+    #[values(false, true)] use_file_storage: bool,
 ) {
     let (mut storage_tries_indices, mut contracts_trie_indices, mut classes_trie_indices) =
         get_all_modified_indices(&input.state_diff);
@@ -280,15 +284,33 @@ fn test_create_original_skeleton_forest(
         contracts_trie_sorted_indices: SortedLeafIndices::new(&mut contracts_trie_indices),
         classes_trie_sorted_indices: SortedLeafIndices::new(&mut classes_trie_indices),
     };
-    let (actual_forest, original_contracts_trie_leaves) = OriginalSkeletonForest::create(
-        MapStorage::from(input.storage),
-        input.contracts_trie_root_hash,
-        input.classes_trie_root_hash,
-        &input.state_diff.actual_storage_updates(),
-        &input.state_diff.actual_classes_updates(),
-        &forest_sorted_indices,
-        &ConfigImpl::new(false, log::LevelFilter::Debug),
-    )
+    let actual_storage_updates = input.state_diff.actual_storage_updates();
+    let actual_classes_updates = input.state_diff.actual_classes_updates();
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug);
+    let (actual_forest, original_contracts_trie_leaves) = if use_file_storage {
+        let (_storage_dir, file_storage) = create_file_storage(input.storage);
+        OriginalSkeletonForest::create(
+            &file_storage,
+            input.contracts_trie_root_hash,
+            input.classes_trie_root_hash,
+            &actual_storage_updates,
+            &actual_classes_updates,
+            &forest_sorted_indices,
+            &config,
+        )
+        .await
+    } else {
+        OriginalSkeletonForest::create(
+            &MapStorage::from(input.storage),
+            input.contracts_trie_root_hash,
+            input.classes_trie_root_hash,
+            &actual_storage_updates,
+            &actual_classes_updates,
+            &forest_sorted_indices,
+            &config,
+        )
+        .await
+    }
     .unwrap();
     let expected_original_contracts_trie_leaves = expected_original_contracts_trie_leaves
         .into_iter()
@@ -6,7 +6,7 @@ use crate::patricia_merkle_tree::original_skeleton_tree::config::OriginalSkeleto
 use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
 use crate::patricia_merkle_tree::original_skeleton_tree::node::OriginalSkeletonNode;
 use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
-use crate::storage::storage_trait::Storage;
+use crate::storage::storage_trait::AsyncStorage;
 
 pub(crate) type OriginalSkeletonNodeMap = HashMap<NodeIndex, OriginalSkeletonNode>;
 pub(crate) type OriginalSkeletonTreeResult<T> = Result<T, OriginalSkeletonTreeError>;
@@ -16,8 +16,8 @@ pub(crate) type OriginalSkeletonTreeResult<T> = Result<T, OriginalSkeletonTreeEr
 /// update. It also contains the hashes (for edge siblings - also the edge data) of the unmodified
 /// nodes on the Merkle paths from the updated leaves to the root.
 pub(crate) trait OriginalSkeletonTree<'a>: Sized {
-    fn create<L: Leaf>(
-        storage: &impl Storage,
+    async fn create<L: Leaf>(
+        storage: &impl AsyncStorage,
         root_hash: HashOutput,
         sorted_leaf_indices: SortedLeafIndices<'a>,
         config: &impl OriginalSkeletonTreeConfig<L>,
@@ -27,13 +27,6 @@ pub(crate) trait OriginalSkeletonTree<'a>: Sized {
 
     fn get_nodes_mut(&mut self) -> &mut OriginalSkeletonNodeMap;
 
-    fn create_and_get_previous_leaves<L: Leaf>(
-        storage: &impl Storage,
-        root_hash: HashOutput,
-        sorted_leaf_indices: SortedLeafIndices<'a>,
-        config: &impl OriginalSkeletonTreeConfig<L>,
-    ) -> OriginalSkeletonTreeResult<(Self, HashMap<NodeIndex, L>)>;
-
     #[allow(dead_code)]
     fn get_sorted_leaf_indices(&self) -> SortedLeafIndices<'a>;
 }
@@ -46,13 +39,22 @@ pub(crate) struct OriginalSkeletonTreeImpl<'a> {
 }
 
 impl<'a> OriginalSkeletonTree<'a> for OriginalSkeletonTreeImpl<'a> {
-    fn create<L: Leaf>(
-        storage: &impl Storage,
+    async fn create<L: Leaf>(
+        storage: &impl AsyncStorage,
         root_hash: HashOutput,
         sorted_leaf_indices: SortedLeafIndices<'a>,
         config: &impl OriginalSkeletonTreeConfig<L>,
     ) -> OriginalSkeletonTreeResult<Self> {
-        Self::create_impl(storage, root_hash, sorted_leaf_indices, config)
+        let (skeleton_tree, _) = Self::create_many(
+            storage,
+            &[(root_hash, sorted_leaf_indices, config)],
+            false,
+            None,
+        )
+        .await?
+        .pop()
+        .expect("A skeleton is created for each given tree.");
+        Ok(skeleton_tree)
     }
 
     fn get_nodes(&self) -> &OriginalSkeletonNodeMap {
@@ -63,15 +65,6 @@ impl<'a> OriginalSkeletonTree<'a> for OriginalSkeletonTreeImpl<'a> {
         &mut self.nodes
     }
 
-    fn create_and_get_previous_leaves<L: Leaf>(
-        storage: &impl Storage,
-        root_hash: HashOutput,
-        sorted_leaf_indices: SortedLeafIndices<'a>,
-        config: &impl OriginalSkeletonTreeConfig<L>,
-    ) -> OriginalSkeletonTreeResult<(Self, HashMap<NodeIndex, L>)> {
-        Self::create_and_get_previous_leaves_impl(storage, root_hash, sorted_leaf_indices, config)
-    }
-
     fn get_sorted_leaf_indices(&self) -> SortedLeafIndices<'a> {
         self.sorted_leaf_indices
     }
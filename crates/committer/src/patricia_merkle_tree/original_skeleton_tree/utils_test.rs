@@ -89,12 +89,12 @@ fn test_split_leaves_big_tree(mut random: ThreadRng) {
             left_leaf_indices
                 .clone()
                 .into_iter()
-                .map(NodeIndex::new)
+                .map(|index| NodeIndex::new(index).unwrap())
                 .collect::<Vec<NodeIndex>>(),
             right_leaf_indices
                 .clone()
                 .into_iter()
-                .map(NodeIndex::new)
+                .map(|index| NodeIndex::new(index).unwrap())
                 .collect(),
         ]
         .concat(),
@@ -0,0 +1,413 @@
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::{TraversalError, TraversalResult};
+use crate::patricia_merkle_tree::filled_tree::node::FilledNode;
+use crate::patricia_merkle_tree::node_data::inner_node::{
+    BinaryData, EdgeData, NodeData, PathToBottom,
+};
+use crate::patricia_merkle_tree::node_data::leaf::Leaf;
+use crate::patricia_merkle_tree::original_skeleton_tree::create_tree::SubTree;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
+use crate::storage::errors::StorageError;
+use crate::storage::storage_trait::{create_db_key, AsyncStorage, StarknetPrefix, StorageKey};
+
+#[cfg(test)]
+#[path = "traversal_test.rs"]
+pub mod traversal_test;
+
+/// Returns the storage key of the node with the given hash.
+pub(crate) fn node_db_key<L: Leaf>(hash: HashOutput, is_leaf: bool) -> StorageKey {
+    create_db_key(
+        if is_leaf {
+            L::prefix()
+        } else {
+            StarknetPrefix::InnerNode.to_storage_prefix()
+        },
+        &hash.0.to_bytes_be(),
+    )
+}
+
+/// Reads the node with the given hash, located at the given index, from storage.
+pub(crate) async fn fetch_node<L: Leaf>(
+    storage: &impl AsyncStorage,
+    hash: HashOutput,
+    index: NodeIndex,
+) -> TraversalResult<FilledNode<L>> {
+    let is_leaf = index.is_leaf();
+    let db_key = node_db_key::<L>(hash, is_leaf);
+    let value = storage
+        .get(&db_key)
+        .await?
+        .ok_or(StorageError::MissingKey(db_key))?;
+    Ok(FilledNode::deserialize(hash, &value, is_leaf)?)
+}
+
+/// Reads the nodes with the given indices and hashes from storage, with a single storage access.
+pub(crate) async fn fetch_nodes<L: Leaf>(
+    storage: &impl AsyncStorage,
+    nodes: &[(NodeIndex, HashOutput)],
+) -> TraversalResult<Vec<FilledNode<L>>> {
+    let db_keys: Vec<StorageKey> = nodes
+        .iter()
+        .map(|(index, hash)| node_db_key::<L>(*hash, index.is_leaf()))
+        .collect();
+    let values = storage.mget(&db_keys).await?;
+    if let Some((index, _)) = nodes.get(values.len()) {
+        return Err(TraversalError::MissingFetchedNode(*index));
+    }
+    let mut filled_nodes = Vec::with_capacity(nodes.len());
+    for (((index, hash), value), db_key) in nodes.iter().zip(values).zip(db_keys) {
+        let value = value.ok_or(StorageError::MissingKey(db_key))?;
+        filled_nodes.push(FilledNode::deserialize(*hash, &value, index.is_leaf())?);
+    }
+    Ok(filled_nodes)
+}
+
+/// Returns the index of the bottom of the given edge, which is at the given index. Fails if the edge
+/// is empty or descends below the leaves.
+fn edge_bottom_index(
+    index: NodeIndex,
+    path_to_bottom: &PathToBottom,
+) -> TraversalResult<NodeIndex> {
+    if index.is_leaf() || u8::from(path_to_bottom.length) == 0 {
+        return Err(TraversalError::InvalidNode(index));
+    }
+    path_to_bottom
+        .bottom_index(index)
+        .map_err(|_| TraversalError::InvalidNode(index))
+}
+
+/// Reads from storage the nodes on the paths from the root of the tree towards the given leaves,
+/// layer by layer, with a single storage access per layer. Each fetched node is passed to `visit`
+/// along with its index; subtrees which hold none of the leaves are not fetched.
+pub(crate) async fn fetch_paths<L: Leaf>(
+    storage: &impl AsyncStorage,
+    root_hash: HashOutput,
+    sorted_leaf_indices: SortedLeafIndices<'_>,
+    mut visit: impl FnMut(NodeIndex, FilledNode<L>),
+) -> TraversalResult<()> {
+    if root_hash == HashOutput::ROOT_OF_EMPTY_TREE || sorted_leaf_indices.is_empty() {
+        return Ok(());
+    }
+    let mut subtrees = vec![SubTree {
+        sorted_leaf_indices,
+        root_index: NodeIndex::ROOT,
+        root_hash,
+    }];
+    while !subtrees.is_empty() {
+        let nodes_to_fetch: Vec<(NodeIndex, HashOutput)> = subtrees
+            .iter()
+            .map(|subtree| (subtree.root_index, subtree.root_hash))
+            .collect();
+        let filled_roots = fetch_nodes::<L>(storage, &nodes_to_fetch).await?;
+        let mut next_subtrees = Vec::new();
+        for (filled_root, subtree) in filled_roots.into_iter().zip(subtrees.iter()) {
+            match &filled_root.data {
+                NodeData::Binary(BinaryData {
+                    left_hash,
+                    right_hash,
+                }) => {
+                    if subtree.root_index.is_leaf() {
+                        return Err(TraversalError::InvalidNode(subtree.root_index));
+                    }
+                    let (left_subtree, right_subtree) =
+                        subtree.get_children_subtrees(*left_hash, *right_hash);
+                    next_subtrees.extend(
+                        [left_subtree, right_subtree]
+                            .into_iter()
+                            .filter(|child| !child.is_unmodified()),
+                    );
+                }
+                NodeData::Edge(EdgeData {
+                    bottom_hash,
+                    path_to_bottom,
+                }) => {
+                    edge_bottom_index(subtree.root_index, path_to_bottom)?;
+                    let (bottom_subtree, _) = subtree
+                        .get_bottom_subtree(path_to_bottom, *bottom_hash)
+                        .map_err(|_| TraversalError::InvalidNode(subtree.root_index))?;
+                    if !bottom_subtree.is_unmodified() {
+                        next_subtrees.push(bottom_subtree);
+                    }
+                }
+                NodeData::Leaf(_) => {}
+            }
+            visit(subtree.root_index, filled_root);
+        }
+        subtrees = next_subtrees;
+    }
+    Ok(())
+}
+
+/// Iterates over the leaves of a tree in ascending index order, optionally starting from a given
+/// leaf index, reading the nodes from storage on the fly. The children of each binary node are
+/// fetched together, with a single storage access.
+pub struct LeafIterator<'a, L: Leaf, S: AsyncStorage> {
+    storage: &'a S,
+    // The root hash, until the root is fetched.
+    root_hash: Option<HashOutput>,
+    // Leaves before this index are skipped.
+    start: NodeIndex,
+    // Fetched nodes which were not visited yet; the next node to visit is at the top.
+    stack: Vec<(NodeIndex, FilledNode<L>)>,
+}
+
+impl<'a, L: Leaf, S: AsyncStorage> LeafIterator<'a, L, S> {
+    /// Creates an iterator over the leaves of the tree with the given root, starting from the given
+    /// leaf index (inclusive), or from the first leaf if no index is given.
+    pub fn new(
+        storage: &'a S,
+        root_hash: HashOutput,
+        start: Option<NodeIndex>,
+    ) -> TraversalResult<Self> {
+        let start = start.unwrap_or(NodeIndex::FIRST_LEAF);
+        if !start.is_leaf() {
+            return Err(TraversalError::NonLeafStart(start));
+        }
+        Ok(Self {
+            storage,
+            root_hash: (root_hash != HashOutput::ROOT_OF_EMPTY_TREE).then_some(root_hash),
+            start,
+            stack: Vec::new(),
+        })
+    }
+
+    /// Returns the next leaf and its index, or None if all leaves were visited.
+    pub async fn next(&mut self) -> TraversalResult<Option<(NodeIndex, L)>> {
+        if let Some(root_hash) = self.root_hash.take() {
+            self.fetch_and_push(&[(NodeIndex::ROOT, root_hash)]).await?;
+        }
+        while let Some((index, node)) = self.stack.pop() {
+            match node.data {
+                NodeData::Leaf(leaf) => return Ok(Some((index, leaf))),
+                NodeData::Binary(BinaryData {
+                    left_hash,
+                    right_hash,
+                }) => {
+                    if index.is_leaf() {
+                        return Err(TraversalError::InvalidNode(index));
+                    }
+                    let [left_index, right_index] = index.get_children_indices();
+                    self.fetch_and_push(&[(left_index, left_hash), (right_index, right_hash)])
+                        .await?;
+                }
+                NodeData::Edge(EdgeData {
+                    bottom_hash,
+                    path_to_bottom,
+                }) => {
+                    let bottom_index = edge_bottom_index(index, &path_to_bottom)?;
+                    self.fetch_and_push(&[(bottom_index, bottom_hash)]).await?;
+                }
+            }
+        }
+        Ok(None)
+    }
+
+    /// Collects the remaining leaves.
+    pub async fn collect_remaining(&mut self) -> TraversalResult<Vec<(NodeIndex, L)>> {
+        let mut leaves = Vec::new();
+        while let Some(leaf) = self.next().await? {
+            leaves.push(leaf);
+        }
+        Ok(leaves)
+    }
+
+    /// Fetches the given sibling nodes (ordered by index), skipping the ones whose subtree is
+    /// entirely before the start, and pushes them to the stack.
+    async fn fetch_and_push(&mut self, nodes: &[(NodeIndex, HashOutput)]) -> TraversalResult<()> {
+        let nodes: Vec<(NodeIndex, HashOutput)> = nodes
+            .iter()
+            .copied()
+            .filter(|(index, _)| !self.is_before_start(index))
+            .collect();
+        let filled_nodes = fetch_nodes::<L>(self.storage, &nodes).await?;
+        self.stack.extend(
+            nodes
+                .into_iter()
+                .map(|(index, _)| index)
+                .zip(filled_nodes)
+                .rev(),
+        );
+        Ok(())
+    }
+
+    /// Returns true iff all leaves in the subtree of the given node are before the start.
+    fn is_before_start(&self, index: &NodeIndex) -> bool {
+        let height = NodeIndex::BITS - index.bit_length();
+        *index < self.start >> height
+    }
+}
+
+/// A leaf which differs between two trees.
+#[derive(Debug, Eq, PartialEq)]
+pub struct LeafDiff<L: Leaf> {
+    pub index: NodeIndex,
+    /// The leaf in the previous tree (empty if it is not in the tree).
+    pub previous: L,
+    /// The leaf in the new tree (empty if it is not in the tree).
+    pub new: L,
+}
+
+/// The subtree rooted at some index, in a top-down traversal. The subtree consists of the path from
+/// its root down to the bottom index, where the node with the bottom hash is.
+#[derive(Clone, Copy, Debug, Eq, PartialEq)]
+struct SubTreePointer {
+    bottom_index: NodeIndex,
+    bottom_hash: HashOutput,
+}
+
+impl SubTreePointer {
+    fn from_root(root_hash: HashOutput) -> Option<Self> {
+        (root_hash != HashOutput::ROOT_OF_EMPTY_TREE).then_some(Self {
+            bottom_index: NodeIndex::ROOT,
+            bottom_hash: root_hash,
+        })
+    }
+
+    /// Returns the subtrees of the children of the given (non-leaf) index, where the pointed node is
+    /// an ancestor or descendant of the index. The node is required iff it is at the given index.
+    fn get_children<L: Leaf>(
+        self,
+        index: NodeIndex,
+        node: Option<FilledNode<L>>,
+    ) -> TraversalResult<[Option<Self>; 2]> {
+        let descendant = match node.map(|node| node.data) {
+            None => self,
+            Some(NodeData::Binary(BinaryData {
+                left_hash,
+                right_hash,
+            })) => {
+                let [left_index, right_index] = index.get_children_indices();
+                return Ok([
+                    Some(Self {
+                        bottom_index: left_index,
+                        bottom_hash: left_hash,
+                    }),
+                    Some(Self {
+                        bottom_index: right_index,
+                        bottom_hash: right_hash,
+                    }),
+                ]);
+            }
+            Some(NodeData::Edge(EdgeData {
+                bottom_hash,
+                path_to_bottom,
+            })) => Self {
+                bottom_index: edge_bottom_index(index, &path_to_bottom)?,
+                bottom_hash,
+            },
+            Some(NodeData::Leaf(_)) => return Err(TraversalError::InvalidNode(index)),
+        };
+        // The number of layers between the child of the index and the bottom.
+        let child_depth = descendant
+            .bottom_index
+            .bit_length()
+            .checked_sub(index.bit_length() + 1)
+            .ok_or(TraversalError::InvalidNode(descendant.bottom_index))?;
+        let child_index = descendant.bottom_index >> child_depth;
+        Ok(if child_index == index.get_children_indices()[0] {
+            [Some(descendant), None]
+        } else {
+            [None, Some(descendant)]
+        })
+    }
+}
+
+/// Returns the leaves which differ between the trees with the given roots (sorted by index). Both
+/// trees are traversed together, layer by layer, with a single storage access per layer, and
+/// subtrees with equal hashes are skipped.
+pub async fn diff_leaves<L: Leaf>(
+    storage: &impl AsyncStorage,
+    previous_root_hash: HashOutput,
+    new_root_hash: HashOutput,
+) -> TraversalResult<Vec<LeafDiff<L>>> {
+    let roots = [
+        SubTreePointer::from_root(previous_root_hash),
+        SubTreePointer::from_root(new_root_hash),
+    ];
+    let mut layer: Vec<(NodeIndex, [Option<SubTreePointer>; 2])> = Vec::new();
+    if roots[0] != roots[1] {
+        layer.push((NodeIndex::ROOT, roots));
+    }
+    while !layer.is_empty() && !layer[0].0.is_leaf() {
+        let nodes_to_fetch: Vec<(NodeIndex, HashOutput)> = layer
+            .iter()
+            .flat_map(|(index, subtrees)| {
+                subtrees
+                    .iter()
+                    .flatten()
+                    .filter(|subtree| subtree.bottom_index == *index)
+                    .map(|subtree| (subtree.bottom_index, subtree.bottom_hash))
+            })
+            .collect();
+        let mut fetched_nodes = fetch_nodes::<L>(storage, &nodes_to_fetch)
+            .await?
+            .into_iter();
+
+        let mut next_layer = Vec::new();
+        for (index, subtrees) in layer {
+            let mut children = [[None, None]; 2];
+            for (subtree_children, subtree) in children.iter_mut().zip(subtrees) {
+                let Some(subtree) = subtree else {
+                    continue;
+                };
+                let node = if subtree.bottom_index == index {
+                    Some(
+                        fetched_nodes
+                            .next()
+                            .ok_or(TraversalError::MissingFetchedNode(index))?,
+                    )
+                } else {
+                    None
+                };
+                *subtree_children = subtree.get_children(index, node)?;
+            }
+            let [previous_children, new_children] = children;
+            for ((child_index, previous_child), new_child) in index
+                .get_children_indices()
+                .into_iter()
+                .zip(previous_children)
+                .zip(new_children)
+            {
+                if previous_child != new_child {
+                    next_layer.push((child_index, [previous_child, new_child]));
+                }
+            }
+        }
+        layer = next_layer;
+    }
+
+    // All remaining subtrees are leaves.
+    let leaves_to_fetch: Vec<(NodeIndex, HashOutput)> = layer
+        .iter()
+        .flat_map(|(_, subtrees)| subtrees.iter().flatten())
+        .map(|leaf| (leaf.bottom_index, leaf.bottom_hash))
+        .collect();
+    let mut fetched_leaves = fetch_nodes::<L>(storage, &leaves_to_fetch)
+        .await?
+        .into_iter();
+    let mut leaf_diffs = Vec::with_capacity(layer.len());
+    for (index, subtrees) in layer {
+        let mut leaves = [L::default(), L::default()];
+        for (leaf, subtree) in leaves.iter_mut().zip(subtrees) {
+            if subtree.is_none() {
+                continue;
+            }
+            let node = fetched_leaves
+                .next()
+                .ok_or(TraversalError::MissingFetchedNode(index))?;
+            match node.data {
+                NodeData::Leaf(fetched_leaf) => *leaf = fetched_leaf,
+                NodeData::Binary(_) | NodeData::Edge(_) => {
+                    return Err(TraversalError::InvalidNode(index));
+                }
+            }
+        }
+        let [previous, new] = leaves;
+        leaf_diffs.push(LeafDiff {
+            index,
+            previous,
+            new,
+        });
+    }
+    Ok(leaf_diffs)
+}
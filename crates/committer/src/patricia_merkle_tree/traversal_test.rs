@@ -0,0 +1,227 @@
+use ethnum::U256;
+use rstest::rstest;
+
+use crate::block_committer::input::StarknetStorageValue;
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TraversalError;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, FilledNode};
+use crate::patricia_merkle_tree::internal_test_utils::{address, create_committed_state};
+use crate::patricia_merkle_tree::merkle_proof::proof::proof_test::create_storage_trie;
+use crate::patricia_merkle_tree::node_data::inner_node::{
+    EdgeData, EdgePathLength, NodeData, PathToBottom,
+};
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::traversal::{diff_leaves, LeafDiff, LeafIterator};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::storage::db_object::DBObject;
+use crate::storage::errors::StorageError;
+use crate::storage::map_storage::MapStorage;
+
+const LEAVES: [(u128, u128); 5] = [(0, 1), (1, 2), (7, 3), (1 << 100, 4), (u128::MAX, 5)];
+
+fn leaf_index(key: u128) -> NodeIndex {
+    NodeIndex::FIRST_LEAF + NodeIndex::new(U256::from(key)).unwrap()
+}
+
+#[rstest]
+#[case::from_first_leaf(None, 0)]
+#[case::from_existing_leaf(Some(1), 1)]
+#[case::from_missing_leaf(Some(2), 2)]
+#[case::from_last_leaf(Some(u128::MAX), 4)]
+#[case::before_last_leaf(Some(u128::MAX - 1), 4)]
+#[tokio::test]
+async fn test_leaf_iterator(#[case] start_key: Option<u128>, #[case] first_expected: usize) {
+    let leaves: Vec<(U256, u128)> = LEAVES
+        .iter()
+        .map(|(key, value)| (U256::from(*key), *value))
+        .collect();
+    let (storage, root_hash) = create_storage_trie(&leaves).await;
+
+    let mut iterator = LeafIterator::<StarknetStorageValue, _>::new(
+        &storage,
+        root_hash,
+        start_key.map(leaf_index),
+    )
+    .unwrap();
+    let expected: Vec<(NodeIndex, StarknetStorageValue)> = LEAVES[first_expected..]
+        .iter()
+        .map(|(key, value)| (leaf_index(*key), StarknetStorageValue(Felt::from(*value))))
+        .collect();
+    assert_eq!(iterator.collect_remaining().await.unwrap(), expected);
+    assert!(iterator.next().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_leaf_iterator_past_last_leaf() {
+    let (storage, root_hash) = create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2)]).await;
+    let mut iterator =
+        LeafIterator::<StarknetStorageValue, _>::new(&storage, root_hash, Some(leaf_index(2)))
+            .unwrap();
+    assert!(iterator.next().await.unwrap().is_none());
+}
+
+#[tokio::test]
+async fn test_leaf_iterator_empty_tree() {
+    let storage = MapStorage::default();
+    let mut iterator = LeafIterator::<StarknetStorageValue, _>::new(
+        &storage,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        None,
+    )
+    .unwrap();
+    assert!(iterator.next().await.unwrap().is_none());
+}
+
+#[rstest]
+#[case::root(NodeIndex::ROOT)]
+#[case::inner_node(NodeIndex::FIRST_LEAF >> 1)]
+fn test_leaf_iterator_non_leaf_start(#[case] start: NodeIndex) {
+    let storage = MapStorage::default();
+    assert!(matches!(
+        LeafIterator::<StarknetStorageValue, _>::new(
+            &storage,
+            HashOutput::ROOT_OF_EMPTY_TREE,
+            Some(start)
+        ),
+        Err(TraversalError::NonLeafStart(index)) if index == start
+    ));
+}
+
+#[tokio::test]
+async fn test_leaf_iterator_contracts_trie() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+
+    let contracts = LeafIterator::<ContractState, _>::new(&storage, contracts_trie_root_hash, None)
+        .unwrap()
+        .collect_remaining()
+        .await
+        .unwrap();
+    let indices: Vec<NodeIndex> = contracts.iter().map(|(index, _)| *index).collect();
+    assert_eq!(
+        indices,
+        vec![
+            NodeIndex::from_contract_address(&address(1)),
+            NodeIndex::from_contract_address(&address(2))
+        ]
+    );
+    assert_eq!(contracts[0].1.class_hash, ClassHash(Felt::from(10_u128)));
+    assert_eq!(contracts[1].1.class_hash, ClassHash(Felt::from(11_u128)));
+
+    let classes = LeafIterator::<CompiledClassHash, _>::new(&storage, classes_trie_root_hash, None)
+        .unwrap()
+        .collect_remaining()
+        .await
+        .unwrap();
+    let compiled_class_hashes: Vec<CompiledClassHash> =
+        classes.into_iter().map(|(_, leaf)| leaf).collect();
+    assert_eq!(
+        compiled_class_hashes,
+        vec![
+            CompiledClassHash(Felt::from(100_u128)),
+            CompiledClassHash(Felt::from(101_u128))
+        ]
+    );
+}
+
+#[rstest]
+#[case::equal_trees(&[(0, 1), (5, 2)], &[(0, 1), (5, 2)], &[])]
+#[case::from_empty_tree(&[], &[(0, 1), (5, 2)], &[(0, 0, 1), (5, 0, 2)])]
+#[case::to_empty_tree(&[(0, 1), (5, 2)], &[], &[(0, 1, 0), (5, 2, 0)])]
+#[case::modified_leaf(&[(0, 1), (5, 2)], &[(0, 1), (5, 3)], &[(5, 2, 3)])]
+#[case::split_edge(&[(0, 1), (1 << 100, 2)], &[(0, 1), (6, 3), (1 << 100, 2)], &[(6, 0, 3)])]
+#[case::disjoint_trees(
+    &[(0, 1), (7, 2)],
+    &[(3, 3), (u128::MAX, 4)],
+    &[(0, 1, 0), (3, 0, 3), (7, 2, 0), (u128::MAX, 0, 4)]
+)]
+#[tokio::test]
+async fn test_diff_leaves(
+    #[case] previous_leaves: &[(u128, u128)],
+    #[case] new_leaves: &[(u128, u128)],
+    #[case] expected: &[(u128, u128, u128)],
+) {
+    let to_leaves = |leaves: &[(u128, u128)]| -> Vec<(U256, u128)> {
+        leaves
+            .iter()
+            .map(|(key, value)| (U256::from(*key), *value))
+            .collect()
+    };
+    let (mut storage, previous_root_hash) = create_storage_trie(&to_leaves(previous_leaves)).await;
+    let (new_storage, new_root_hash) = create_storage_trie(&to_leaves(new_leaves)).await;
+    storage.storage.extend(new_storage.storage);
+
+    let expected: Vec<LeafDiff<StarknetStorageValue>> = expected
+        .iter()
+        .map(|(key, previous, new)| LeafDiff {
+            index: leaf_index(*key),
+            previous: StarknetStorageValue(Felt::from(*previous)),
+            new: StarknetStorageValue(Felt::from(*new)),
+        })
+        .collect();
+    assert_eq!(
+        diff_leaves::<StarknetStorageValue>(&storage, previous_root_hash, new_root_hash)
+            .await
+            .unwrap(),
+        expected
+    );
+}
+
+/// Creates a storage holding a tree whose root is an edge of the given length, which is not a valid
+/// edge at the root if it is empty or longer than the height of the tree.
+fn create_tree_with_root_edge(length: u8) -> (MapStorage, HashOutput) {
+    let root: FilledNode<StarknetStorageValue> = FilledNode {
+        hash: HashOutput(Felt::from(17_u128)),
+        data: NodeData::Edge(EdgeData {
+            bottom_hash: HashOutput(Felt::ONE),
+            path_to_bottom: PathToBottom::new(
+                U256::ZERO.into(),
+                EdgePathLength::new(length).unwrap(),
+            )
+            .unwrap(),
+        }),
+    };
+    let mut storage = MapStorage::default();
+    storage.storage.insert(root.db_key(), root.serialize());
+    (storage, root.hash)
+}
+
+#[tokio::test]
+async fn test_leaf_iterator_invalid_edge() {
+    let (storage, root_hash) = create_tree_with_root_edge(0);
+    let mut iterator =
+        LeafIterator::<StarknetStorageValue, _>::new(&storage, root_hash, None).unwrap();
+    assert!(matches!(
+        iterator.next().await,
+        Err(TraversalError::InvalidNode(NodeIndex::ROOT))
+    ));
+}
+
+#[rstest]
+#[case::previous_tree(true)]
+#[case::new_tree(false)]
+#[tokio::test]
+async fn test_diff_leaves_invalid_edge(#[case] is_previous: bool) {
+    let (storage, root_hash) = create_tree_with_root_edge(0);
+    let (previous_root_hash, new_root_hash) = if is_previous {
+        (root_hash, HashOutput::ROOT_OF_EMPTY_TREE)
+    } else {
+        (HashOutput::ROOT_OF_EMPTY_TREE, root_hash)
+    };
+    assert!(matches!(
+        diff_leaves::<StarknetStorageValue>(&storage, previous_root_hash, new_root_hash).await,
+        Err(TraversalError::InvalidNode(NodeIndex::ROOT))
+    ));
+}
+
+#[tokio::test]
+async fn test_diff_leaves_missing_node() {
+    let (storage, root_hash) = create_tree_with_root_edge(1);
+    // The bottom of the root edge is not in storage.
+    assert!(matches!(
+        diff_leaves::<StarknetStorageValue>(&storage, HashOutput::ROOT_OF_EMPTY_TREE, root_hash)
+            .await,
+        Err(TraversalError::StorageRead(StorageError::MissingKey(_)))
+    ));
+}
@@ -2,6 +2,7 @@ use crate::block_committer::input::{ContractAddress, StarknetStorageKey};
 use crate::felt::Felt;
 use crate::patricia_merkle_tree::errors::TypesError;
 use crate::patricia_merkle_tree::filled_tree::node::ClassHash;
+use crate::patricia_merkle_tree::node_data::errors::PathToBottomError;
 use crate::patricia_merkle_tree::node_data::inner_node::{EdgePathLength, PathToBottom};
 
 use ethnum::U256;
@@ -54,22 +55,53 @@ impl NodeIndex {
         u128::MAX,
     ));
 
-    pub fn new(index: U256) -> Self {
-        assert!(index <= Self::MAX.0, "Index {index} is too large.");
-        Self(index)
+    pub fn new(index: U256) -> Result<Self, TypesError<U256>> {
+        if index > Self::MAX.0 {
+            return Err(TypesError::OutOfRange {
+                value: index,
+                max: Self::MAX.0,
+            });
+        }
+        Ok(Self(index))
+    }
+
+    /// Creates an index from the result of index arithmetic, which (as with primitive integers)
+    /// panics on overflow. Indices read from the storage or given by the caller go through the
+    /// checked conversions ([Self::compute_bottom_index], [Self::checked_shl] and the `try_from_*`
+    /// functions) instead.
+    fn from_arithmetic_result(index: U256) -> Self {
+        Self::new(index).unwrap_or_else(|error| panic!("Node index overflow: {error}"))
     }
 
     pub(crate) fn is_leaf(&self) -> bool {
         Self::FIRST_LEAF <= *self && *self <= Self::MAX
     }
 
+    /// Returns the index of the bottom of the given path from the given index, or an error if the
+    /// path descends below the leaves.
     // TODO(Amos, 1/5/2024): Move to EdgePath.
     pub(crate) fn compute_bottom_index(
         index: NodeIndex,
         path_to_bottom: &PathToBottom,
-    ) -> NodeIndex {
+    ) -> Result<NodeIndex, PathToBottomError> {
         let PathToBottom { path, length, .. } = path_to_bottom;
-        (index << u8::from(*length)) + Self::new(path.into())
+        index
+            .checked_shl(u8::from(*length))
+            // The path is shorter than its length, so adding it to the shifted index cannot carry.
+            .map(|shifted_index| Self(shifted_index.0 + U256::from(path)))
+            .ok_or(PathToBottomError::BottomOutOfRange {
+                root_index: index,
+                path_to_bottom: *path_to_bottom,
+            })
+    }
+
+    /// Returns the index of the left descendant at the given distance below the node, or None if
+    /// it is below the leaves.
+    pub fn checked_shl(self, rhs: u8) -> Option<Self> {
+        if rhs > Self::BITS - self.bit_length() {
+            return None;
+        }
+        Some(Self(self.0 << rhs))
     }
 
     pub(crate) fn get_children_indices(&self) -> [Self; 2] {
@@ -88,6 +120,14 @@ impl NodeIndex {
         Self::BITS - self.leading_zeros()
     }
 
+    /// Returns true iff the node is an ancestor of the given node, or the node itself.
+    pub(crate) fn is_ancestor_of(&self, descendant: &Self) -> bool {
+        let bit_length = self.bit_length();
+        let descendant_bit_length = descendant.bit_length();
+        bit_length <= descendant_bit_length
+            && *descendant >> (descendant_bit_length - bit_length) == *self
+    }
+
     /// Get the LCA (Lowest Common Ancestor) of the two nodes.
     pub(crate) fn get_lca(&self, other: &NodeIndex) -> NodeIndex {
         if self == other {
@@ -105,52 +145,78 @@ impl NodeIndex {
 
         let xor = adapted_self.0 ^ adapted_other.0;
         // The length of the remainder after removing the common prefix of the two nodes.
-        let post_common_prefix_len = NodeIndex::new(xor).bit_length();
+        let post_common_prefix_len = Self(xor).bit_length();
 
-        let lca = adapted_self.0 >> post_common_prefix_len;
-        NodeIndex::new(lca)
+        adapted_self >> post_common_prefix_len
     }
 
     /// Returns the path from the node to its given descendant (0 length if node == descendant).
-    /// Panics if the supposed descendant is not really a descendant.
-    pub(crate) fn get_path_to_descendant(&self, descendant: Self) -> PathToBottom {
-        let descendant_bit_length = descendant.bit_length();
-        let bit_length = self.bit_length();
-        if bit_length > descendant_bit_length {
-            panic!("The descendant is not a really descendant of the node.");
+    pub(crate) fn get_path_to_descendant(
+        &self,
+        descendant: Self,
+    ) -> Result<PathToBottom, TypesError<Self>> {
+        let not_a_descendant = || TypesError::NotADescendant {
+            ancestor: *self,
+            descendant,
         };
+        if !self.is_ancestor_of(&descendant) {
+            return Err(not_a_descendant());
+        }
 
-        let distance = descendant_bit_length - bit_length;
+        let distance = descendant.bit_length() - self.bit_length();
         let delta = descendant - (*self << distance);
-        if descendant >> distance != *self {
-            panic!("The descendant is not a really descendant of the node.");
-        };
+        let length = EdgePathLength::new(distance).map_err(|_| not_a_descendant())?;
+        PathToBottom::new(delta.0.into(), length).map_err(|_| not_a_descendant())
+    }
+
+    /// Returns the index of the leaf of the given key, or an error if the key is out of the range
+    /// of the tree (felts of at least 2^251).
+    pub fn try_from_starknet_storage_key(
+        key: &StarknetStorageKey,
+    ) -> Result<Self, TypesError<U256>> {
+        Self::try_from_leaf_felt(&key.0)
+    }
+
+    /// Returns the index of the leaf of the given address, or an error if the address is out of
+    /// the range of the tree (felts of at least 2^251).
+    pub fn try_from_contract_address(address: &ContractAddress) -> Result<Self, TypesError<U256>> {
+        Self::try_from_leaf_felt(&address.0)
+    }
 
-        PathToBottom::new(
-            delta.0.into(),
-            EdgePathLength::new(distance).expect("Illegal length"),
-        )
-        .expect("Illegal PathToBottom")
+    /// Returns the index of the leaf of the given class hash, or an error if the class hash is out
+    /// of the range of the tree (felts of at least 2^251).
+    pub fn try_from_class_hash(class_hash: &ClassHash) -> Result<Self, TypesError<U256>> {
+        Self::try_from_leaf_felt(&class_hash.0)
     }
 
+    /// Same as [Self::try_from_starknet_storage_key], for keys already known to be in range (e.g.,
+    /// keys of a validated state diff).
     pub(crate) fn from_starknet_storage_key(key: &StarknetStorageKey) -> Self {
         Self::from_leaf_felt(&key.0)
     }
 
+    /// Same as [Self::try_from_contract_address], for addresses already known to be in range.
     pub(crate) fn from_contract_address(address: &ContractAddress) -> Self {
         Self::from_leaf_felt(&address.0)
     }
 
+    /// Same as [Self::try_from_class_hash], for class hashes already known to be in range.
     pub(crate) fn from_class_hash(class_hash: &ClassHash) -> Self {
         Self::from_leaf_felt(&class_hash.0)
     }
 
     fn from_leaf_felt(felt: &Felt) -> Self {
-        Self::FIRST_LEAF + Self::from_felt_value(felt)
+        Self::try_from_leaf_felt(felt).expect("The felt of a leaf is out of the range of the tree.")
     }
 
-    fn from_felt_value(felt: &Felt) -> Self {
-        Self(U256::from(felt))
+    fn try_from_leaf_felt(felt: &Felt) -> Result<Self, TypesError<U256>> {
+        Self::new(Self::FIRST_LEAF.0 + U256::from(felt))
+    }
+
+    /// Returns the felt (key, address or class hash) of the leaf at this index. Assumes the index
+    /// is a leaf index.
+    pub(crate) fn to_leaf_felt(self) -> Felt {
+        Felt::try_from(self - Self::FIRST_LEAF).expect("Leaf index offsets fit in a felt.")
     }
 }
 
@@ -158,7 +224,7 @@ impl std::ops::Add for NodeIndex {
     type Output = Self;
 
     fn add(self, rhs: Self) -> Self {
-        Self::new(self.0 + rhs.0)
+        Self::from_arithmetic_result(self.0 + rhs.0)
     }
 }
 
@@ -166,7 +232,7 @@ impl std::ops::Mul for NodeIndex {
     type Output = Self;
 
     fn mul(self, rhs: Self) -> Self {
-        Self::new(self.0 * rhs.0)
+        Self::from_arithmetic_result(self.0 * rhs.0)
     }
 }
 
@@ -183,7 +249,7 @@ impl std::ops::Shl<u8> for NodeIndex {
 
     /// Returns the index of the left descendant (child for rhs=1) of the node.
     fn shl(self, rhs: u8) -> Self::Output {
-        Self::new(self.0 << rhs)
+        Self::from_arithmetic_result(self.0 << rhs)
     }
 }
 
@@ -192,13 +258,13 @@ impl std::ops::Shr<u8> for NodeIndex {
 
     /// Returns the index of the ancestor (parent for rhs=1) of the node.
     fn shr(self, rhs: u8) -> Self::Output {
-        Self::new(self.0 >> rhs)
+        Self::from_arithmetic_result(self.0 >> rhs)
     }
 }
 
 impl From<u128> for NodeIndex {
     fn from(value: u128) -> Self {
-        Self::new(U256::from(value))
+        Self::from_arithmetic_result(U256::from(value))
     }
 }
 
@@ -224,15 +290,30 @@ impl TryFrom<NodeIndex> for Felt {
     }
 }
 
+/// A trie of the Starknet state.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum TrieId {
+    ContractsTrie,
+    ClassesTrie,
+    StorageTrie(ContractAddress),
+}
+
 #[derive(Clone, Copy, Debug, Default, PartialEq)]
 pub(crate) struct SortedLeafIndices<'a>(&'a [NodeIndex]);
 
 impl<'a> SortedLeafIndices<'a> {
-    /// Creates a new instance by sorting the given indices.
-    // TODO(Nimrod, 1/8/2024): Remove duplicates from the given indices.
+    /// Creates a new instance by sorting the given indices, and moving the duplicates to the end of
+    /// the slice, out of the instance.
     pub(crate) fn new(indices: &'a mut [NodeIndex]) -> Self {
         indices.sort();
-        Self(indices)
+        let mut unique_count = 0;
+        for position in 0..indices.len() {
+            if unique_count == 0 || indices[position] != indices[unique_count - 1] {
+                indices.swap(unique_count, position);
+                unique_count += 1;
+            }
+        }
+        Self(&indices[..unique_count])
     }
 
     /// Returns a subslice of the indices stored at self, at the range [leftmost_idx, rightmost_idx).
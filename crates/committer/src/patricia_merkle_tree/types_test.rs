@@ -1,9 +1,12 @@
 use crate::block_committer::input::{ContractAddress, StarknetStorageKey};
 use crate::felt::Felt;
+use crate::patricia_merkle_tree::errors::TypesError;
 use crate::patricia_merkle_tree::external_test_utils::get_random_u256;
+use crate::patricia_merkle_tree::filled_tree::node::ClassHash;
 use crate::patricia_merkle_tree::internal_test_utils::random;
+use crate::patricia_merkle_tree::node_data::errors::PathToBottomError;
 use crate::patricia_merkle_tree::node_data::inner_node::{EdgePathLength, PathToBottom};
-use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices, SubTreeHeight};
 
 use ethnum::{uint, U256};
 use rand::rngs::ThreadRng;
@@ -24,7 +27,8 @@ fn test_compute_bottom_index(
     let bottom_index = NodeIndex::compute_bottom_index(
         NodeIndex::from(node_index),
         &PathToBottom::new(path.into(), EdgePathLength::new(length).unwrap()).unwrap(),
-    );
+    )
+    .unwrap();
     let expected = NodeIndex::from(expected);
     assert_eq!(bottom_index, expected);
 }
@@ -67,27 +71,43 @@ fn test_cast_to_node_index(
     uint!("3")
 )]
 fn test_get_lca(#[case] node_index: U256, #[case] other: U256, #[case] expected: U256) {
-    let root_index = NodeIndex::new(node_index);
-    let other_index = NodeIndex::new(other);
+    let root_index = NodeIndex::new(node_index).unwrap();
+    let other_index = NodeIndex::new(other).unwrap();
     let lca = root_index.get_lca(&other_index);
-    let expected = NodeIndex::new(expected);
+    let expected = NodeIndex::new(expected).unwrap();
     assert_eq!(lca, expected);
 }
 
+#[rstest]
+#[case(1, 1, true)]
+#[case(1, 5, true)]
+#[case(2, 5, true)]
+#[case(3, 5, false)]
+#[case(5, 2, false)]
+#[case(0xDAD, 0xDADFEE, true)]
+#[case(0xDAE, 0xDADFEE, false)]
+fn test_is_ancestor_of(#[case] node_index: u128, #[case] other: u128, #[case] expected: bool) {
+    assert_eq!(
+        NodeIndex::from(node_index).is_ancestor_of(&NodeIndex::from(other)),
+        expected
+    );
+}
+
 #[rstest]
 fn test_get_lca_big(mut random: ThreadRng) {
     let lca = NodeIndex::new(get_random_u256(
         &mut random,
         U256::ZERO,
         (NodeIndex::MAX >> 1).into(),
-    ));
+    ))
+    .unwrap();
 
     let left_child = lca << 1;
     let right_child = left_child + 1;
     let mut random_extension = |index: NodeIndex| {
         let extension_bits = index.leading_zeros();
         let extension: u128 = random.gen_range(0..(1 << extension_bits));
-        (index << extension_bits) + NodeIndex::new(U256::from(extension))
+        (index << extension_bits) + NodeIndex::new(U256::from(extension)).unwrap()
     };
 
     let left_descendant = random_extension(left_child);
@@ -98,21 +118,15 @@ fn test_get_lca_big(mut random: ThreadRng) {
 #[rstest]
 #[case(3, 3, 0, 0)]
 #[case(2, 10, 2, 2)]
-#[should_panic]
-#[case(2, 3, 0, 0)]
-#[should_panic]
-#[case(2, 6, 0, 0)]
-#[should_panic]
-#[case(6, 2, 0, 0)]
 fn test_get_path_to_descendant(
     #[case] root_index: u8,
     #[case] descendant: u8,
     #[case] expected_path: u8,
     #[case] expected_length: u8,
 ) {
-    let root_index = NodeIndex::new(root_index.into());
-    let descendant = NodeIndex::new(descendant.into());
-    let path_to_bottom = root_index.get_path_to_descendant(descendant);
+    let root_index = NodeIndex::new(root_index.into()).unwrap();
+    let descendant = NodeIndex::new(descendant.into()).unwrap();
+    let path_to_bottom = root_index.get_path_to_descendant(descendant).unwrap();
     assert_eq!(path_to_bottom.path, U256::from(expected_path).into());
     assert_eq!(
         path_to_bottom.length,
@@ -120,15 +134,29 @@ fn test_get_path_to_descendant(
     );
 }
 
+#[rstest]
+#[case::sibling(2, 3)]
+#[case::sibling_descendant(2, 6)]
+#[case::ancestor(6, 2)]
+fn test_get_path_to_non_descendant(#[case] root_index: u8, #[case] descendant: u8) {
+    let root_index = NodeIndex::new(root_index.into()).unwrap();
+    let descendant = NodeIndex::new(descendant.into()).unwrap();
+    assert!(matches!(
+        root_index.get_path_to_descendant(descendant),
+        Err(TypesError::NotADescendant { ancestor, descendant: actual_descendant })
+            if ancestor == root_index && actual_descendant == descendant
+    ));
+}
+
 #[rstest]
 fn test_get_path_to_descendant_big() {
-    let root_index = NodeIndex::new(U256::from(rand::thread_rng().gen::<u128>()));
+    let root_index = NodeIndex::new(U256::from(rand::thread_rng().gen::<u128>())).unwrap();
     let max_bits = NodeIndex::BITS - 128;
     let extension: u128 = rand::thread_rng().gen_range(0..1 << max_bits);
-    let extension_index = NodeIndex::new(U256::from(extension));
+    let extension_index = NodeIndex::new(U256::from(extension)).unwrap();
 
     let descendant = (root_index << extension_index.bit_length()) + extension_index;
-    let path_to_bottom = root_index.get_path_to_descendant(descendant);
+    let path_to_bottom = root_index.get_path_to_descendant(descendant).unwrap();
     assert_eq!(path_to_bottom.path, extension.into());
     assert_eq!(
         path_to_bottom.length,
@@ -136,14 +164,98 @@ fn test_get_path_to_descendant_big() {
     );
 }
 
+#[rstest]
+#[case::leaf(NodeIndex::FIRST_LEAF, 1)]
+#[case::child_of_root(NodeIndex::from(2), SubTreeHeight::ACTUAL_HEIGHT.into())]
+#[case::deep_node(NodeIndex::MAX >> 5, 6)]
+fn test_compute_bottom_index_below_leaves(#[case] index: NodeIndex, #[case] length: u8) {
+    let path_to_bottom =
+        PathToBottom::new(U256::ZERO.into(), EdgePathLength::new(length).unwrap()).unwrap();
+    assert!(matches!(
+        NodeIndex::compute_bottom_index(index, &path_to_bottom),
+        Err(PathToBottomError::BottomOutOfRange { root_index, path_to_bottom: path })
+            if root_index == index && path == path_to_bottom
+    ));
+}
+
+#[rstest]
+#[case::root_to_first_leaf(NodeIndex::ROOT, NodeIndex::BITS - 1, Some(NodeIndex::FIRST_LEAF))]
+#[case::root_below_leaves(NodeIndex::ROOT, NodeIndex::BITS, None)]
+#[case::leaf(NodeIndex::MAX, 0, Some(NodeIndex::MAX))]
+#[case::leaf_below_leaves(NodeIndex::MAX, 1, None)]
+fn test_checked_shl(
+    #[case] index: NodeIndex,
+    #[case] rhs: u8,
+    #[case] expected: Option<NodeIndex>,
+) {
+    assert_eq!(index.checked_shl(rhs), expected);
+}
+
+#[rstest]
+#[case::two_to_251(Felt::from_hex(
+    "0x800000000000000000000000000000000000000000000000000000000000000"
+).unwrap())]
+#[case::max(Felt::MAX)]
+fn test_leaf_felt_out_of_range(#[case] felt: Felt) {
+    assert!(matches!(
+        NodeIndex::try_from_starknet_storage_key(&StarknetStorageKey(felt)),
+        Err(TypesError::OutOfRange { .. })
+    ));
+    assert!(matches!(
+        NodeIndex::try_from_contract_address(&ContractAddress(felt)),
+        Err(TypesError::OutOfRange { .. })
+    ));
+    assert!(matches!(
+        NodeIndex::try_from_class_hash(&ClassHash(felt)),
+        Err(TypesError::OutOfRange { .. })
+    ));
+}
+
+#[rstest]
+fn test_node_index_out_of_range() {
+    let index = NodeIndex::MAX.0 + 1;
+    assert!(matches!(
+        NodeIndex::new(index),
+        Err(TypesError::OutOfRange { value, max }) if value == index && max == NodeIndex::MAX.0
+    ));
+}
+
 #[rstest]
 fn test_nodeindex_to_felt_conversion() {
     let index = NodeIndex::MAX;
     assert!(Felt::try_from(index).is_err());
 }
 
+#[rstest]
+#[case::zero(Felt::ZERO)]
+#[case::small(Felt::from(17_u8))]
+#[case::largest_leaf(Felt::from_hex(
+    "0x7ffffffffffffffffffffffffffffffffffffffffffffffffffffffffffffff"
+).unwrap())]
+fn test_leaf_index_to_felt_round_trip(#[case] felt: Felt) {
+    let index = NodeIndex::from_starknet_storage_key(&StarknetStorageKey(felt));
+    assert_eq!(index.to_leaf_felt(), felt);
+}
+
 #[rstest]
 fn test_felt_printing() {
     let felt = Felt::from(17_u8);
     assert_eq!(format!("{:?}", felt), "17");
 }
+
+#[rstest]
+#[case::no_indices(&[], &[])]
+#[case::unique_indices(&[7, 3, 5], &[3, 5, 7])]
+#[case::duplicate_indices(&[5, 3, 5, 7, 3, 5], &[3, 5, 7])]
+#[case::single_repeated_index(&[4, 4, 4], &[4])]
+fn test_sorted_leaf_indices(#[case] indices: &[u128], #[case] expected_indices: &[u128]) {
+    let to_leaf_index = |index: &u128| NodeIndex::FIRST_LEAF + *index;
+    let mut leaf_indices: Vec<NodeIndex> = indices.iter().map(to_leaf_index).collect();
+    assert_eq!(
+        SortedLeafIndices::new(&mut leaf_indices).get_indices(),
+        expected_indices
+            .iter()
+            .map(to_leaf_index)
+            .collect::<Vec<_>>()
+    );
+}
@@ -37,24 +37,29 @@ impl TempSkeletonNode {
 }
 
 /// Returns the path from the given root_index to the LCA of the given subtree node indices.
-/// Assumes the nodes are:
-/// * Descendants of the given index.
-/// * A non-empty array.
+/// Fails if the nodes are not a non-empty array of descendants of the given index.
+///
 /// Note that the if the LCA is the root, the path will be empty (0 length).
 fn get_path_to_lca(
     root_index: &NodeIndex,
     subtree_indices: &SortedLeafIndices<'_>,
-) -> PathToBottom {
-    if subtree_indices.is_empty() {
-        panic!("Unexpected empty array.");
-    }
-    let first_index = *subtree_indices.first().expect("Unexpected empty array.");
-    let lca = if subtree_indices.len() == 1 {
-        first_index
-    } else {
-        first_index.get_lca(subtree_indices.last().expect("Unexpected empty array"))
+) -> UpdatedSkeletonTreeResult<PathToBottom> {
+    let (Some(first_index), Some(last_index)) = (subtree_indices.first(), subtree_indices.last())
+    else {
+        return Err(unexpected_leaf_indices(root_index, subtree_indices));
     };
-    root_index.get_path_to_descendant(lca)
+    let lca = first_index.get_lca(last_index);
+    Ok(root_index.get_path_to_descendant(lca)?)
+}
+
+fn unexpected_leaf_indices(
+    root_index: &NodeIndex,
+    leaf_indices: &SortedLeafIndices<'_>,
+) -> UpdatedSkeletonTreeError {
+    UpdatedSkeletonTreeError::UnexpectedLeafIndices {
+        root_index: *root_index,
+        leaf_indices: leaf_indices.get_indices().to_vec(),
+    }
 }
 
 /// Returns whether a root of a subtree has leaves on both sides. Assumes that all leaves are
@@ -98,7 +103,7 @@ impl UpdatedSkeletonTreeImpl {
     pub(crate) fn finalize_middle_layers<'a>(
         &mut self,
         original_skeleton: &mut impl OriginalSkeletonTree<'a>,
-    ) -> TempSkeletonNode {
+    ) -> UpdatedSkeletonTreeResult<TempSkeletonNode> {
         let sorted_leaf_indices = original_skeleton.get_sorted_leaf_indices();
         if original_skeleton.get_nodes().is_empty() {
             self.update_node_in_empty_tree(&NodeIndex::ROOT, &sorted_leaf_indices)
@@ -118,35 +123,33 @@ impl UpdatedSkeletonTreeImpl {
         &mut self,
         root_index: &NodeIndex,
         leaf_indices: &SortedLeafIndices<'_>,
-    ) -> TempSkeletonNode {
+    ) -> UpdatedSkeletonTreeResult<TempSkeletonNode> {
         if root_index.is_leaf() {
             // Leaf. As this is an empty tree, the leaf *should* be new.
-            assert!(
-                leaf_indices.len() == 1
-                    && leaf_indices.first().expect("Unexpected empty array.") == root_index,
-                "Unexpected leaf index (root_index={root_index:?}, leaf_indices={leaf_indices:?})."
-            );
+            if leaf_indices.get_indices() != [*root_index] {
+                return Err(unexpected_leaf_indices(root_index, leaf_indices));
+            }
             if !self.skeleton_tree.contains_key(root_index) {
                 // "Deletion" of an original empty leaf (as non-zero leaf modifications are finalized in `finalize_bottom_layer`).
                 // Supported but not expected.
-                return TempSkeletonNode::Empty;
+                return Ok(TempSkeletonNode::Empty);
             }
-            return TempSkeletonNode::Leaf;
+            return Ok(TempSkeletonNode::Leaf);
         }
 
         if has_leaves_on_both_sides(root_index, leaf_indices) {
             // Binary node.
             let [left_indices, right_indices] = split_leaves(root_index, leaf_indices);
             let [left_child_index, right_child_index] = root_index.get_children_indices();
-            let left_child = self.update_node_in_empty_tree(&left_child_index, &left_indices);
-            let right_child = self.update_node_in_empty_tree(&right_child_index, &right_indices);
+            let left_child = self.update_node_in_empty_tree(&left_child_index, &left_indices)?;
+            let right_child = self.update_node_in_empty_tree(&right_child_index, &right_indices)?;
             return self.node_from_binary_data(root_index, &left_child, &right_child);
         }
 
         // Edge node.
-        let path_to_lca = get_path_to_lca(root_index, leaf_indices);
-        let bottom_index = path_to_lca.bottom_index(*root_index);
-        let bottom = self.update_node_in_empty_tree(&bottom_index, leaf_indices);
+        let path_to_lca = get_path_to_lca(root_index, leaf_indices)?;
+        let bottom_index = path_to_lca.bottom_index(*root_index)?;
+        let bottom = self.update_node_in_empty_tree(&bottom_index, leaf_indices)?;
         self.node_from_edge_data(&path_to_lca, &bottom_index, &bottom)
     }
 
@@ -156,41 +159,42 @@ impl UpdatedSkeletonTreeImpl {
         root_index: &NodeIndex,
         original_skeleton: &mut OriginalSkeletonNodeMap,
         leaf_indices: &SortedLeafIndices<'_>,
-    ) -> TempSkeletonNode {
+    ) -> UpdatedSkeletonTreeResult<TempSkeletonNode> {
         if root_index.is_leaf() && leaf_indices.contains(root_index) {
             // A new/modified/deleted leaf.
             if self.skeleton_tree.contains_key(root_index) {
                 // A new/modified leaf.
-                return TempSkeletonNode::Leaf;
+                return Ok(TempSkeletonNode::Leaf);
             } else {
                 // A deleted leaf.
-                return TempSkeletonNode::Empty;
+                return Ok(TempSkeletonNode::Empty);
             };
         };
 
         // Not a leaf or an unmodified node.
         let original_node = *original_skeleton
             .get(root_index)
-            .unwrap_or_else(|| panic!("Node {root_index:?} not found."));
+            .ok_or(UpdatedSkeletonTreeError::MissingNode(*root_index))?;
 
         if leaf_indices.is_empty() {
             match original_node {
-                OriginalSkeletonNode::Binary => unreachable!(
-                    "Index {root_index:?} is an original Binary node without leaf modifications -
-                    it should be an unmodified subtree instead."
-                ),
+                // An original Binary node without leaf modifications should be an unmodified
+                // subtree instead.
+                OriginalSkeletonNode::Binary => {
+                    return Err(UpdatedSkeletonTreeError::ExpectedUnmodifiedSubTree(
+                        *root_index,
+                    ));
+                }
                 OriginalSkeletonNode::Edge(_) | OriginalSkeletonNode::UnmodifiedSubTree(_) => {
-                    return TempSkeletonNode::Original(original_node);
+                    return Ok(TempSkeletonNode::Original(original_node));
                 }
             }
         };
 
         match original_node {
-            OriginalSkeletonNode::UnmodifiedSubTree(_) => {
-                unreachable!(
-                    "An unmodified subtree can't have any leaf_modifications in its subtree."
-                )
-            }
+            OriginalSkeletonNode::UnmodifiedSubTree(_) => Err(
+                UpdatedSkeletonTreeError::ModifiedUnmodifiedSubTree(*root_index),
+            ),
             OriginalSkeletonNode::Binary => {
                 let [left_indices, right_indices] = split_leaves(root_index, leaf_indices);
                 let [left_child_index, right_child_index] = root_index.get_children_indices();
@@ -198,12 +202,12 @@ impl UpdatedSkeletonTreeImpl {
                     &left_child_index,
                     original_skeleton,
                     &left_indices,
-                );
+                )?;
                 let right = self.update_node_in_nonempty_tree(
                     &right_child_index,
                     original_skeleton,
                     &right_indices,
-                );
+                )?;
                 self.node_from_binary_data(root_index, &left, &right)
             }
             OriginalSkeletonNode::Edge(path_to_bottom) => {
@@ -220,44 +224,36 @@ impl UpdatedSkeletonTreeImpl {
         root_index: &NodeIndex,
         left: &TempSkeletonNode,
         right: &TempSkeletonNode,
-    ) -> TempSkeletonNode {
+    ) -> UpdatedSkeletonTreeResult<TempSkeletonNode> {
         let [left_index, right_index] = root_index.get_children_indices();
 
         if !left.is_empty() && !right.is_empty() {
             // Both children are non-empty - a binary node.
             // Finalize children, as a binary node cannot change form.
             for (index, node) in [(left_index, left), (right_index, right)] {
-                let TempSkeletonNode::Original(original_node) = node else {
-                    match node {
-                        TempSkeletonNode::Leaf => {
-                            // Leaf is finalized in the initial phase of updated skeleton creation.
-                            assert!(
-                                self.skeleton_tree.contains_key(&index),
-                                "Leaf index {index:?} doesn't appear in the skeleton."
-                            );
-                            continue;
-                        }
-                        TempSkeletonNode::Empty => unreachable!("Unexpected empty node."),
-                        TempSkeletonNode::Original(_) => {
-                            unreachable!("node is not an Original variant.")
+                let updated = match node {
+                    TempSkeletonNode::Leaf => {
+                        // Leaf is finalized in the initial phase of updated skeleton creation.
+                        if !self.skeleton_tree.contains_key(&index) {
+                            return Err(UpdatedSkeletonTreeError::MissingLeaf(index));
                         }
+                        continue;
                     }
-                };
-                let updated = match original_node {
-                    OriginalSkeletonNode::Binary => UpdatedSkeletonNode::Binary,
-                    OriginalSkeletonNode::Edge(path_to_bottom) => {
-                        UpdatedSkeletonNode::Edge(*path_to_bottom)
+                    TempSkeletonNode::Original(OriginalSkeletonNode::Binary) => {
+                        UpdatedSkeletonNode::Binary
                     }
-                    OriginalSkeletonNode::UnmodifiedSubTree(_) => {
-                        // Unmodified nodes are finalized in the initial phase of updated skeleton
-                        // creation.
-                        continue;
+                    TempSkeletonNode::Original(OriginalSkeletonNode::Edge(path_to_bottom)) => {
+                        UpdatedSkeletonNode::Edge(*path_to_bottom)
                     }
+                    // Unmodified nodes are finalized in the initial phase of updated skeleton
+                    // creation, and both children are non-empty.
+                    TempSkeletonNode::Original(OriginalSkeletonNode::UnmodifiedSubTree(_))
+                    | TempSkeletonNode::Empty => continue,
                 };
                 self.skeleton_tree.insert(index, updated);
             }
 
-            return TempSkeletonNode::Original(OriginalSkeletonNode::Binary);
+            return Ok(TempSkeletonNode::Original(OriginalSkeletonNode::Binary));
         }
 
         // At least one of the children is empty.
@@ -277,25 +273,23 @@ impl UpdatedSkeletonTreeImpl {
         path: &PathToBottom,
         bottom_index: &NodeIndex,
         bottom: &TempSkeletonNode,
-    ) -> TempSkeletonNode {
-        let TempSkeletonNode::Original(original_node) = bottom else {
-            match bottom {
-                TempSkeletonNode::Empty => {
-                    return TempSkeletonNode::Empty;
-                }
-                TempSkeletonNode::Leaf => {
-                    // Leaf is finalized in the initial phase of updated skeleton creation.
-                    assert!(
-                        self.skeleton_tree.contains_key(bottom_index),
-                        "bottom {bottom_index:?} is a non-empty leaf but doesn't appear in the \
-                        skeleton."
-                    );
-                    return TempSkeletonNode::Original(OriginalSkeletonNode::Edge(*path));
+    ) -> UpdatedSkeletonTreeResult<TempSkeletonNode> {
+        let original_node = match bottom {
+            TempSkeletonNode::Empty => {
+                return Ok(TempSkeletonNode::Empty);
+            }
+            TempSkeletonNode::Leaf => {
+                // Leaf is finalized in the initial phase of updated skeleton creation.
+                if !self.skeleton_tree.contains_key(bottom_index) {
+                    return Err(UpdatedSkeletonTreeError::MissingLeaf(*bottom_index));
                 }
-                TempSkeletonNode::Original(_) => unreachable!("bottom is not an Original variant."),
-            };
+                return Ok(TempSkeletonNode::Original(OriginalSkeletonNode::Edge(
+                    *path,
+                )));
+            }
+            TempSkeletonNode::Original(original_node) => original_node,
         };
-        TempSkeletonNode::Original(match original_node {
+        Ok(TempSkeletonNode::Original(match original_node {
             OriginalSkeletonNode::Edge(path_to_bottom) => {
                 OriginalSkeletonNode::Edge(path.concat_paths(*path_to_bottom))
             }
@@ -306,7 +300,7 @@ impl UpdatedSkeletonTreeImpl {
                 OriginalSkeletonNode::Edge(*path)
             }
             OriginalSkeletonNode::UnmodifiedSubTree(_) => OriginalSkeletonNode::Edge(*path),
-        })
+        }))
     }
 
     /// Update an original subtree rooted with an edge node.
@@ -316,7 +310,7 @@ impl UpdatedSkeletonTreeImpl {
         path_to_bottom: &PathToBottom,
         original_skeleton: &mut OriginalSkeletonNodeMap,
         leaf_indices: &SortedLeafIndices<'_>,
-    ) -> TempSkeletonNode {
+    ) -> UpdatedSkeletonTreeResult<TempSkeletonNode> {
         let [left_child_index, right_child_index] = root_index.get_children_indices();
         let [left_indices, right_indices] = split_leaves(root_index, leaf_indices);
         let was_left_nonempty = path_to_bottom.is_left_descendant();
@@ -352,9 +346,7 @@ impl UpdatedSkeletonTreeImpl {
                 // edge node. Inject the new node to the original skeleton as if it was in it
                 // originally (fake original).
                 let fake_original_child_node = OriginalSkeletonNode::Edge(
-                    path_to_bottom
-                        .remove_first_edges(EdgePathLength::ONE)
-                        .expect("Original Edge node is unexpectedly trivial"),
+                    path_to_bottom.remove_first_edges(EdgePathLength::ONE)?,
                 );
                 original_skeleton.insert(nonempty_subtree_child_index, fake_original_child_node);
             };
@@ -363,11 +355,13 @@ impl UpdatedSkeletonTreeImpl {
                 &nonempty_subtree_child_index,
                 original_skeleton,
                 &nonempty_subtree_leaf_indices,
-            );
+            )?;
 
             // 2. Handle the originally empty subtree.
-            let orig_empty_subtree_child = self
-                .update_node_in_empty_tree(&empty_subtree_child_index, &empty_subtree_leaf_indices);
+            let orig_empty_subtree_child = self.update_node_in_empty_tree(
+                &empty_subtree_child_index,
+                &empty_subtree_leaf_indices,
+            )?;
             let (left, right) = if was_left_nonempty {
                 (orig_nonempty_subtree_child, orig_empty_subtree_child)
             } else {
@@ -379,33 +373,33 @@ impl UpdatedSkeletonTreeImpl {
 
         // All leaves are on the edge's subtree - they have a non-trivial common path with the edge.
         // Create a new edge to the LCA of the leaves and the bottom.
-        let path_to_leaves_lca = get_path_to_lca(root_index, leaf_indices);
-        let leaves_lca_index = path_to_leaves_lca.bottom_index(*root_index);
+        let path_to_leaves_lca = get_path_to_lca(root_index, leaf_indices)?;
+        let leaves_lca_index = path_to_leaves_lca.bottom_index(*root_index)?;
 
-        let bottom_index = path_to_bottom.bottom_index(*root_index);
+        let bottom_index = path_to_bottom.bottom_index(*root_index)?;
         let path_to_new_bottom = get_path_to_lca(
             root_index,
             &SortedLeafIndices::new(&mut [leaves_lca_index, bottom_index]),
-        );
+        )?;
 
-        let new_bottom_index = path_to_new_bottom.bottom_index(*root_index);
+        let new_bottom_index = path_to_new_bottom.bottom_index(*root_index)?;
         if new_bottom_index == bottom_index {
             //  All leaf_indices are in the bottom_node subtree.
-            assert_eq!(&path_to_new_bottom, path_to_bottom);
+            if &path_to_new_bottom != path_to_bottom {
+                return Err(UpdatedSkeletonTreeError::EdgePathMismatch(*path_to_bottom));
+            }
         } else {
             // Inject the new node to the original skeleton as if it was in it
             // originally (fake original).
             let fake_original_new_bottom_node = OriginalSkeletonNode::Edge(
-                path_to_bottom
-                    .remove_first_edges(path_to_new_bottom.length)
-                    .expect("Unexpectedly failed to remove first edges."),
+                path_to_bottom.remove_first_edges(path_to_new_bottom.length)?,
             );
 
             original_skeleton.insert(new_bottom_index, fake_original_new_bottom_node);
         }
 
         let bottom =
-            self.update_node_in_nonempty_tree(&new_bottom_index, original_skeleton, leaf_indices);
+            self.update_node_in_nonempty_tree(&new_bottom_index, original_skeleton, leaf_indices)?;
 
         self.node_from_edge_data(&path_to_new_bottom, &new_bottom_index, &bottom)
     }
@@ -417,8 +411,11 @@ impl UpdatedSkeletonTreeImpl {
             .get_nodes()
             .get(&NodeIndex::ROOT)
             .ok_or(UpdatedSkeletonTreeError::MissingNode(NodeIndex::ROOT))?;
+        // A root of tree without modifications is expected to be an unmodified node.
         let OriginalSkeletonNode::UnmodifiedSubTree(root_hash) = original_root_node else {
-            panic!("A root of tree without modifications is expected to be an unmodified node.")
+            return Err(UpdatedSkeletonTreeError::ExpectedUnmodifiedSubTree(
+                NodeIndex::ROOT,
+            ));
         };
 
         Ok(Self {
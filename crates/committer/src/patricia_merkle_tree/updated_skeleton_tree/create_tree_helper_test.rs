@@ -10,6 +10,7 @@ use std::sync::Arc;
 
 use crate::felt::Felt;
 use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TypesError;
 use crate::patricia_merkle_tree::internal_test_utils::OriginalSkeletonMockTrieConfig;
 use crate::patricia_merkle_tree::internal_test_utils::{
     as_fully_indexed, get_initial_updated_skeleton, small_tree_index_to_full,
@@ -17,11 +18,14 @@ use crate::patricia_merkle_tree::internal_test_utils::{
 use crate::patricia_merkle_tree::node_data::inner_node::{EdgePathLength, PathToBottom};
 use crate::patricia_merkle_tree::original_skeleton_tree::node::OriginalSkeletonNode;
 use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonNodeMap;
-use crate::patricia_merkle_tree::original_skeleton_tree::tree::OriginalSkeletonTreeImpl;
+use crate::patricia_merkle_tree::original_skeleton_tree::tree::{
+    OriginalSkeletonTree, OriginalSkeletonTreeImpl,
+};
 use crate::patricia_merkle_tree::types::{NodeIndex, SubTreeHeight};
 use crate::patricia_merkle_tree::updated_skeleton_tree::create_tree_helper::{
     get_path_to_lca, has_leaves_on_both_sides, TempSkeletonNode,
 };
+use crate::patricia_merkle_tree::updated_skeleton_tree::errors::UpdatedSkeletonTreeError;
 use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
 use crate::patricia_merkle_tree::updated_skeleton_tree::{
@@ -130,17 +134,18 @@ fn test_get_path_to_lca(
     #[case] leaf_indices: Vec<U256>,
     #[case] expected: PathToBottom,
 ) {
-    let root_index = NodeIndex::new(root_index.into());
+    let root_index = NodeIndex::new(root_index.into()).unwrap();
     assert_eq!(
         get_path_to_lca(
             &root_index,
             &SortedLeafIndices::new(
                 &mut leaf_indices
                     .iter()
-                    .map(|index: &ethnum::U256| NodeIndex::new(*index))
+                    .map(|index: &ethnum::U256| NodeIndex::new(*index).unwrap())
                     .collect::<Vec<_>>()[..]
             )
-        ),
+        )
+        .unwrap(),
         expected
     );
 }
@@ -218,7 +223,9 @@ fn test_node_from_binary_data(
 ) {
     let mut expected_skeleton_tree = initial_updated_skeleton.skeleton_tree.clone();
     expected_skeleton_tree.extend(expected_skeleton_additions.iter().cloned());
-    let temp_node = initial_updated_skeleton.node_from_binary_data(root_index, left, right);
+    let temp_node = initial_updated_skeleton
+        .node_from_binary_data(root_index, left, right)
+        .unwrap();
     assert_eq!(temp_node, expected_node);
     assert_eq!(
         initial_updated_skeleton.skeleton_tree,
@@ -288,7 +295,9 @@ fn test_node_from_edge_data(
 ) {
     let mut expected_skeleton_tree = initial_updated_skeleton.skeleton_tree.clone();
     expected_skeleton_tree.extend(expected_skeleton_additions.iter().cloned());
-    let temp_node = initial_updated_skeleton.node_from_edge_data(path, bottom_index, bottom);
+    let temp_node = initial_updated_skeleton
+        .node_from_edge_data(path, bottom_index, bottom)
+        .unwrap();
     assert_eq!(temp_node, expected_node);
     assert_eq!(
         initial_updated_skeleton.skeleton_tree,
@@ -335,7 +344,8 @@ fn test_update_node_in_empty_tree(
     let mut expected_skeleton_tree = initial_updated_skeleton.skeleton_tree.clone();
     expected_skeleton_tree.extend(expected_skeleton_additions.iter().cloned());
     let temp_node = initial_updated_skeleton
-        .update_node_in_empty_tree(root_index, &SortedLeafIndices::new(&mut leaf_indices));
+        .update_node_in_empty_tree(root_index, &SortedLeafIndices::new(&mut leaf_indices))
+        .unwrap();
     assert_eq!(temp_node, expected_node);
     assert_eq!(
         initial_updated_skeleton.skeleton_tree,
@@ -484,11 +494,13 @@ fn test_update_node_in_nonempty_tree(
         leaf_modifications.iter().map(|(index, _)| *index).collect();
     let mut expected_skeleton_tree = initial_updated_skeleton.skeleton_tree.clone();
     expected_skeleton_tree.extend(expected_skeleton_additions.iter().cloned());
-    let temp_node = initial_updated_skeleton.update_node_in_nonempty_tree(
-        root_index,
-        &mut original_skeleton,
-        &SortedLeafIndices::new(&mut leaf_indices),
-    );
+    let temp_node = initial_updated_skeleton
+        .update_node_in_nonempty_tree(
+            root_index,
+            &mut original_skeleton,
+            &SortedLeafIndices::new(&mut leaf_indices),
+        )
+        .unwrap();
     assert_eq!(temp_node, expected_node);
     assert_eq!(
         initial_updated_skeleton.skeleton_tree,
@@ -496,19 +508,87 @@ fn test_update_node_in_nonempty_tree(
     );
 }
 
+#[rstest]
+fn test_get_path_to_lca_of_non_descendants() {
+    let root_index = NodeIndex::from(2);
+    let error = get_path_to_lca(&root_index, &SortedLeafIndices::new(&mut [NodeIndex::MAX]))
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::Types(TypesError::NotADescendant { ancestor, descendant })
+            if ancestor == root_index && descendant == NodeIndex::MAX
+    ));
+}
+
+#[rstest]
+fn test_update_node_in_empty_tree_with_unexpected_leaf(
+    mut initial_updated_skeleton: UpdatedSkeletonTreeImpl,
+) {
+    let error = initial_updated_skeleton
+        .update_node_in_empty_tree(
+            &NodeIndex::FIRST_LEAF,
+            &SortedLeafIndices::new(&mut [NodeIndex::FIRST_LEAF + 1]),
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::UnexpectedLeafIndices { root_index, leaf_indices }
+            if root_index == NodeIndex::FIRST_LEAF && leaf_indices == [NodeIndex::FIRST_LEAF + 1]
+    ));
+}
+
+#[rstest]
+fn test_node_from_binary_data_with_missing_leaf(
+    mut initial_updated_skeleton: UpdatedSkeletonTreeImpl,
+) {
+    let error = initial_updated_skeleton
+        .node_from_binary_data(
+            &(NodeIndex::FIRST_LEAF >> 1),
+            &TempSkeletonNode::Leaf,
+            &TempSkeletonNode::Leaf,
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::MissingLeaf(index) if index == NodeIndex::FIRST_LEAF
+    ));
+}
+
+#[rstest]
+fn test_node_from_edge_data_with_missing_leaf(
+    mut initial_updated_skeleton: UpdatedSkeletonTreeImpl,
+) {
+    let error = initial_updated_skeleton
+        .node_from_edge_data(
+            &PathToBottom::RIGHT_CHILD,
+            &(NodeIndex::FIRST_LEAF + 1),
+            &TempSkeletonNode::Leaf,
+        )
+        .err()
+        .unwrap();
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::MissingLeaf(index) if index == NodeIndex::FIRST_LEAF + 1
+    ));
+}
+
 #[rstest]
 #[case::empty_tree(HashOutput::ROOT_OF_EMPTY_TREE)]
 #[case::non_empty_tree(HashOutput(Felt::from(77_u128)))]
 #[tokio::test]
 async fn test_update_non_modified_storage_tree(#[case] root_hash: HashOutput) {
     let empty_map = HashMap::new();
-    let config = OriginalSkeletonMockTrieConfig::new(&empty_map, false);
-    let mut original_skeleton_tree = OriginalSkeletonTreeImpl::create_impl::<MockLeaf>(
+    let config = OriginalSkeletonMockTrieConfig::new(&empty_map, false, None);
+    let mut original_skeleton_tree = OriginalSkeletonTreeImpl::create::<MockLeaf>(
         &MapStorage::default(),
         root_hash,
         SortedLeafIndices::new(&mut []),
         &config,
     )
+    .await
     .unwrap();
     let updated =
         UpdatedSkeletonTreeImpl::create(&mut original_skeleton_tree, &HashMap::new()).unwrap();
@@ -1,7 +1,38 @@
+use crate::patricia_merkle_tree::errors::TypesError;
+use crate::patricia_merkle_tree::node_data::errors::PathToBottomError;
+use crate::patricia_merkle_tree::node_data::inner_node::PathToBottom;
 use crate::patricia_merkle_tree::types::NodeIndex;
 
 #[derive(Debug, thiserror::Error)]
 pub enum UpdatedSkeletonTreeError {
     #[error("Missing node at index {0:?}.")]
     MissingNode(NodeIndex),
+    #[error("The leaf at index {0:?} is modified, but is missing from the updated skeleton.")]
+    MissingLeaf(NodeIndex),
+    #[error("The root of the updated skeleton is a leaf.")]
+    LeafRoot,
+    #[error("The node at index {0:?} already exists in the updated skeleton.")]
+    DuplicateNode(NodeIndex),
+    #[error("All leaves were deleted, but {0} nodes remain in the updated skeleton.")]
+    NodesInEmptyTree(usize),
+    #[error("The original node at index {0:?} is an unmodified subtree, but has modified leaves.")]
+    ModifiedUnmodifiedSubTree(NodeIndex),
+    #[error(
+        "The original node at index {0:?} has no modified leaves, but is not an unmodified subtree."
+    )]
+    ExpectedUnmodifiedSubTree(NodeIndex),
+    #[error("Unexpected leaf indices {leaf_indices:?} in the subtree rooted at {root_index:?}.")]
+    UnexpectedLeafIndices {
+        root_index: NodeIndex,
+        leaf_indices: Vec<NodeIndex>,
+    },
+    #[error(
+        "The modified leaves are under the bottom of the original edge {0:?}, but the computed \
+         path to them differs from it."
+    )]
+    EdgePathMismatch(PathToBottom),
+    #[error(transparent)]
+    PathToBottom(#[from] PathToBottomError),
+    #[error(transparent)]
+    Types(#[from] TypesError<NodeIndex>),
 }
@@ -14,7 +14,7 @@ use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
 pub mod hash_function_test;
 
 /// Trait for hash functions.
-pub(crate) trait HashFunction {
+pub trait HashFunction {
     /// Computes the hash of the given input.
     fn hash(left: &Felt, right: &Felt) -> HashOutput;
 }
@@ -35,7 +35,7 @@ impl HashFunction for PoseidonHashFunction {
     }
 }
 
-pub(crate) trait TreeHashFunction<L: Leaf> {
+pub trait TreeHashFunction<L: Leaf> {
     /// Computes the hash of the given leaf.
     fn compute_leaf_hash(leaf_data: &L) -> HashOutput;
 
@@ -70,6 +70,32 @@ impl TreeHashFunctionImpl {
     // The hex string corresponding to b'CONTRACT_CLASS_LEAF_V0' in big-endian.
     pub const CONTRACT_CLASS_LEAF_V0: &'static str =
         "0x434f4e54524143545f434c4153535f4c4541465f5630";
+
+    // The hex string corresponding to b'STARKNET_STATE_V0' in big-endian.
+    pub const STARKNET_STATE_V0: &'static str = "0x535441524b4e45545f53544154455f5630";
+
+    /// Computes the global state root from the roots of the contracts trie and the classes trie.
+    /// As long as the classes trie is empty, the global root is the root of the contracts trie.
+    /// The implementation is based on the following reference:
+    /// https://docs.starknet.io/documentation/architecture_and_concepts/Network_Architecture/starknet-state/#state_commitment
+    pub fn compute_global_root_hash(
+        contracts_trie_root_hash: HashOutput,
+        classes_trie_root_hash: HashOutput,
+    ) -> HashOutput {
+        if classes_trie_root_hash == HashOutput::ROOT_OF_EMPTY_TREE {
+            return contracts_trie_root_hash;
+        }
+        let starknet_state_version: Felt = Felt::from_hex(Self::STARKNET_STATE_V0)
+            .expect("could not parse hex string corresponding to b'STARKNET_STATE_V0' to Felt");
+        HashOutput(
+            Poseidon::hash_array(&[
+                starknet_state_version.into(),
+                contracts_trie_root_hash.0.into(),
+                classes_trie_root_hash.0.into(),
+            ])
+            .into(),
+        )
+    }
 }
 
 /// Implementation of TreeHashFunction for contracts trie.
@@ -131,7 +157,7 @@ impl TreeHashFunction<StarknetStorageValue> for TreeHashFunctionImpl {
 }
 
 /// Combined trait for all specific implementations.
-pub(crate) trait ForestHashFunction:
+pub trait ForestHashFunction:
     TreeHashFunction<ContractState>
     + TreeHashFunction<CompiledClassHash>
     + TreeHashFunction<StarknetStorageValue>
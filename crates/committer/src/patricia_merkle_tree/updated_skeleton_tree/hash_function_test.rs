@@ -134,3 +134,51 @@ fn test_tree_hash_function_storage_leaf(
     let hash_output = TreeHashFunctionImpl::compute_node_hash(&node_data);
     assert_eq!(hash_output, HashOutput(expected_hash));
 }
+
+#[test]
+fn test_starknet_state_version() {
+    assert_eq!(
+        Felt::from_hex(TreeHashFunctionImpl::STARKNET_STATE_V0)
+            .unwrap()
+            .to_bytes_be()
+            .strip_prefix(&[0; 15]),
+        Some(b"STARKNET_STATE_V0".as_slice())
+    );
+}
+
+#[rstest]
+// Expected hash values were computed independently, with a reference Poseidon implementation
+// checked against the cairo-lang test vectors.
+#[case::empty_state(Felt::ZERO, Felt::ZERO, Felt::ZERO)]
+#[case::no_classes(Felt::from(0xDEAFBEEF_u128), Felt::ZERO, Felt::from(0xDEAFBEEF_u128))]
+#[case::only_classes(
+    Felt::ZERO,
+    Felt::from(0xACDC_u128),
+    Felt::from_hex("0x1075bafbb1a207c231cf2e3cc1675b9a9e9d89ab9a17b4590e62ddd323238db").unwrap()
+)]
+#[case::both_tries(
+    Felt::from(0xDEAFBEEF_u128),
+    Felt::from(0xACDC_u128),
+    Felt::from_hex("0x46137e3186980b983feff2b65910b7320a4e9332a174eba705f289eac554422").unwrap()
+)]
+#[case::small_roots(
+    Felt::ONE,
+    Felt::TWO,
+    Felt::from_hex("0x794df522f4bc0d998d32559d28ce3a436bfa592902b1bda398023bad7c1f71e").unwrap()
+)]
+#[case::max_roots(
+    Felt::MAX,
+    Felt::MAX - Felt::ONE,
+    Felt::from_hex("0x59fb6cfcb9c715ffca5f221ecb879756f4c4ec58d0ea7a93b6e71ce42262e96").unwrap()
+)]
+fn test_compute_global_root_hash(
+    #[case] contracts_trie_root_hash: Felt,
+    #[case] classes_trie_root_hash: Felt,
+    #[case] expected_global_root_hash: Felt,
+) {
+    let global_root_hash = TreeHashFunctionImpl::compute_global_root_hash(
+        HashOutput(contracts_trie_root_hash),
+        HashOutput(classes_trie_root_hash),
+    );
+    assert_eq!(global_root_hash, HashOutput(expected_global_root_hash));
+}
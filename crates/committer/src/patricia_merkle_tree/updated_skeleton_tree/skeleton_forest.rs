@@ -9,6 +9,7 @@ use crate::patricia_merkle_tree::node_data::leaf::{
 };
 use crate::patricia_merkle_tree::original_skeleton_tree::skeleton_forest::OriginalSkeletonForest;
 use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTree;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::UpdatedSkeletonTreeImpl;
 
@@ -78,6 +79,20 @@ impl UpdatedSkeletonForest {
         })
     }
 
+    /// Returns the number of modified nodes in all tries of the forest, i.e., the number of new
+    /// facts they produce when filled.
+    pub(crate) fn n_modified_nodes(&self) -> usize {
+        self.storage_tries
+            .values()
+            .chain([&self.contracts_trie, &self.classes_trie])
+            .map(|tree| {
+                tree.get_nodes()
+                    .filter(|(_, node)| !matches!(node, UpdatedSkeletonNode::UnmodifiedSubTree(_)))
+                    .count()
+            })
+            .sum()
+    }
+
     /// Given the previous contract state, whether the contract's storage has become empty or not,
     /// optional new nonce & new class hash, the function creates a skeleton leaf.
     fn updated_contract_skeleton_leaf(
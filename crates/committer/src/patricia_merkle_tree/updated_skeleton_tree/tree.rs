@@ -53,32 +53,38 @@ impl<'a> UpdatedSkeletonTree<'a> for UpdatedSkeletonTreeImpl {
 
         let mut updated_skeleton_tree = UpdatedSkeletonTreeImpl { skeleton_tree };
 
-        let temp_root_node = updated_skeleton_tree.finalize_middle_layers(original_skeleton);
+        let temp_root_node = updated_skeleton_tree.finalize_middle_layers(original_skeleton)?;
         // Finalize root.
         match temp_root_node {
-            TempSkeletonNode::Empty => assert!(updated_skeleton_tree.skeleton_tree.is_empty()),
-            TempSkeletonNode::Leaf => {
-                unreachable!("Root node cannot be a leaf")
+            TempSkeletonNode::Empty => {
+                if !updated_skeleton_tree.skeleton_tree.is_empty() {
+                    return Err(UpdatedSkeletonTreeError::NodesInEmptyTree(
+                        updated_skeleton_tree.skeleton_tree.len(),
+                    ));
+                }
             }
+            TempSkeletonNode::Leaf => return Err(UpdatedSkeletonTreeError::LeafRoot),
             TempSkeletonNode::Original(original_skeleton_node) => {
                 let new_node = match original_skeleton_node {
                     OriginalSkeletonNode::Binary => UpdatedSkeletonNode::Binary,
                     OriginalSkeletonNode::Edge(path_to_bottom) => {
                         UpdatedSkeletonNode::Edge(path_to_bottom)
                     }
+                    // Root node cannot be unmodified when there are some modifications.
                     OriginalSkeletonNode::UnmodifiedSubTree(_) => {
-                        unreachable!(
-                            "Root node cannot be unmodified when there are some modifications."
-                        )
+                        return Err(UpdatedSkeletonTreeError::ModifiedUnmodifiedSubTree(
+                            NodeIndex::ROOT,
+                        ))
                     }
                 };
 
-                updated_skeleton_tree
+                if updated_skeleton_tree
                     .skeleton_tree
                     .insert(NodeIndex::ROOT, new_node)
-                    .map_or((), |_| {
-                        panic!("Root node already exists in the updated skeleton tree")
-                    })
+                    .is_some()
+                {
+                    return Err(UpdatedSkeletonTreeError::DuplicateNode(NodeIndex::ROOT));
+                }
             }
         };
         Ok(updated_skeleton_tree)
@@ -13,6 +13,7 @@ use crate::patricia_merkle_tree::original_skeleton_tree::tree::{
     OriginalSkeletonTree, OriginalSkeletonTreeImpl,
 };
 use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices, SubTreeHeight};
+use crate::patricia_merkle_tree::updated_skeleton_tree::errors::UpdatedSkeletonTreeError;
 use crate::patricia_merkle_tree::updated_skeleton_tree::node::UpdatedSkeletonNode;
 use crate::patricia_merkle_tree::updated_skeleton_tree::tree::{
     UpdatedSkeletonTree, UpdatedSkeletonTreeImpl,
@@ -145,15 +146,17 @@ fn test_updated_skeleton_tree_impl_create(
 #[rstest]
 #[case::empty_modifications(HashMap::new())]
 #[case::non_empty_modifications(HashMap::from([(NodeIndex::FIRST_LEAF + NodeIndex::from(7), MockLeaf::default())]))]
-fn test_updated_empty_tree(#[case] modifications: LeafModifications<MockLeaf>) {
+#[tokio::test]
+async fn test_updated_empty_tree(#[case] modifications: LeafModifications<MockLeaf>) {
     let storage: MapStorage = HashMap::new().into();
     let mut indices: Vec<NodeIndex> = modifications.keys().copied().collect();
     let mut original_skeleton = OriginalSkeletonTreeImpl::create(
         &storage,
         HashOutput::ROOT_OF_EMPTY_TREE,
         SortedLeafIndices::new(&mut indices),
-        &OriginalSkeletonMockTrieConfig::new(&modifications, false),
+        &OriginalSkeletonMockTrieConfig::new(&modifications, false, None),
     )
+    .await
     .unwrap();
 
     let skeleton_modifications = modifications
@@ -164,3 +167,132 @@ fn test_updated_empty_tree(#[case] modifications: LeafModifications<MockLeaf>) {
         UpdatedSkeletonTreeImpl::create(&mut original_skeleton, &skeleton_modifications).unwrap();
     assert!(updated_skeleton_tree.is_empty());
 }
+
+/// Creates an updated skeleton from an original skeleton with the given nodes and (possibly
+/// inconsistent) leaf indices, and returns the resulting error.
+fn create_error(
+    original_skeleton: &[(NodeIndex, OriginalSkeletonNode)],
+    leaf_indices: &mut [NodeIndex],
+    leaf_modifications: &[(NodeIndex, u8)],
+) -> UpdatedSkeletonTreeError {
+    let leaf_modifications: LeafModifications<SkeletonLeaf> = leaf_modifications
+        .iter()
+        .map(|(index, val)| (*index, (*val).into()))
+        .collect();
+    let mut original_skeleton = OriginalSkeletonTreeImpl {
+        nodes: original_skeleton.iter().cloned().collect(),
+        sorted_leaf_indices: SortedLeafIndices::new(leaf_indices),
+    };
+    UpdatedSkeletonTreeImpl::create(&mut original_skeleton, &leaf_modifications)
+        .err()
+        .unwrap()
+}
+
+#[rstest]
+fn test_create_with_missing_node() {
+    let error = create_error(
+        &[(NodeIndex::ROOT, OriginalSkeletonNode::Binary)],
+        &mut [NodeIndex::FIRST_LEAF],
+        &[(NodeIndex::FIRST_LEAF, 1)],
+    );
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::MissingNode(index) if index == NodeIndex::from(2)
+    ));
+}
+
+#[rstest]
+fn test_create_with_modified_unmodified_root() {
+    let error = create_error(
+        &[(
+            NodeIndex::ROOT,
+            OriginalSkeletonNode::UnmodifiedSubTree(HashOutput(Felt::ONE)),
+        )],
+        &mut [NodeIndex::FIRST_LEAF],
+        &[(NodeIndex::FIRST_LEAF, 1)],
+    );
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::ModifiedUnmodifiedSubTree(NodeIndex::ROOT)
+    ));
+}
+
+#[rstest]
+fn test_create_with_unmodified_binary_node() {
+    let error = create_error(
+        &[
+            (NodeIndex::ROOT, OriginalSkeletonNode::Binary),
+            (
+                NodeIndex::from(2),
+                OriginalSkeletonNode::Edge(PathToBottom::from(
+                    "0".repeat(TREE_HEIGHT - 1).as_str(),
+                )),
+            ),
+            (NodeIndex::from(3), OriginalSkeletonNode::Binary),
+        ],
+        &mut [NodeIndex::FIRST_LEAF],
+        &[(NodeIndex::FIRST_LEAF, 1)],
+    );
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::ExpectedUnmodifiedSubTree(index) if index == NodeIndex::from(3)
+    ));
+}
+
+#[rstest]
+fn test_create_unmodified_with_modified_root() {
+    let error = create_error(
+        &[(NodeIndex::ROOT, OriginalSkeletonNode::Binary)],
+        &mut [],
+        &[],
+    );
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::ExpectedUnmodifiedSubTree(NodeIndex::ROOT)
+    ));
+}
+
+#[rstest]
+fn test_create_with_missing_leaf_indices() {
+    let error = create_error(&[], &mut [], &[(NodeIndex::FIRST_LEAF, 1)]);
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::UnexpectedLeafIndices { root_index, leaf_indices }
+            if root_index == NodeIndex::ROOT && leaf_indices.is_empty()
+    ));
+}
+
+#[rstest]
+fn test_create_with_orphan_node() {
+    let error = create_error(
+        &[
+            (
+                NodeIndex::ROOT,
+                OriginalSkeletonNode::Edge(PathToBottom::from("0".repeat(TREE_HEIGHT).as_str())),
+            ),
+            (
+                NodeIndex::from(5),
+                OriginalSkeletonNode::UnmodifiedSubTree(HashOutput(Felt::ONE)),
+            ),
+        ],
+        &mut [NodeIndex::FIRST_LEAF],
+        &[(NodeIndex::FIRST_LEAF, 0)],
+    );
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::NodesInEmptyTree(1)
+    ));
+}
+
+#[rstest]
+fn test_create_with_modified_root_index() {
+    let error = create_error(
+        &[],
+        &mut [NodeIndex::FIRST_LEAF],
+        &[(NodeIndex::FIRST_LEAF, 1), (NodeIndex::ROOT, 1)],
+    );
+    assert!(matches!(
+        error,
+        UpdatedSkeletonTreeError::DuplicateNode(NodeIndex::ROOT)
+    ));
+}
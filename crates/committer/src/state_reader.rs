@@ -0,0 +1,4 @@
+pub mod diff;
+pub mod errors;
+pub mod flat_index;
+pub mod reader;
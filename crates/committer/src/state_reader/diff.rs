@@ -0,0 +1,85 @@
+use std::collections::HashMap;
+
+use crate::block_committer::input::{
+    ContractAddress, StarknetStorageKey, StarknetStorageValue, StateDiff,
+};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash};
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::traversal::{diff_leaves, LeafDiff};
+use crate::state_reader::errors::StateReaderResult;
+use crate::storage::storage_trait::AsyncStorage;
+
+#[cfg(test)]
+#[path = "diff_test.rs"]
+pub mod diff_test;
+
+/// Computes the state diff which turns the state with the previous roots into the state with the
+/// new roots. Only subtrees whose hashes differ are traversed. Removed storage values and compiled
+/// class hashes appear in the diff as zero.
+pub async fn compute_state_diff(
+    storage: &impl AsyncStorage,
+    previous_contracts_trie_root_hash: HashOutput,
+    new_contracts_trie_root_hash: HashOutput,
+    previous_classes_trie_root_hash: HashOutput,
+    new_classes_trie_root_hash: HashOutput,
+) -> StateReaderResult<StateDiff> {
+    let mut state_diff = StateDiff::default();
+    let contract_diffs = diff_leaves::<ContractState>(
+        storage,
+        previous_contracts_trie_root_hash,
+        new_contracts_trie_root_hash,
+    )
+    .await?;
+    for LeafDiff {
+        index,
+        previous,
+        new,
+    } in contract_diffs
+    {
+        let address = ContractAddress(index.to_leaf_felt());
+        if previous.class_hash != new.class_hash {
+            state_diff
+                .address_to_class_hash
+                .insert(address, new.class_hash);
+        }
+        if previous.nonce != new.nonce {
+            state_diff.address_to_nonce.insert(address, new.nonce);
+        }
+        if previous.storage_root_hash != new.storage_root_hash {
+            let storage_updates =
+                compute_storage_updates(storage, previous.storage_root_hash, new.storage_root_hash)
+                    .await?;
+            state_diff.storage_updates.insert(address, storage_updates);
+        }
+    }
+
+    state_diff.class_hash_to_compiled_class_hash = diff_leaves::<CompiledClassHash>(
+        storage,
+        previous_classes_trie_root_hash,
+        new_classes_trie_root_hash,
+    )
+    .await?
+    .into_iter()
+    .map(|LeafDiff { index, new, .. }| (ClassHash(index.to_leaf_felt()), new))
+    .collect();
+    Ok(state_diff)
+}
+
+/// Computes the storage updates which turn the storage trie with the previous root into the
+/// storage trie with the new root.
+pub async fn compute_storage_updates(
+    storage: &impl AsyncStorage,
+    previous_storage_root_hash: HashOutput,
+    new_storage_root_hash: HashOutput,
+) -> StateReaderResult<HashMap<StarknetStorageKey, StarknetStorageValue>> {
+    Ok(diff_leaves::<StarknetStorageValue>(
+        storage,
+        previous_storage_root_hash,
+        new_storage_root_hash,
+    )
+    .await?
+    .into_iter()
+    .map(|LeafDiff { index, new, .. }| (StarknetStorageKey(index.to_leaf_felt()), new))
+    .collect())
+}
@@ -0,0 +1,168 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::input::{StarknetStorageValue, StateDiff};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TraversalError;
+use crate::patricia_merkle_tree::filled_tree::node::{CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, class_hash, commit_to_storage, key, value,
+};
+use crate::state_reader::diff::compute_state_diff;
+use crate::state_reader::errors::StateReaderError;
+use crate::storage::db_object::Deserializable;
+use crate::storage::map_storage::MapStorage;
+
+/// A state diff which only modifies storage, given as (contract, key, value) triplets.
+fn storage_diff(updates: &[(u128, u128, u128)]) -> StateDiff {
+    let mut storage_updates: HashMap<_, HashMap<_, _>> = HashMap::new();
+    for (contract, slot, slot_value) in updates {
+        storage_updates
+            .entry(address(*contract))
+            .or_default()
+            .insert(key(*slot), value(*slot_value));
+    }
+    StateDiff {
+        storage_updates,
+        ..Default::default()
+    }
+}
+
+/// Commits the given state diffs one after another to an empty storage. Returns the storage and
+/// the roots before the first diff and after each diff.
+async fn commit_diffs(state_diffs: &[StateDiff]) -> (MapStorage, Vec<(HashOutput, HashOutput)>) {
+    let mut storage = MapStorage::default();
+    let mut roots = vec![(
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+    )];
+    for state_diff in state_diffs {
+        let (contracts_trie_root_hash, classes_trie_root_hash) = *roots.last().unwrap();
+        roots.push(
+            commit_to_storage(
+                &mut storage,
+                state_diff,
+                contracts_trie_root_hash,
+                classes_trie_root_hash,
+            )
+            .await,
+        );
+    }
+    (storage, roots)
+}
+
+async fn state_diff_between(
+    storage: &MapStorage,
+    previous_roots: (HashOutput, HashOutput),
+    new_roots: (HashOutput, HashOutput),
+) -> StateDiff {
+    compute_state_diff(
+        storage,
+        previous_roots.0,
+        new_roots.0,
+        previous_roots.1,
+        new_roots.1,
+    )
+    .await
+    .unwrap()
+}
+
+#[rstest]
+#[case::empty_state(StateDiff::default(), StateDiff::default(), StateDiff::default())]
+#[case::from_empty_state(
+    StateDiff::default(),
+    storage_diff(&[(1, 5, 50), (1, 6, 60), (2, 5, 70)]),
+    storage_diff(&[(1, 5, 50), (1, 6, 60), (2, 5, 70)]),
+)]
+#[case::unmodified_state(
+    storage_diff(&[(1, 5, 50)]),
+    storage_diff(&[(1, 5, 50)]),
+    StateDiff::default(),
+)]
+#[case::deleted_value(
+    storage_diff(&[(1, 5, 50), (1, 6, 60)]),
+    storage_diff(&[(1, 5, 0)]),
+    storage_diff(&[(1, 5, 0)]),
+)]
+// Deleting the only value of a contract with no class or nonce deletes its leaf.
+#[case::deleted_contract(
+    storage_diff(&[(1, 5, 50), (2, 5, 70)]),
+    storage_diff(&[(2, 5, 0)]),
+    storage_diff(&[(2, 5, 0)]),
+)]
+// Both contracts have the same storage trie; only the modified one is in the diff.
+#[case::shared_storage_trie(
+    storage_diff(&[(1, 5, 50), (2, 5, 50)]),
+    storage_diff(&[(2, 5, 51)]),
+    storage_diff(&[(2, 5, 51)]),
+)]
+#[case::classes_and_nonces(
+    StateDiff {
+        address_to_class_hash: HashMap::from([(address(1), class_hash(10))]),
+        class_hash_to_compiled_class_hash: HashMap::from([
+            (class_hash(10), CompiledClassHash(Felt::from(100_u128))),
+        ]),
+        ..Default::default()
+    },
+    StateDiff {
+        address_to_nonce: HashMap::from([(address(1), Nonce(Felt::ONE))]),
+        class_hash_to_compiled_class_hash: HashMap::from([
+            (class_hash(10), CompiledClassHash(Felt::from(101_u128))),
+        ]),
+        ..Default::default()
+    },
+    StateDiff {
+        address_to_nonce: HashMap::from([(address(1), Nonce(Felt::ONE))]),
+        class_hash_to_compiled_class_hash: HashMap::from([
+            (class_hash(10), CompiledClassHash(Felt::from(101_u128))),
+        ]),
+        ..Default::default()
+    },
+)]
+#[tokio::test]
+async fn test_compute_state_diff(
+    #[case] previous_state_diff: StateDiff,
+    #[case] state_diff: StateDiff,
+    #[case] expected_state_diff: StateDiff,
+) {
+    let (storage, roots) = commit_diffs(&[previous_state_diff, state_diff]).await;
+    assert_eq!(
+        state_diff_between(&storage, roots[1], roots[2]).await,
+        expected_state_diff
+    );
+}
+
+/// The diff from a state to an earlier state reverts the values modified in between, and deletes
+/// the values added in between.
+#[tokio::test]
+async fn test_compute_state_diff_backward() {
+    let (storage, roots) = commit_diffs(&[
+        storage_diff(&[(1, 5, 50), (1, 6, 60)]),
+        storage_diff(&[(1, 5, 51), (1, 7, 70), (2, 5, 80)]),
+    ])
+    .await;
+    assert_eq!(
+        state_diff_between(&storage, roots[2], roots[1]).await,
+        storage_diff(&[(1, 5, 50), (1, 7, 0), (2, 5, 0)])
+    );
+    assert_eq!(
+        state_diff_between(&storage, roots[2], roots[0]).await,
+        storage_diff(&[(1, 5, 0), (1, 6, 0), (1, 7, 0), (2, 5, 0)])
+    );
+}
+
+#[tokio::test]
+async fn test_compute_state_diff_with_missing_node() {
+    let (mut storage, roots) =
+        commit_diffs(&[storage_diff(&[(1, 5, 50)]), storage_diff(&[(1, 5, 51)])]).await;
+    // Remove the storage leaves, keeping the inner nodes.
+    storage
+        .storage
+        .retain(|key, _| !key.0.starts_with(&StarknetStorageValue::prefix()));
+    assert!(matches!(
+        compute_state_diff(&storage, roots[1].0, roots[2].0, roots[1].1, roots[2].1).await,
+        Err(StateReaderError::Traversal(TraversalError::StorageRead(_)))
+    ));
+}
@@ -0,0 +1,22 @@
+use ethnum::U256;
+use thiserror::Error;
+
+use crate::block_committer::errors::RootRegistryError;
+use crate::patricia_merkle_tree::errors::{TraversalError, TypesError};
+use crate::storage::errors::{DeserializationError, StorageError};
+
+#[derive(Debug, Error)]
+pub enum StateReaderError {
+    #[error(transparent)]
+    Traversal(#[from] TraversalError),
+    #[error(transparent)]
+    RootRegistry(#[from] RootRegistryError),
+    #[error(transparent)]
+    Storage(#[from] StorageError),
+    #[error(transparent)]
+    Deserialization(#[from] DeserializationError),
+    #[error(transparent)]
+    Types(#[from] TypesError<U256>),
+}
+
+pub type StateReaderResult<T> = Result<T, StateReaderError>;
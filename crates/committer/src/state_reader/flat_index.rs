@@ -0,0 +1,247 @@
+//! A flat index of the state, kept alongside the tries when enabled by the configuration (see
+//! [crate::block_committer::input::Config::flat_state_index]). It maps each
+//! (contract address, storage key) to its storage value, and each contract address to its contract
+//! state, so that the state is read with a single storage access rather than by traversing the
+//! tries.
+//!
+//! The index holds the state at a single root of the contracts trie, which is stored along with
+//! it. A forest committed on top of that root (e.g., the next block, or the revert of the last
+//! block) updates the index along with its facts. Any other forest (e.g., after a reorg) rewrites
+//! the entries in which the state at the root of the index differs from the new state, once its
+//! facts are written. If the state at the root of the index cannot be traversed (e.g., its nodes
+//! were pruned), the index is marked as stale instead, and it is neither read nor updated from then
+//! on. The readers of [crate::state_reader::reader] use the index for reads at its root, and
+//! traverse the tries otherwise.
+
+use std::collections::HashMap;
+
+use log::warn;
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey, StarknetStorageValue};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TraversalResult;
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, LeafModifications};
+use crate::patricia_merkle_tree::traversal::{diff_leaves, LeafDiff};
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::state_reader::errors::StateReaderResult;
+use crate::storage::db_object::{DBObject, Deserializable};
+use crate::storage::storage_trait::{
+    create_db_key, AsyncStorage, StorageKey, StorageResult, StorageValue,
+};
+
+#[cfg(test)]
+#[path = "flat_index_test.rs"]
+pub mod flat_index_test;
+
+const FLAT_STORAGE_VALUE_PREFIX: &[u8] = b"flat_storage_value";
+const FLAT_CONTRACT_STATE_PREFIX: &[u8] = b"flat_contract_state";
+const FLAT_STATE_ROOT_PREFIX: &[u8] = b"flat_state_root";
+
+/// The key of the root of the contracts trie whose state the index holds. An empty value marks
+/// the index as stale.
+fn flat_state_root_key() -> StorageKey {
+    create_db_key(FLAT_STATE_ROOT_PREFIX.to_vec(), &[])
+}
+
+/// Returns the root of the contracts trie whose state the index holds, or None if the index is
+/// stale. An index which was never written holds the empty state.
+async fn flat_state_index_root(storage: &impl AsyncStorage) -> StorageResult<Option<HashOutput>> {
+    Ok(match storage.get(&flat_state_root_key()).await? {
+        None => Some(HashOutput::ROOT_OF_EMPTY_TREE),
+        Some(StorageValue(root)) if root.is_empty() => None,
+        Some(StorageValue(root)) => Some(HashOutput(Felt::from_bytes_be_slice(&root))),
+    })
+}
+
+/// Returns whether the index holds the state at the given root of the contracts trie.
+pub(crate) async fn is_flat_state_index_at(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+) -> StorageResult<bool> {
+    Ok(flat_state_index_root(storage).await? == Some(contracts_trie_root_hash))
+}
+
+/// Returns the values to write to the storage in order to move the index from the given previous
+/// root of the contracts trie to the given new root, given the entries modified in between. If
+/// the index is not at the previous root, returns None; the index is then rewritten by
+/// [rewrite_flat_state_index] once the new tries are written.
+pub(crate) async fn flat_state_index_update(
+    storage: &impl AsyncStorage,
+    previous_contracts_trie_root_hash: HashOutput,
+    contracts_trie_root_hash: HashOutput,
+    entries: &HashMap<StorageKey, StorageValue>,
+) -> StorageResult<Option<HashMap<StorageKey, StorageValue>>> {
+    if !is_flat_state_index_at(storage, previous_contracts_trie_root_hash).await? {
+        return Ok(None);
+    }
+    let mut update = entries.clone();
+    update.insert(
+        flat_state_root_key(),
+        StorageValue(contracts_trie_root_hash.0.to_bytes_be().to_vec()),
+    );
+    Ok(Some(update))
+}
+
+/// Moves the index from its root to the given root of the contracts trie, whose tries are already
+/// in the storage, by rewriting the entries of the leaves which differ between the two states.
+/// If the state at the root of the index cannot be traversed, marks the index as stale instead.
+pub(crate) async fn rewrite_flat_state_index(
+    storage: &mut impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+) -> StorageResult<()> {
+    let Some(index_root_hash) = flat_state_index_root(storage).await? else {
+        return Ok(());
+    };
+    let (mut update, root) = match flat_state_entries_between(
+        storage,
+        index_root_hash,
+        contracts_trie_root_hash,
+    )
+    .await
+    {
+        Ok(entries) => (entries, contracts_trie_root_hash.0.to_bytes_be().to_vec()),
+        Err(error) => {
+            warn!("Marking the flat state index as stale, as it cannot be rewritten: {error}");
+            (HashMap::new(), Vec::new())
+        }
+    };
+    update.insert(flat_state_root_key(), StorageValue(root));
+    storage.mset(update).await
+}
+
+/// Returns the index entries of the leaves which differ between the states with the given roots
+/// of the contracts trie: the new storage values of the modified keys, and the new states of the
+/// modified contracts.
+async fn flat_state_entries_between(
+    storage: &impl AsyncStorage,
+    previous_contracts_trie_root_hash: HashOutput,
+    contracts_trie_root_hash: HashOutput,
+) -> TraversalResult<HashMap<StorageKey, StorageValue>> {
+    let mut storage_updates = HashMap::new();
+    let mut contract_states = HashMap::new();
+    for LeafDiff {
+        index,
+        previous,
+        new,
+    } in diff_leaves::<ContractState>(
+        storage,
+        previous_contracts_trie_root_hash,
+        contracts_trie_root_hash,
+    )
+    .await?
+    {
+        let address = ContractAddress(index.to_leaf_felt());
+        if previous.storage_root_hash != new.storage_root_hash {
+            let updates: LeafModifications<StarknetStorageValue> =
+                diff_leaves::<StarknetStorageValue>(
+                    storage,
+                    previous.storage_root_hash,
+                    new.storage_root_hash,
+                )
+                .await?
+                .into_iter()
+                .map(|LeafDiff { index, new, .. }| (index, new))
+                .collect();
+            storage_updates.insert(address, updates);
+        }
+        contract_states.insert(address, new);
+    }
+    Ok(flat_state_entries(&storage_updates, contract_states.iter()))
+}
+
+/// Returns the index key of the storage value at the given leaf of the storage trie of the given
+/// contract.
+pub(crate) fn flat_storage_value_key(
+    address: &ContractAddress,
+    leaf_index: &NodeIndex,
+) -> StorageKey {
+    create_db_key(
+        FLAT_STORAGE_VALUE_PREFIX.to_vec(),
+        &[
+            address.0.to_bytes_be(),
+            leaf_index.to_leaf_felt().to_bytes_be(),
+        ]
+        .concat(),
+    )
+}
+
+fn flat_contract_state_key(address: &ContractAddress) -> StorageKey {
+    create_db_key(
+        FLAT_CONTRACT_STATE_PREFIX.to_vec(),
+        &address.0.to_bytes_be(),
+    )
+}
+
+/// Returns the index entries of the given storage modifications and contract states. Deleted
+/// storage values are indexed as zero, which is also the value of storage keys that are not
+/// indexed.
+pub(crate) fn flat_state_entries<'a>(
+    storage_updates: &HashMap<ContractAddress, LeafModifications<StarknetStorageValue>>,
+    contract_states: impl Iterator<Item = (&'a ContractAddress, &'a ContractState)>,
+) -> HashMap<StorageKey, StorageValue> {
+    storage_updates
+        .iter()
+        .flat_map(|(address, updates)| {
+            updates.iter().map(|(leaf_index, value)| {
+                (
+                    flat_storage_value_key(address, leaf_index),
+                    value.serialize(),
+                )
+            })
+        })
+        .chain(contract_states.map(|(address, contract_state)| {
+            (flat_contract_state_key(address), contract_state.serialize())
+        }))
+        .collect()
+}
+
+/// Reads the values of the given storage keys of the given contract from the flat index. The
+/// caller checks that the index is at the requested root (see [is_flat_state_index_at]).
+pub(crate) async fn get_flat_storage_values(
+    storage: &impl AsyncStorage,
+    address: &ContractAddress,
+    keys: &[StarknetStorageKey],
+) -> StateReaderResult<HashMap<StarknetStorageKey, StarknetStorageValue>> {
+    let index_keys = keys
+        .iter()
+        .map(|key| {
+            Ok(flat_storage_value_key(
+                address,
+                &NodeIndex::try_from_starknet_storage_key(key)?,
+            ))
+        })
+        .collect::<StateReaderResult<Vec<StorageKey>>>()?;
+    let values = storage.mget(&index_keys).await?;
+    keys.iter()
+        .zip(values)
+        .map(|(key, value)| {
+            let value = match value {
+                Some(value) => StarknetStorageValue::deserialize(&value)?,
+                None => StarknetStorageValue::default(),
+            };
+            Ok((*key, value))
+        })
+        .collect()
+}
+
+/// Reads the states of the given contracts from the flat index. The caller checks that the index
+/// is at the requested root (see [is_flat_state_index_at]).
+pub(crate) async fn get_flat_contract_states(
+    storage: &impl AsyncStorage,
+    addresses: &[ContractAddress],
+) -> StateReaderResult<HashMap<ContractAddress, ContractState>> {
+    let index_keys: Vec<StorageKey> = addresses.iter().map(flat_contract_state_key).collect();
+    let values = storage.mget(&index_keys).await?;
+    addresses
+        .iter()
+        .zip(values)
+        .map(|(address, value)| {
+            let contract_state = match value {
+                Some(value) => ContractState::deserialize(&value)?,
+                None => ContractState::default(),
+            };
+            Ok((*address, contract_state))
+        })
+        .collect()
+}
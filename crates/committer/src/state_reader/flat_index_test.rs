@@ -0,0 +1,376 @@
+use std::collections::HashMap;
+
+use rstest::rstest;
+
+use crate::block_committer::commit::commit_state_diff;
+use crate::block_committer::errors::BlockCommitmentError;
+use crate::block_committer::input::{ConfigImpl, StarknetStorageValue, StateDiff};
+use crate::felt::Felt;
+use crate::forest_errors::ForestError;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, initial_state_diff, inverse_update_state_diff, key, update_state_diff, value,
+};
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::original_skeleton_tree::errors::OriginalSkeletonTreeError;
+use crate::patricia_merkle_tree::traversal::node_db_key;
+use crate::state_reader::flat_index::{
+    get_flat_contract_states, get_flat_storage_values, is_flat_state_index_at,
+};
+use crate::state_reader::reader::{get_contract_state, get_storage_value};
+use crate::storage::errors::StorageError;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey};
+
+fn config(warn_on_trivial_modifications: bool, flat_state_index: bool) -> ConfigImpl {
+    ConfigImpl::new(warn_on_trivial_modifications, log::LevelFilter::Debug)
+        .with_flat_state_index(flat_state_index)
+}
+
+/// Commits each of the given state diffs on top of the contracts trie root of the block with the
+/// given index (or on top of the empty state), writing the flat state index if enabled. Returns
+/// the storage and the roots after each block.
+async fn commit_blocks(
+    blocks: &[(Option<usize>, StateDiff)],
+    flat_state_index: bool,
+) -> (MapStorage, Vec<(HashOutput, HashOutput)>) {
+    let mut storage = MapStorage::default();
+    let mut roots: Vec<(HashOutput, HashOutput)> = Vec::new();
+    for (parent, state_diff) in blocks {
+        let (contracts_trie_root_hash, classes_trie_root_hash) = match parent {
+            Some(parent) => roots[*parent],
+            None => (
+                HashOutput::ROOT_OF_EMPTY_TREE,
+                HashOutput::ROOT_OF_EMPTY_TREE,
+            ),
+        };
+        let filled_forest = commit_state_diff(
+            &storage,
+            state_diff,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+            &config(false, flat_state_index),
+        )
+        .await
+        .unwrap();
+        filled_forest.write_to_storage(&mut storage).await.unwrap();
+        roots.push((
+            filled_forest.get_contract_root_hash(),
+            filled_forest.get_compiled_class_root_hash(),
+        ));
+    }
+    (storage, roots)
+}
+
+/// Asserts that the state read from the given storage at the given root is the same as the state
+/// read from the tries only.
+async fn assert_state_matches_tries(
+    storage: &MapStorage,
+    tries_storage: &MapStorage,
+    contracts_trie_root_hash: HashOutput,
+) {
+    for contract in [1, 2, 3, 4] {
+        assert_eq!(
+            get_contract_state(storage, contracts_trie_root_hash, &address(contract))
+                .await
+                .unwrap(),
+            get_contract_state(tries_storage, contracts_trie_root_hash, &address(contract))
+                .await
+                .unwrap()
+        );
+        for storage_key in [5, 6, 7, 8] {
+            assert_eq!(
+                get_storage_value(
+                    storage,
+                    contracts_trie_root_hash,
+                    &address(contract),
+                    &key(storage_key)
+                )
+                .await
+                .unwrap(),
+                get_storage_value(
+                    tries_storage,
+                    contracts_trie_root_hash,
+                    &address(contract),
+                    &key(storage_key)
+                )
+                .await
+                .unwrap()
+            );
+        }
+    }
+}
+
+#[rstest]
+#[case::initial_state(vec![(None, initial_state_diff())])]
+#[case::updated_state(vec![(None, initial_state_diff()), (Some(0), update_state_diff())])]
+// The inverse diff reverts the last block, on top of the root the index is at.
+#[case::reverted_state(vec![
+    (None, initial_state_diff()),
+    (Some(0), update_state_diff()),
+    (Some(1), inverse_update_state_diff()),
+])]
+#[tokio::test]
+async fn test_flat_index_matches_tries(#[case] blocks: Vec<(Option<usize>, StateDiff)>) {
+    let (storage, roots) = commit_blocks(&blocks, true).await;
+    let (tries_storage, _) = commit_blocks(&blocks, false).await;
+    let (contracts_trie_root_hash, _) = *roots.last().unwrap();
+    assert!(is_flat_state_index_at(&storage, contracts_trie_root_hash)
+        .await
+        .unwrap());
+
+    let addresses = [address(1), address(2), address(3), address(4)];
+    let contract_states = get_flat_contract_states(&storage, &addresses)
+        .await
+        .unwrap();
+    for address in addresses {
+        assert_eq!(
+            contract_states[&address],
+            get_contract_state(&tries_storage, contracts_trie_root_hash, &address)
+                .await
+                .unwrap()
+        );
+    }
+    let storage_values = get_flat_storage_values(&storage, &address(1), &[key(5), key(6), key(8)])
+        .await
+        .unwrap();
+    for storage_key in [key(5), key(6), key(8)] {
+        assert_eq!(
+            storage_values[&storage_key],
+            get_storage_value(
+                &tries_storage,
+                contracts_trie_root_hash,
+                &address(1),
+                &storage_key
+            )
+            .await
+            .unwrap()
+        );
+    }
+}
+
+/// Reads at the root of the index are served by the index, while reads at other roots traverse
+/// the tries.
+#[tokio::test]
+async fn test_reads_use_flat_index_at_its_root() {
+    let (mut storage, roots) = commit_blocks(
+        &[(None, initial_state_diff()), (Some(0), update_state_diff())],
+        true,
+    )
+    .await;
+    // The leaf holding the value 60 of key 6 of contract 1, which is not modified by the update.
+    let leaf_key: StorageKey =
+        node_db_key::<StarknetStorageValue>(HashOutput(Felt::from(60_u128)), true);
+    storage.delete(&leaf_key).await.unwrap();
+
+    assert_eq!(
+        get_storage_value(&storage, roots[1].0, &address(1), &key(6))
+            .await
+            .unwrap(),
+        value(60)
+    );
+    assert!(
+        get_storage_value(&storage, roots[0].0, &address(1), &key(6))
+            .await
+            .is_err()
+    );
+}
+
+/// State diffs of a reorg: the third block replaces the second one, and the fourth is committed on
+/// top of it.
+fn reorged_blocks() -> Vec<(Option<usize>, StateDiff)> {
+    vec![
+        (None, initial_state_diff()),
+        (Some(0), update_state_diff()),
+        (
+            Some(0),
+            StateDiff {
+                storage_updates: HashMap::from([(address(1), HashMap::from([(key(8), value(1))]))]),
+                ..Default::default()
+            },
+        ),
+        (Some(2), inverse_update_state_diff()),
+    ]
+}
+
+/// A commitment on top of a root other than the root of the index (e.g., a reorg) rewrites the
+/// entries which differ between the state at the root of the index and the new state, and moves
+/// the index to the new root.
+#[rstest]
+#[case::reorg(3)]
+#[case::block_on_top_of_reorg(4)]
+#[tokio::test]
+async fn test_commit_on_other_root_rewrites_flat_index(#[case] n_blocks: usize) {
+    let blocks = &reorged_blocks()[..n_blocks];
+    let (storage, roots) = commit_blocks(blocks, true).await;
+    let (tries_storage, _) = commit_blocks(blocks, false).await;
+    let (contracts_trie_root_hash, _) = *roots.last().unwrap();
+
+    assert!(is_flat_state_index_at(&storage, contracts_trie_root_hash)
+        .await
+        .unwrap());
+    assert_state_matches_tries(&storage, &tries_storage, contracts_trie_root_hash).await;
+    // The value set by the replaced block is rewritten.
+    assert_eq!(
+        get_flat_storage_values(&storage, &address(1), &[key(7)])
+            .await
+            .unwrap()[&key(7)],
+        get_storage_value(
+            &tries_storage,
+            contracts_trie_root_hash,
+            &address(1),
+            &key(7)
+        )
+        .await
+        .unwrap()
+    );
+}
+
+/// If the state at the root of the index is not in the storage (e.g., it was pruned), a commitment
+/// on top of another root marks the index as stale, so that later reads traverse the tries.
+#[tokio::test]
+async fn test_commit_on_other_root_marks_flat_index_stale() {
+    let blocks = reorged_blocks();
+    let (mut storage, roots) = commit_blocks(&blocks[..2], true).await;
+    // The root node of the contracts trie at the root of the index.
+    storage
+        .delete(&node_db_key::<ContractState>(roots[1].0, false))
+        .await
+        .unwrap();
+    let filled_forest = commit_state_diff(
+        &storage,
+        &blocks[2].1,
+        roots[0].0,
+        roots[0].1,
+        &config(false, true),
+    )
+    .await
+    .unwrap();
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+    let contracts_trie_root_hash = filled_forest.get_contract_root_hash();
+
+    for root_hash in [roots[0].0, roots[1].0, contracts_trie_root_hash] {
+        assert!(!is_flat_state_index_at(&storage, root_hash).await.unwrap());
+    }
+    let (tries_storage, _) = commit_blocks(&blocks[..3], false).await;
+    assert_state_matches_tries(&storage, &tries_storage, contracts_trie_root_hash).await;
+}
+
+#[tokio::test]
+async fn test_flat_index_is_optional() {
+    let mut storage = MapStorage::default();
+    let filled_forest = commit_state_diff(
+        &storage,
+        &initial_state_diff(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &config(false, false),
+    )
+    .await
+    .unwrap();
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+    assert!(filled_forest.flat_state_entries.is_empty());
+    assert_eq!(storage.storage, filled_forest.serialize());
+}
+
+/// With the flat state index, trivial modifications of storage values are checked against the
+/// index, so the previous modified leaves need not be in the storage.
+#[rstest]
+#[case::with_flat_index(true)]
+#[case::without_flat_index(false)]
+#[tokio::test]
+async fn test_trivial_modification_check_uses_flat_index(#[case] flat_state_index: bool) {
+    let (mut storage, roots) = commit_blocks(&[(None, initial_state_diff())], true).await;
+    let (contracts_trie_root_hash, classes_trie_root_hash) = roots[0];
+    // The leaf holding the value of key 5 of contract 1.
+    let leaf_key: StorageKey =
+        node_db_key::<StarknetStorageValue>(HashOutput(Felt::from(50_u128)), true);
+    storage.delete(&leaf_key).await.unwrap();
+
+    let result = commit_state_diff(
+        &storage,
+        &update_state_diff(),
+        contracts_trie_root_hash,
+        classes_trie_root_hash,
+        &config(true, flat_state_index),
+    )
+    .await;
+    if flat_state_index {
+        assert!(result.is_ok());
+    } else {
+        assert!(matches!(
+            result,
+            Err(BlockCommitmentError::ForestError(
+                ForestError::OriginalSkeleton(OriginalSkeletonTreeError::StorageRead(
+                    StorageError::MissingKey(key)
+                ))
+            )) if key == leaf_key
+        ));
+    }
+}
+
+/// An index which was never written holds the empty state, so it has no entries.
+#[tokio::test]
+async fn test_flat_index_of_empty_state() {
+    let storage = MapStorage::default();
+    assert!(
+        is_flat_state_index_at(&storage, HashOutput::ROOT_OF_EMPTY_TREE)
+            .await
+            .unwrap()
+    );
+    assert!(!is_flat_state_index_at(&storage, HashOutput(Felt::ONE))
+        .await
+        .unwrap());
+    assert_eq!(
+        get_flat_contract_states(&storage, &[address(1)])
+            .await
+            .unwrap()[&address(1)],
+        ContractState::default()
+    );
+    assert_eq!(
+        get_flat_storage_values(&storage, &address(1), &[key(5)])
+            .await
+            .unwrap()[&key(5)],
+        StarknetStorageValue::default()
+    );
+}
+
+/// Deleted and never written entries of the index have the default value, while the values of
+/// other contracts are kept.
+#[tokio::test]
+async fn test_flat_index_missing_and_deleted_entries() {
+    let set_value = |contract, storage_key, storage_value| StateDiff {
+        storage_updates: HashMap::from([(
+            address(contract),
+            HashMap::from([(key(storage_key), value(storage_value))]),
+        )]),
+        ..Default::default()
+    };
+    let (storage, roots) = commit_blocks(
+        &[
+            (None, set_value(1, 5, 50)),
+            (Some(0), set_value(2, 5, 50)),
+            (Some(1), set_value(1, 5, 0)),
+        ],
+        true,
+    )
+    .await;
+    assert!(is_flat_state_index_at(&storage, roots[2].0).await.unwrap());
+
+    let storage_values = get_flat_storage_values(&storage, &address(1), &[key(5), key(6)])
+        .await
+        .unwrap();
+    assert_eq!(storage_values[&key(5)], StarknetStorageValue::default());
+    assert_eq!(storage_values[&key(6)], StarknetStorageValue::default());
+    assert_eq!(
+        get_flat_storage_values(&storage, &address(2), &[key(5)])
+            .await
+            .unwrap()[&key(5)],
+        value(50)
+    );
+    let contract_states = get_flat_contract_states(&storage, &[address(1), address(3)])
+        .await
+        .unwrap();
+    assert_eq!(contract_states[&address(1)], ContractState::default());
+    assert_eq!(contract_states[&address(3)], ContractState::default());
+}
@@ -0,0 +1,192 @@
+use std::collections::HashMap;
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey, StarknetStorageValue};
+use crate::block_committer::root_registry::{get_block_roots, BlockNumber};
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::TraversalError;
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash};
+use crate::patricia_merkle_tree::node_data::inner_node::NodeData;
+use crate::patricia_merkle_tree::node_data::leaf::{ContractState, Leaf};
+use crate::patricia_merkle_tree::traversal::fetch_paths;
+use crate::patricia_merkle_tree::types::{NodeIndex, SortedLeafIndices};
+use crate::state_reader::errors::StateReaderResult;
+use crate::state_reader::flat_index::{
+    get_flat_contract_states, get_flat_storage_values, is_flat_state_index_at,
+};
+use crate::storage::storage_trait::AsyncStorage;
+
+#[cfg(test)]
+#[path = "reader_test.rs"]
+pub mod reader_test;
+
+/// Reads the leaves at the given indices from the tree with the given root. The paths to the
+/// leaves are fetched layer by layer, with a single storage access per layer. Leaves that are not
+/// in the tree are returned as empty (default) leaves.
+pub async fn get_leaves<L: Leaf>(
+    storage: &impl AsyncStorage,
+    root_hash: HashOutput,
+    leaf_indices: &[NodeIndex],
+) -> StateReaderResult<HashMap<NodeIndex, L>> {
+    if let Some(index) = leaf_indices.iter().find(|index| !index.is_leaf()) {
+        return Err(TraversalError::NonLeafIndex(*index).into());
+    }
+    let mut leaves: HashMap<NodeIndex, L> = leaf_indices
+        .iter()
+        .map(|index| (*index, L::default()))
+        .collect();
+    let mut sorted_leaf_indices: Vec<NodeIndex> = leaves.keys().copied().collect();
+    sorted_leaf_indices.sort();
+    // Only the paths to the given leaves are traversed, so the fetched leaves are the given ones.
+    fetch_paths::<L>(
+        storage,
+        root_hash,
+        SortedLeafIndices::new(&mut sorted_leaf_indices),
+        |index, node| {
+            if let NodeData::Leaf(leaf) = node.data {
+                leaves.insert(index, leaf);
+            }
+        },
+    )
+    .await?;
+    Ok(leaves)
+}
+
+/// Reads the states of the given contracts, from the contracts trie with the given root. If the
+/// flat state index (see [crate::state_reader::flat_index]) is at this root, the states are read
+/// from it with a single storage access instead.
+pub async fn get_contract_states(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+    addresses: &[ContractAddress],
+) -> StateReaderResult<HashMap<ContractAddress, ContractState>> {
+    if is_flat_state_index_at(storage, contracts_trie_root_hash).await? {
+        return get_flat_contract_states(storage, addresses).await;
+    }
+    let leaf_indices = addresses
+        .iter()
+        .map(NodeIndex::try_from_contract_address)
+        .collect::<Result<Vec<_>, _>>()?;
+    let leaves =
+        get_leaves::<ContractState>(storage, contracts_trie_root_hash, &leaf_indices).await?;
+    Ok(addresses
+        .iter()
+        .zip(leaf_indices)
+        .map(|(address, index)| (*address, leaves[&index].clone()))
+        .collect())
+}
+
+/// Reads the state of the given contract, from the contracts trie with the given root.
+pub async fn get_contract_state(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+    address: &ContractAddress,
+) -> StateReaderResult<ContractState> {
+    let mut contract_states =
+        get_contract_states(storage, contracts_trie_root_hash, &[*address]).await?;
+    Ok(contract_states.remove(address).unwrap_or_default())
+}
+
+/// Reads the state of the given contract as of the given block, from the contracts trie whose root
+/// is recorded for the block (see [crate::block_committer::root_registry]).
+pub async fn get_contract_state_at_block(
+    storage: &impl AsyncStorage,
+    block_number: BlockNumber,
+    address: &ContractAddress,
+) -> StateReaderResult<ContractState> {
+    let roots = get_block_roots(storage, block_number).await?;
+    get_contract_state(storage, roots.contracts_trie_root_hash, address).await
+}
+
+/// Reads the values of the given storage keys of the given contract, given the root of the
+/// contracts trie. If the flat state index (see [crate::state_reader::flat_index]) is at this root,
+/// the values are read from it with a single storage access instead.
+pub async fn get_storage_values(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+    address: &ContractAddress,
+    keys: &[StarknetStorageKey],
+) -> StateReaderResult<HashMap<StarknetStorageKey, StarknetStorageValue>> {
+    if is_flat_state_index_at(storage, contracts_trie_root_hash).await? {
+        return get_flat_storage_values(storage, address, keys).await;
+    }
+    let contract_state = get_contract_state(storage, contracts_trie_root_hash, address).await?;
+    let leaf_indices = keys
+        .iter()
+        .map(NodeIndex::try_from_starknet_storage_key)
+        .collect::<Result<Vec<_>, _>>()?;
+    let leaves = get_leaves::<StarknetStorageValue>(
+        storage,
+        contract_state.storage_root_hash,
+        &leaf_indices,
+    )
+    .await?;
+    Ok(keys
+        .iter()
+        .zip(leaf_indices)
+        .map(|(key, index)| (*key, leaves[&index]))
+        .collect())
+}
+
+/// Reads the value of the given storage key of the given contract, given the root of the
+/// contracts trie.
+pub async fn get_storage_value(
+    storage: &impl AsyncStorage,
+    contracts_trie_root_hash: HashOutput,
+    address: &ContractAddress,
+    key: &StarknetStorageKey,
+) -> StateReaderResult<StarknetStorageValue> {
+    let mut values =
+        get_storage_values(storage, contracts_trie_root_hash, address, &[*key]).await?;
+    Ok(values.remove(key).unwrap_or_default())
+}
+
+/// Reads the value of the given storage key of the given contract as of the given block.
+pub async fn get_storage_value_at_block(
+    storage: &impl AsyncStorage,
+    block_number: BlockNumber,
+    address: &ContractAddress,
+    key: &StarknetStorageKey,
+) -> StateReaderResult<StarknetStorageValue> {
+    let roots = get_block_roots(storage, block_number).await?;
+    get_storage_value(storage, roots.contracts_trie_root_hash, address, key).await
+}
+
+/// Reads the compiled class hashes of the given classes, from the classes trie with the given root.
+pub async fn get_compiled_class_hashes(
+    storage: &impl AsyncStorage,
+    classes_trie_root_hash: HashOutput,
+    class_hashes: &[ClassHash],
+) -> StateReaderResult<HashMap<ClassHash, CompiledClassHash>> {
+    let leaf_indices = class_hashes
+        .iter()
+        .map(NodeIndex::try_from_class_hash)
+        .collect::<Result<Vec<_>, _>>()?;
+    let leaves =
+        get_leaves::<CompiledClassHash>(storage, classes_trie_root_hash, &leaf_indices).await?;
+    Ok(class_hashes
+        .iter()
+        .zip(leaf_indices)
+        .map(|(class_hash, index)| (*class_hash, leaves[&index]))
+        .collect())
+}
+
+/// Reads the compiled class hash of the given class, from the classes trie with the given root.
+pub async fn get_compiled_class_hash(
+    storage: &impl AsyncStorage,
+    classes_trie_root_hash: HashOutput,
+    class_hash: &ClassHash,
+) -> StateReaderResult<CompiledClassHash> {
+    let mut compiled_class_hashes =
+        get_compiled_class_hashes(storage, classes_trie_root_hash, &[*class_hash]).await?;
+    Ok(compiled_class_hashes.remove(class_hash).unwrap_or_default())
+}
+
+/// Reads the compiled class hash of the given class as of the given block.
+pub async fn get_compiled_class_hash_at_block(
+    storage: &impl AsyncStorage,
+    block_number: BlockNumber,
+    class_hash: &ClassHash,
+) -> StateReaderResult<CompiledClassHash> {
+    let roots = get_block_roots(storage, block_number).await?;
+    get_compiled_class_hash(storage, roots.classes_trie_root_hash, class_hash).await
+}
@@ -0,0 +1,235 @@
+use std::collections::HashMap;
+
+use ethnum::U256;
+use rstest::rstest;
+
+use crate::block_committer::input::{ContractAddress, StarknetStorageKey, StarknetStorageValue};
+use crate::felt::Felt;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::errors::{TraversalError, TypesError};
+use crate::patricia_merkle_tree::filled_tree::node::{ClassHash, CompiledClassHash, Nonce};
+use crate::patricia_merkle_tree::internal_test_utils::{
+    address, class_hash, create_committed_state, key,
+};
+use crate::patricia_merkle_tree::merkle_proof::proof::proof_test::create_storage_trie;
+use crate::patricia_merkle_tree::node_data::leaf::ContractState;
+use crate::patricia_merkle_tree::types::NodeIndex;
+use crate::state_reader::errors::StateReaderError;
+use crate::state_reader::reader::{
+    get_compiled_class_hash, get_compiled_class_hashes, get_contract_state, get_contract_states,
+    get_leaves, get_storage_value, get_storage_values,
+};
+use crate::storage::db_object::Deserializable;
+use crate::storage::map_storage::MapStorage;
+
+#[rstest]
+#[case::existing(1, 5, 50)]
+#[case::other_contract(2, 5, 70)]
+#[case::missing_key(2, 6, 0)]
+#[case::missing_contract(3, 5, 0)]
+#[tokio::test]
+async fn test_get_storage_value(
+    #[case] contract: u128,
+    #[case] storage_key: u128,
+    #[case] expected_value: u128,
+) {
+    let (storage, contracts_trie_root_hash, _) = create_committed_state().await;
+    assert_eq!(
+        get_storage_value(
+            &storage,
+            contracts_trie_root_hash,
+            &address(contract),
+            &key(storage_key)
+        )
+        .await
+        .unwrap(),
+        StarknetStorageValue(Felt::from(expected_value))
+    );
+}
+
+#[tokio::test]
+async fn test_get_storage_values() {
+    let (storage, contracts_trie_root_hash, _) = create_committed_state().await;
+    let keys = [key(5), key(6), key(7), key(5)];
+    assert_eq!(
+        get_storage_values(&storage, contracts_trie_root_hash, &address(1), &keys)
+            .await
+            .unwrap(),
+        HashMap::from([
+            (key(5), StarknetStorageValue(Felt::from(50_u128))),
+            (key(6), StarknetStorageValue(Felt::from(60_u128))),
+            (key(7), StarknetStorageValue::default()),
+        ])
+    );
+}
+
+#[tokio::test]
+async fn test_get_contract_states() {
+    let (storage, contracts_trie_root_hash, _) = create_committed_state().await;
+    let contract_states = get_contract_states(
+        &storage,
+        contracts_trie_root_hash,
+        &[address(1), address(2), address(3)],
+    )
+    .await
+    .unwrap();
+    assert_eq!(contract_states[&address(1)].nonce, Nonce(Felt::ONE));
+    assert_eq!(contract_states[&address(1)].class_hash, class_hash(10));
+    assert_eq!(contract_states[&address(2)].nonce, Nonce::default());
+    assert_eq!(contract_states[&address(2)].class_hash, class_hash(11));
+    assert_ne!(
+        contract_states[&address(1)].storage_root_hash,
+        contract_states[&address(2)].storage_root_hash
+    );
+    assert_eq!(contract_states[&address(3)], ContractState::default());
+    assert_eq!(
+        get_contract_state(&storage, contracts_trie_root_hash, &address(2))
+            .await
+            .unwrap(),
+        contract_states[&address(2)]
+    );
+}
+
+#[tokio::test]
+async fn test_get_compiled_class_hashes() {
+    let (storage, _, classes_trie_root_hash) = create_committed_state().await;
+    assert_eq!(
+        get_compiled_class_hashes(
+            &storage,
+            classes_trie_root_hash,
+            &[class_hash(10), class_hash(11), class_hash(12)]
+        )
+        .await
+        .unwrap(),
+        HashMap::from([
+            (class_hash(10), CompiledClassHash(Felt::from(100_u128))),
+            (class_hash(11), CompiledClassHash(Felt::from(101_u128))),
+            (class_hash(12), CompiledClassHash::default()),
+        ])
+    );
+    assert_eq!(
+        get_compiled_class_hash(&storage, classes_trie_root_hash, &class_hash(11))
+            .await
+            .unwrap(),
+        CompiledClassHash(Felt::from(101_u128))
+    );
+}
+
+#[tokio::test]
+async fn test_read_from_empty_state() {
+    let storage = MapStorage::default();
+    assert_eq!(
+        get_storage_value(
+            &storage,
+            HashOutput::ROOT_OF_EMPTY_TREE,
+            &address(1),
+            &key(1)
+        )
+        .await
+        .unwrap(),
+        StarknetStorageValue::default()
+    );
+    assert_eq!(
+        get_compiled_class_hash(
+            &storage,
+            HashOutput::ROOT_OF_EMPTY_TREE,
+            &ClassHash(Felt::ONE)
+        )
+        .await
+        .unwrap(),
+        CompiledClassHash::default()
+    );
+}
+
+/// Addresses, keys and class hashes of at least 2^251 are not in the tries, and fail the read.
+#[tokio::test]
+async fn test_read_out_of_range_felts() {
+    let (storage, contracts_trie_root_hash, classes_trie_root_hash) =
+        create_committed_state().await;
+    let out_of_range = Felt::MAX;
+    assert!(matches!(
+        get_contract_state(
+            &storage,
+            contracts_trie_root_hash,
+            &ContractAddress(out_of_range)
+        )
+        .await,
+        Err(StateReaderError::Types(TypesError::OutOfRange { .. }))
+    ));
+    assert!(matches!(
+        get_storage_value(
+            &storage,
+            contracts_trie_root_hash,
+            &address(1),
+            &StarknetStorageKey(out_of_range)
+        )
+        .await,
+        Err(StateReaderError::Types(TypesError::OutOfRange { .. }))
+    ));
+    assert!(matches!(
+        get_compiled_class_hash(&storage, classes_trie_root_hash, &ClassHash(out_of_range)).await,
+        Err(StateReaderError::Types(TypesError::OutOfRange { .. }))
+    ));
+}
+
+fn leaf_index(key: u128) -> NodeIndex {
+    NodeIndex::FIRST_LEAF + NodeIndex::new(U256::from(key)).unwrap()
+}
+
+#[rstest]
+// Leaves 0 and 1 share their value.
+#[case::shared_values(&[(0, 7), (1, 7), (6, 8)], &[0, 1, 6])]
+#[case::missing_leaves(&[(0, 7), (6, 8)], &[1, 2, 7, u128::MAX])]
+#[case::empty_tree(&[], &[0, 5])]
+#[case::no_leaves(&[(0, 7)], &[])]
+#[tokio::test]
+async fn test_get_leaves(#[case] tree_leaves: &[(u128, u128)], #[case] keys: &[u128]) {
+    let (storage, root_hash) = create_storage_trie(
+        &tree_leaves
+            .iter()
+            .map(|(key, value)| (U256::from(*key), *value))
+            .collect::<Vec<_>>(),
+    )
+    .await;
+    let tree_leaves: HashMap<u128, u128> = tree_leaves.iter().copied().collect();
+    let leaf_indices: Vec<NodeIndex> = keys.iter().map(|key| leaf_index(*key)).collect();
+    let expected: HashMap<NodeIndex, StarknetStorageValue> = keys
+        .iter()
+        .map(|key| {
+            (
+                leaf_index(*key),
+                StarknetStorageValue(Felt::from(tree_leaves.get(key).copied().unwrap_or(0))),
+            )
+        })
+        .collect();
+    assert_eq!(
+        get_leaves::<StarknetStorageValue>(&storage, root_hash, &leaf_indices)
+            .await
+            .unwrap(),
+        expected
+    );
+}
+
+#[tokio::test]
+async fn test_get_leaves_non_leaf_index() {
+    let (storage, root_hash) = create_storage_trie(&[(U256::ZERO, 1)]).await;
+    assert!(matches!(
+        get_leaves::<StarknetStorageValue>(&storage, root_hash, &[NodeIndex::ROOT]).await,
+        Err(StateReaderError::Traversal(TraversalError::NonLeafIndex(
+            NodeIndex::ROOT
+        )))
+    ));
+}
+
+#[tokio::test]
+async fn test_get_leaves_missing_node() {
+    let (mut storage, root_hash) = create_storage_trie(&[(U256::ZERO, 1), (U256::ONE, 2)]).await;
+    // Remove the leaf nodes, keeping the inner nodes.
+    storage
+        .storage
+        .retain(|key, _| !key.0.starts_with(&StarknetStorageValue::prefix()));
+    assert!(matches!(
+        get_leaves::<StarknetStorageValue>(&storage, root_hash, &[leaf_index(1)]).await,
+        Err(StateReaderError::Traversal(TraversalError::StorageRead(_)))
+    ));
+}
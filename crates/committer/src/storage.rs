@@ -1,4 +1,7 @@
+pub mod cached_storage;
 pub mod db_object;
 pub mod errors;
+pub mod file_storage;
 pub mod map_storage;
+pub mod ref_counted_storage;
 pub mod storage_trait;
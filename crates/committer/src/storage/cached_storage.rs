@@ -0,0 +1,141 @@
+use std::collections::HashMap;
+use std::sync::RwLock;
+
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageResult, StorageValue};
+
+#[cfg(test)]
+#[path = "cached_storage_test.rs"]
+pub mod cached_storage_test;
+
+/// An in-memory read-through and write-through cache over an [AsyncStorage]. Values read from the
+/// underlying storage are cached, so that warming the cache (e.g., by reading ahead the nodes
+/// needed by an upcoming commitment) saves round-trips to the underlying storage later on.
+/// Any key read or written through the cache is cached, including mutable ones (e.g., the recorded
+/// block roots and the flat index), so the cache is only consistent as long as every write to the
+/// underlying storage goes through it. Keys which the underlying storage writes by itself (e.g.,
+/// reference counts) should not be read through the cache.
+pub struct CachedStorage<'a, S: AsyncStorage> {
+    storage: &'a mut S,
+    cache: RwLock<HashMap<StorageKey, StorageValue>>,
+}
+
+impl<'a, S: AsyncStorage> CachedStorage<'a, S> {
+    pub fn new(storage: &'a mut S) -> Self {
+        Self {
+            storage,
+            cache: RwLock::new(HashMap::new()),
+        }
+    }
+
+    /// Returns the number of cached values.
+    pub fn cache_size(&self) -> usize {
+        self.cache.read().expect("Poisoned cache lock.").len()
+    }
+
+    /// Drops all cached values.
+    pub fn clear_cache(&mut self) {
+        self.cache.get_mut().expect("Poisoned cache lock.").clear();
+    }
+
+    fn get_cached(&self, key: &StorageKey) -> Option<StorageValue> {
+        self.cache
+            .read()
+            .expect("Poisoned cache lock.")
+            .get(key)
+            .cloned()
+    }
+
+    fn cache_values(&self, entries: impl Iterator<Item = (StorageKey, StorageValue)>) {
+        self.cache
+            .write()
+            .expect("Poisoned cache lock.")
+            .extend(entries);
+    }
+}
+
+impl<S: AsyncStorage> AsyncStorage for CachedStorage<'_, S> {
+    async fn get(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        if let Some(value) = self.get_cached(key) {
+            return Ok(Some(value));
+        }
+        let value = self.storage.get(key).await?;
+        if let Some(value) = &value {
+            self.cache_values(std::iter::once((key.clone(), value.clone())));
+        }
+        Ok(value)
+    }
+
+    async fn set(
+        &mut self,
+        key: StorageKey,
+        value: StorageValue,
+    ) -> StorageResult<Option<StorageValue>> {
+        self.cache
+            .get_mut()
+            .expect("Poisoned cache lock.")
+            .insert(key.clone(), value.clone());
+        self.storage.set(key, value).await
+    }
+
+    async fn mget(&self, keys: &[StorageKey]) -> StorageResult<Vec<Option<StorageValue>>> {
+        let mut values: Vec<Option<StorageValue>> =
+            keys.iter().map(|key| self.get_cached(key)).collect();
+        let missing_keys: Vec<StorageKey> = keys
+            .iter()
+            .zip(&values)
+            .filter(|(_, value)| value.is_none())
+            .map(|(key, _)| key.clone())
+            .collect();
+        if missing_keys.is_empty() {
+            return Ok(values);
+        }
+        let fetched_values = self.storage.mget(&missing_keys).await?;
+        let mut fetched_values_iter = fetched_values.iter();
+        for value in values.iter_mut().filter(|value| value.is_none()) {
+            value.clone_from(
+                fetched_values_iter
+                    .next()
+                    .expect("A value is returned for each missing key."),
+            );
+        }
+        self.cache_values(
+            missing_keys
+                .into_iter()
+                .zip(fetched_values)
+                .filter_map(|(key, value)| Some((key, value?))),
+        );
+        Ok(values)
+    }
+
+    async fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) -> StorageResult<()> {
+        self.cache.get_mut().expect("Poisoned cache lock.").extend(
+            key_to_value
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        self.storage.mset(key_to_value).await
+    }
+
+    async fn mset_with_references(
+        &mut self,
+        key_to_value: HashMap<StorageKey, StorageValue>,
+        references: &HashMap<StorageKey, u64>,
+    ) -> StorageResult<()> {
+        self.cache.get_mut().expect("Poisoned cache lock.").extend(
+            key_to_value
+                .iter()
+                .map(|(key, value)| (key.clone(), value.clone())),
+        );
+        self.storage
+            .mset_with_references(key_to_value, references)
+            .await
+    }
+
+    async fn delete(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        self.cache
+            .get_mut()
+            .expect("Poisoned cache lock.")
+            .remove(key);
+        self.storage.delete(key).await
+    }
+}
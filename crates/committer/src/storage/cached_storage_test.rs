@@ -0,0 +1,70 @@
+use std::collections::HashMap;
+
+use crate::storage::cached_storage::CachedStorage;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageValue};
+
+fn entry(key: u8, value: u8) -> (StorageKey, StorageValue) {
+    (StorageKey(vec![key; 3]), StorageValue(vec![value; 5]))
+}
+
+#[tokio::test]
+async fn test_reads_are_cached() {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    let (missing_key, _) = entry(3, 3);
+    let mut storage = MapStorage {
+        storage: HashMap::from([
+            (key_1.clone(), value_1.clone()),
+            (key_2.clone(), value_2.clone()),
+        ]),
+    };
+    let cached_storage = CachedStorage::new(&mut storage);
+
+    assert_eq!(
+        cached_storage.get(&key_1).await.unwrap(),
+        Some(value_1.clone())
+    );
+    assert_eq!(cached_storage.cache_size(), 1);
+    assert_eq!(
+        cached_storage
+            .mget(&[key_1, missing_key.clone(), key_2])
+            .await
+            .unwrap(),
+        vec![Some(value_1), None, Some(value_2)]
+    );
+    // Missing keys are not cached.
+    assert_eq!(cached_storage.cache_size(), 2);
+    assert_eq!(cached_storage.get(&missing_key).await.unwrap(), None);
+}
+
+#[tokio::test]
+async fn test_writes_go_through() {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    let (key_3, value_3) = entry(3, 3);
+    let mut storage = MapStorage {
+        storage: HashMap::from([(key_1.clone(), value_1.clone())]),
+    };
+    let mut cached_storage = CachedStorage::new(&mut storage);
+    cached_storage.get(&key_1).await.unwrap();
+
+    cached_storage
+        .set(key_2.clone(), value_2.clone())
+        .await
+        .unwrap();
+    cached_storage
+        .mset(HashMap::from([(key_3.clone(), value_3.clone())]))
+        .await
+        .unwrap();
+    assert_eq!(cached_storage.delete(&key_1).await.unwrap(), Some(value_1));
+    assert_eq!(cached_storage.get(&key_1).await.unwrap(), None);
+    assert_eq!(cached_storage.cache_size(), 2);
+
+    cached_storage.clear_cache();
+    assert_eq!(cached_storage.cache_size(), 0);
+    assert_eq!(
+        storage.storage,
+        HashMap::from([(key_2, value_2), (key_3, value_3)])
+    );
+}
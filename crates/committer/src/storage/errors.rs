@@ -3,12 +3,21 @@ use crate::storage::storage_trait::StorageKey;
 
 use serde_json;
 use starknet_types_core::felt::FromStrError;
+use std::path::PathBuf;
 use thiserror::Error;
 
 #[derive(Debug, Error)]
 pub enum StorageError {
     #[error("The key {0:?} does not exist in storage.")]
     MissingKey(StorageKey),
+    #[error(transparent)]
+    Io(#[from] std::io::Error),
+    #[error("Corrupted record at offset {offset} of the storage file {path:?}.")]
+    CorruptedFile { path: PathBuf, offset: u64 },
+    #[error("Corrupted reference count of the key {0:?}.")]
+    CorruptedReferenceCount(StorageKey),
+    #[error("The key {0:?} is referenced, so it can only be released.")]
+    ReferencedKeyDeletion(StorageKey),
 }
 
 #[derive(thiserror::Error, Debug)]
@@ -38,4 +47,31 @@ pub enum DeserializationError {
     FeltParsingError(#[from] FromStrError),
     #[error("Encountered an invalid type when deserializing a leaf.")]
     LeafTypeError,
+    #[error("The value is {actual} bytes long, instead of {expected} bytes.")]
+    UnexpectedValueSize { expected: usize, actual: usize },
+    #[error(
+        "The {node_kind:?} node stored under the key {key:?} is {actual} bytes long, instead of \
+         one of {expected:?} bytes."
+    )]
+    UnexpectedNodeSize {
+        key: StorageKey,
+        node_kind: NodeKind,
+        expected: Vec<usize>,
+        actual: usize,
+    },
+    #[error("Failed to deserialize the {node_kind:?} node stored under the key {key:?}: {source}")]
+    MalformedNode {
+        key: StorageKey,
+        node_kind: NodeKind,
+        source: Box<DeserializationError>,
+    },
+}
+
+/// The kind of a node of a Patricia-Merkle tree, as read from storage.
+#[derive(Clone, Copy, Debug, PartialEq, Eq)]
+pub enum NodeKind {
+    /// An inner node, either binary or edge, which are told apart by their size.
+    Inner,
+    Edge,
+    Leaf,
 }
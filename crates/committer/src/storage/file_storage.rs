@@ -0,0 +1,288 @@
+use std::collections::HashMap;
+use std::fs::{File, OpenOptions};
+use std::io::{BufReader, ErrorKind, Read, Write};
+use std::path::{Path, PathBuf};
+
+use log::{error, warn};
+
+use crate::storage::errors::StorageError;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::storage_trait::{Storage, StorageKey, StorageValue};
+
+#[cfg(test)]
+#[path = "file_storage_test.rs"]
+pub mod file_storage_test;
+
+pub type FileStorageResult<T> = Result<T, StorageError>;
+
+/// Tags of the records in the storage file.
+const SET_RECORD_TAG: u8 = 0;
+const DELETE_RECORD_TAG: u8 = 1;
+
+/// A persistent storage, backed by an append-only log file.
+///
+/// The log consists of `set` and `delete` records, each holding a length-prefixed key (and value,
+/// for `set` records). Every length is followed by its bitwise complement, so that a corrupted
+/// length is detected rather than mistaken for the end of the file. Opening the storage replays the
+/// log into an in-memory map, which serves all reads. Writes are applied to the map and appended to
+/// a pending buffer, which is persisted on [FileStorage::flush] (and on a best effort basis when
+/// the storage is dropped).
+/// A truncated trailing record, e.g., due to a crash in the middle of a flush, is discarded on
+/// open. Any other malformed record fails the open with [StorageError::CorruptedFile].
+pub struct FileStorage {
+    path: PathBuf,
+    storage: HashMap<StorageKey, StorageValue>,
+    file: File,
+    // The length of the log file, excluding the pending records.
+    persisted_length: u64,
+    pending_records: Vec<u8>,
+}
+
+impl FileStorage {
+    /// Opens the existing storage at the given path.
+    pub fn open(path: impl AsRef<Path>) -> FileStorageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new().read(true).append(true).open(&path)?;
+        let (storage, persisted_length) = Self::replay(&path, &file, true)?;
+        Ok(Self {
+            path,
+            storage,
+            file,
+            persisted_length,
+            pending_records: Vec::new(),
+        })
+    }
+
+    /// Loads the entries of the existing storage at the given path into memory, without modifying
+    /// the file: it is opened read-only, and a truncated trailing record is ignored rather than
+    /// removed. Used to inspect a storage (e.g., a possibly damaged one) without repairing it.
+    pub fn load(path: impl AsRef<Path>) -> FileStorageResult<MapStorage> {
+        let path = path.as_ref();
+        let file = OpenOptions::new().read(true).open(path)?;
+        let (storage, _) = Self::replay(path, &file, false)?;
+        Ok(MapStorage { storage })
+    }
+
+    /// Creates an empty storage at the given path. Fails if the file already exists.
+    pub fn create(path: impl AsRef<Path>) -> FileStorageResult<Self> {
+        let path = path.as_ref().to_path_buf();
+        let file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .create_new(true)
+            .open(&path)?;
+        Ok(Self {
+            path,
+            storage: HashMap::new(),
+            file,
+            persisted_length: 0,
+            pending_records: Vec::new(),
+        })
+    }
+
+    /// Persists all pending writes to the storage file.
+    pub fn flush(&mut self) -> FileStorageResult<()> {
+        if self.pending_records.is_empty() {
+            return Ok(());
+        }
+        if let Err(error) = self
+            .file
+            .write_all(&self.pending_records)
+            .and_then(|_| self.file.sync_data())
+        {
+            // Drop any partially written record, so that the pending records can be retried.
+            self.file.set_len(self.persisted_length)?;
+            return Err(error.into());
+        }
+        self.persisted_length += u64_from_usize(self.pending_records.len());
+        self.pending_records.clear();
+        Ok(())
+    }
+
+    /// Rewrites the storage file such that it only holds the current entries, dropping overridden
+    /// and deleted values. Pending writes are persisted as well.
+    pub fn compact(&mut self) -> FileStorageResult<()> {
+        let compacted_path = self.path.with_extension("compacted");
+        let mut compacted_records = Vec::new();
+        for (key, value) in self.storage.iter() {
+            encode_set_record(&mut compacted_records, key, value);
+        }
+        let mut compacted_file = File::create(&compacted_path)?;
+        compacted_file.write_all(&compacted_records)?;
+        compacted_file.sync_all()?;
+        std::fs::rename(&compacted_path, &self.path)?;
+
+        self.file = OpenOptions::new()
+            .read(true)
+            .append(true)
+            .open(&self.path)?;
+        self.persisted_length = u64_from_usize(compacted_records.len());
+        self.pending_records.clear();
+        Ok(())
+    }
+
+    /// Returns the number of entries in the storage.
+    pub fn len(&self) -> usize {
+        self.storage.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.storage.is_empty()
+    }
+
+    /// Replays the records of the given log file. Returns the resulting entries and the length of
+    /// the valid prefix of the file; a truncated trailing record is discarded, and removed from the
+    /// file if `truncate` is set.
+    /// As the record lengths are validated, running out of bytes in the middle of a record means
+    /// that it is the last record of the file.
+    fn replay(
+        path: &Path,
+        file: &File,
+        truncate: bool,
+    ) -> FileStorageResult<(HashMap<StorageKey, StorageValue>, u64)> {
+        let mut storage = HashMap::new();
+        let mut reader = BufReader::new(file);
+        let mut valid_length = 0;
+        loop {
+            match read_record(&mut reader) {
+                Ok(Some((record, record_length))) => {
+                    match record {
+                        Record::Set(key, value) => storage.insert(key, value),
+                        Record::Delete(key) => storage.remove(&key),
+                    };
+                    valid_length += record_length;
+                }
+                Ok(None) => break,
+                Err(error) if error.kind() == ErrorKind::UnexpectedEof => {
+                    warn!(
+                        "Discarding a truncated record at offset {valid_length} of the storage \
+                         file {path:?}."
+                    );
+                    if truncate {
+                        file.set_len(valid_length)?;
+                    }
+                    break;
+                }
+                Err(error) if error.kind() == ErrorKind::InvalidData => {
+                    return Err(StorageError::CorruptedFile {
+                        path: path.to_path_buf(),
+                        offset: valid_length,
+                    });
+                }
+                Err(error) => return Err(error.into()),
+            }
+        }
+        Ok((storage, valid_length))
+    }
+
+    fn append_delete_record(&mut self, key: &StorageKey) {
+        self.pending_records.push(DELETE_RECORD_TAG);
+        encode_bytes(&mut self.pending_records, &key.0);
+    }
+}
+
+impl Storage for FileStorage {
+    fn get(&self, key: &StorageKey) -> Option<&StorageValue> {
+        self.storage.get(key)
+    }
+
+    fn set(&mut self, key: StorageKey, value: StorageValue) -> Option<StorageValue> {
+        encode_set_record(&mut self.pending_records, &key, &value);
+        self.storage.insert(key, value)
+    }
+
+    fn mget(&self, keys: &[StorageKey]) -> Vec<Option<&StorageValue>> {
+        keys.iter().map(|key| self.get(key)).collect::<Vec<_>>()
+    }
+
+    fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) {
+        for (key, value) in key_to_value {
+            self.set(key, value);
+        }
+    }
+
+    fn delete(&mut self, key: &StorageKey) -> Option<StorageValue> {
+        let value = self.storage.remove(key)?;
+        self.append_delete_record(key);
+        Some(value)
+    }
+}
+
+impl Drop for FileStorage {
+    fn drop(&mut self) {
+        if let Err(error) = self.flush() {
+            error!(
+                "Failed to flush the storage file {:?} on drop: {error}",
+                self.path
+            );
+        }
+    }
+}
+
+enum Record {
+    Set(StorageKey, StorageValue),
+    Delete(StorageKey),
+}
+
+fn u64_from_usize(value: usize) -> u64 {
+    u64::try_from(value).expect("usize is unexpectedly wider than u64.")
+}
+
+fn encode_bytes(buffer: &mut Vec<u8>, bytes: &[u8]) {
+    let length =
+        u32::try_from(bytes.len()).expect("Storage keys and values are shorter than 4 GiB.");
+    buffer.extend(length.to_be_bytes());
+    buffer.extend((!length).to_be_bytes());
+    buffer.extend(bytes);
+}
+
+fn encode_set_record(buffer: &mut Vec<u8>, key: &StorageKey, value: &StorageValue) {
+    buffer.push(SET_RECORD_TAG);
+    encode_bytes(buffer, &key.0);
+    encode_bytes(buffer, &value.0);
+}
+
+/// Reads length-prefixed bytes. Returns them along with the number of bytes consumed.
+/// A length that does not match its complement is reported as [ErrorKind::InvalidData]; running
+/// out of bytes is reported as [ErrorKind::UnexpectedEof].
+fn read_bytes(reader: &mut impl Read) -> std::io::Result<(Vec<u8>, u64)> {
+    let mut length_bytes = [0; 4];
+    let mut complement_bytes = [0; 4];
+    reader.read_exact(&mut length_bytes)?;
+    reader.read_exact(&mut complement_bytes)?;
+    let length = u32::from_be_bytes(length_bytes);
+    if !length != u32::from_be_bytes(complement_bytes) {
+        return Err(ErrorKind::InvalidData.into());
+    }
+    let length = u64::from(length);
+    let mut bytes = Vec::new();
+    reader.take(length).read_to_end(&mut bytes)?;
+    if u64_from_usize(bytes.len()) != length {
+        return Err(ErrorKind::UnexpectedEof.into());
+    }
+    Ok((bytes, 8 + length))
+}
+
+/// Reads the next record. Returns None at the end of the file, otherwise returns the record along
+/// with the number of bytes consumed.
+fn read_record(reader: &mut impl Read) -> std::io::Result<Option<(Record, u64)>> {
+    let mut tag = [0; 1];
+    if reader.read(&mut tag)? == 0 {
+        return Ok(None);
+    }
+    match tag[0] {
+        SET_RECORD_TAG => {
+            let (key, key_length) = read_bytes(reader)?;
+            let (value, value_length) = read_bytes(reader)?;
+            Ok(Some((
+                Record::Set(StorageKey(key), StorageValue(value)),
+                1 + key_length + value_length,
+            )))
+        }
+        DELETE_RECORD_TAG => {
+            let (key, key_length) = read_bytes(reader)?;
+            Ok(Some((Record::Delete(StorageKey(key)), 1 + key_length)))
+        }
+        _ => Err(ErrorKind::InvalidData.into()),
+    }
+}
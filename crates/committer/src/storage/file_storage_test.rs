@@ -0,0 +1,224 @@
+use std::collections::HashMap;
+use std::fs::OpenOptions;
+use std::path::PathBuf;
+
+use rstest::{fixture, rstest};
+use tempfile::TempDir;
+
+use crate::storage::errors::StorageError;
+use crate::storage::file_storage::FileStorage;
+use crate::storage::storage_trait::{Storage, StorageKey, StorageValue};
+
+struct StorageFile {
+    // Held to keep the directory alive for the duration of the test.
+    _dir: TempDir,
+    path: PathBuf,
+}
+
+#[fixture]
+fn storage_file() -> StorageFile {
+    let dir = TempDir::new().unwrap();
+    let path = dir.path().join("storage");
+    StorageFile { _dir: dir, path }
+}
+
+fn entry(key: u8, value: u8) -> (StorageKey, StorageValue) {
+    (StorageKey(vec![key; 3]), StorageValue(vec![value; 5]))
+}
+
+fn file_length(storage_file: &StorageFile) -> u64 {
+    std::fs::metadata(&storage_file.path).unwrap().len()
+}
+
+#[rstest]
+fn test_writes_persist_across_reopen(storage_file: StorageFile) {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    let (_, new_value_1) = entry(1, 3);
+    {
+        let mut storage = FileStorage::create(&storage_file.path).unwrap();
+        assert!(storage.is_empty());
+        storage.mset(HashMap::from([
+            (key_1.clone(), value_1.clone()),
+            (key_2.clone(), value_2),
+        ]));
+        assert_eq!(
+            storage.set(key_1.clone(), new_value_1.clone()),
+            Some(value_1)
+        );
+        assert!(storage.delete(&key_2).is_some());
+        storage.flush().unwrap();
+    }
+
+    let storage = FileStorage::open(&storage_file.path).unwrap();
+    assert_eq!(storage.len(), 1);
+    assert_eq!(
+        storage.mget(&[key_1, key_2]),
+        vec![Some(&new_value_1), None]
+    );
+}
+
+#[rstest]
+fn test_pending_writes_flushed_on_drop(storage_file: StorageFile) {
+    let (key, value) = entry(1, 1);
+    {
+        let mut storage = FileStorage::create(&storage_file.path).unwrap();
+        storage.set(key.clone(), value.clone());
+        assert_eq!(file_length(&storage_file), 0);
+    }
+
+    let storage = FileStorage::open(&storage_file.path).unwrap();
+    assert_eq!(storage.get(&key), Some(&value));
+}
+
+#[rstest]
+fn test_truncated_record_is_discarded(storage_file: StorageFile) {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    {
+        let mut storage = FileStorage::create(&storage_file.path).unwrap();
+        storage.set(key_1.clone(), value_1.clone());
+        storage.flush().unwrap();
+    }
+    let valid_length = file_length(&storage_file);
+    {
+        let mut storage = FileStorage::open(&storage_file.path).unwrap();
+        storage.set(key_2.clone(), value_2);
+        storage.flush().unwrap();
+    }
+    // Simulate a crash in the middle of the second flush.
+    OpenOptions::new()
+        .write(true)
+        .open(&storage_file.path)
+        .unwrap()
+        .set_len(file_length(&storage_file) - 1)
+        .unwrap();
+
+    let storage = FileStorage::open(&storage_file.path).unwrap();
+    assert_eq!(storage.len(), 1);
+    assert_eq!(storage.get(&key_1), Some(&value_1));
+    assert_eq!(storage.get(&key_2), None);
+    assert_eq!(file_length(&storage_file), valid_length);
+}
+
+#[rstest]
+fn test_corrupted_record(storage_file: StorageFile) {
+    std::fs::write(&storage_file.path, [7, 0, 0]).unwrap();
+    assert!(matches!(
+        FileStorage::open(&storage_file.path),
+        Err(StorageError::CorruptedFile { offset: 0, .. })
+    ));
+}
+
+#[rstest]
+fn test_corrupted_length_before_the_last_record(storage_file: StorageFile) {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    {
+        let mut storage = FileStorage::create(&storage_file.path).unwrap();
+        storage.set(key_1, value_1);
+        storage.set(key_2, value_2);
+    }
+    let length = file_length(&storage_file);
+    // Corrupt the key length of the first record, such that it seemingly runs past the end of the
+    // file.
+    let mut content = std::fs::read(&storage_file.path).unwrap();
+    content[1] = 0xff;
+    std::fs::write(&storage_file.path, content).unwrap();
+
+    assert!(matches!(
+        FileStorage::open(&storage_file.path),
+        Err(StorageError::CorruptedFile { offset: 0, .. })
+    ));
+    // The records are kept for inspection.
+    assert_eq!(file_length(&storage_file), length);
+}
+
+#[rstest]
+fn test_open_missing_file(storage_file: StorageFile) {
+    assert!(matches!(
+        FileStorage::open(&storage_file.path),
+        Err(StorageError::Io(error)) if error.kind() == std::io::ErrorKind::NotFound
+    ));
+    assert!(!storage_file.path.exists());
+}
+
+#[rstest]
+fn test_load_does_not_modify_file(storage_file: StorageFile) {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    {
+        let mut storage = FileStorage::create(&storage_file.path).unwrap();
+        storage.set(key_1.clone(), value_1.clone());
+        storage.flush().unwrap();
+        storage.set(key_2.clone(), value_2);
+    }
+    // Simulate a crash in the middle of the second flush.
+    let truncated_length = file_length(&storage_file) - 1;
+    OpenOptions::new()
+        .write(true)
+        .open(&storage_file.path)
+        .unwrap()
+        .set_len(truncated_length)
+        .unwrap();
+
+    let storage = FileStorage::load(&storage_file.path).unwrap();
+    assert_eq!(storage.storage, HashMap::from([(key_1, value_1)]));
+    // The truncated record is kept for inspection.
+    assert_eq!(file_length(&storage_file), truncated_length);
+}
+
+#[rstest]
+fn test_load_missing_file(storage_file: StorageFile) {
+    assert!(matches!(
+        FileStorage::load(&storage_file.path),
+        Err(StorageError::Io(error)) if error.kind() == std::io::ErrorKind::NotFound
+    ));
+    assert!(!storage_file.path.exists());
+}
+
+#[rstest]
+fn test_create_existing_file(storage_file: StorageFile) {
+    let (key, value) = entry(1, 1);
+    FileStorage::create(&storage_file.path)
+        .unwrap()
+        .set(key.clone(), value.clone());
+
+    assert!(matches!(
+        FileStorage::create(&storage_file.path),
+        Err(StorageError::Io(error)) if error.kind() == std::io::ErrorKind::AlreadyExists
+    ));
+    assert_eq!(
+        FileStorage::open(&storage_file.path).unwrap().get(&key),
+        Some(&value)
+    );
+}
+
+#[rstest]
+fn test_compact(storage_file: StorageFile) {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    let (_, new_value_1) = entry(1, 3);
+    let mut storage = FileStorage::create(&storage_file.path).unwrap();
+    storage.set(key_1.clone(), value_1);
+    storage.set(key_2.clone(), value_2);
+    storage.set(key_1.clone(), new_value_1.clone());
+    storage.delete(&key_2);
+    storage.flush().unwrap();
+    let uncompacted_length = file_length(&storage_file);
+
+    storage.compact().unwrap();
+    assert!(file_length(&storage_file) < uncompacted_length);
+
+    // Writes after compaction are appended to the compacted file.
+    let (key_3, value_3) = entry(3, 3);
+    storage.set(key_3.clone(), value_3.clone());
+    drop(storage);
+
+    let storage = FileStorage::open(&storage_file.path).unwrap();
+    assert_eq!(storage.len(), 2);
+    assert_eq!(
+        storage.mget(&[key_1, key_2, key_3]),
+        vec![Some(&new_value_1), None, Some(&value_3)]
+    );
+}
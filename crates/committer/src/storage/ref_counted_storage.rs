@@ -0,0 +1,152 @@
+use std::collections::HashMap;
+
+use crate::storage::errors::StorageError;
+use crate::storage::storage_trait::{
+    create_db_key, AsyncStorage, Storage, StorageKey, StorageResult, StorageValue,
+};
+
+#[cfg(test)]
+#[path = "ref_counted_storage_test.rs"]
+pub mod ref_counted_storage_test;
+
+const REFERENCE_COUNT_PREFIX: &[u8] = b"refcount";
+
+/// A [Storage] wrapper which counts the references to each node, and deletes a node only when its
+/// last reference is released.
+///
+/// Nodes are keyed by their hashes, so identical subtrees (e.g., identical storage tries of two
+/// contracts) are stored once. Counting a reference per position holding a node makes deleting
+/// the nodes orphaned in one position safe, while the same nodes are held in other positions.
+/// The count of each key is stored next to it, under the key prefixed by `refcount`.
+///
+/// Only the references given to [AsyncStorage::mset_with_references] are counted. Other values
+/// (e.g., the root registry and the flat state index, which are overwritten in place) are written
+/// as is, and are deleted by [AsyncStorage::delete]. Nodes may also be untracked, when written
+/// without references (e.g., a database populated before the wrapper was used); the number of
+/// positions holding them is unknown, so releasing a reference to them keeps them.
+pub struct RefCountedStorage<S: Storage> {
+    storage: S,
+}
+
+impl<S: Storage> RefCountedStorage<S> {
+    pub fn new(storage: S) -> Self {
+        Self { storage }
+    }
+
+    pub fn into_inner(self) -> S {
+        self.storage
+    }
+
+    /// Returns the number of references to the given key. Keys which were not written through
+    /// this storage have no references.
+    pub fn reference_count(&self, key: &StorageKey) -> StorageResult<u64> {
+        match self.storage.get(&Self::reference_count_key(key)) {
+            None => Ok(0),
+            Some(StorageValue(count)) => {
+                Ok(u64::from_be_bytes(count.as_slice().try_into().map_err(
+                    |_| StorageError::CorruptedReferenceCount(key.clone()),
+                )?))
+            }
+        }
+    }
+
+    fn reference_count_key(key: &StorageKey) -> StorageKey {
+        create_db_key(REFERENCE_COUNT_PREFIX.to_vec(), &key.0)
+    }
+
+    /// Releases a reference to the given node. The node is deleted (and returned) only when its
+    /// last reference is released. Untracked nodes (without references) are kept, as they may be
+    /// held by any number of positions; returns None for them as well.
+    pub fn release_reference(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        let reference_count_key = Self::reference_count_key(key);
+        match self.reference_count(key)? {
+            0 => Ok(None),
+            1 => {
+                self.storage.delete(&reference_count_key);
+                Ok(self.storage.delete(key))
+            }
+            count => {
+                self.storage.set(
+                    reference_count_key,
+                    StorageValue((count - 1).to_be_bytes().to_vec()),
+                );
+                Ok(None)
+            }
+        }
+    }
+
+    /// Sets the given values, and adds the given number of references to each of the referenced
+    /// ones. Values which are already stored without references stay untracked, as the positions
+    /// holding them are unknown.
+    fn add_references(
+        &mut self,
+        key_to_value: HashMap<StorageKey, StorageValue>,
+        references: &HashMap<StorageKey, u64>,
+    ) -> StorageResult<()> {
+        let mut reference_counts = HashMap::with_capacity(references.len());
+        for key in key_to_value.keys() {
+            let Some(n_references) = references.get(key) else {
+                continue;
+            };
+            let previous_count = self.reference_count(key)?;
+            if previous_count == 0 && self.storage.get(key).is_some() {
+                continue;
+            }
+            let count = previous_count + n_references;
+            reference_counts.insert(
+                Self::reference_count_key(key),
+                StorageValue(count.to_be_bytes().to_vec()),
+            );
+        }
+        self.storage.mset(key_to_value);
+        self.storage.mset(reference_counts);
+        Ok(())
+    }
+}
+
+impl<S: Storage + Send + Sync> AsyncStorage for RefCountedStorage<S> {
+    async fn get(&self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        Ok(self.storage.get(key).cloned())
+    }
+
+    /// Sets the value, without adding a reference to it.
+    async fn set(
+        &mut self,
+        key: StorageKey,
+        value: StorageValue,
+    ) -> StorageResult<Option<StorageValue>> {
+        Ok(self.storage.set(key, value))
+    }
+
+    async fn mget(&self, keys: &[StorageKey]) -> StorageResult<Vec<Option<StorageValue>>> {
+        Ok(self
+            .storage
+            .mget(keys)
+            .into_iter()
+            .map(|value| value.cloned())
+            .collect())
+    }
+
+    /// Sets the values, without adding references to them.
+    async fn mset(&mut self, key_to_value: HashMap<StorageKey, StorageValue>) -> StorageResult<()> {
+        self.storage.mset(key_to_value);
+        Ok(())
+    }
+
+    async fn mset_with_references(
+        &mut self,
+        key_to_value: HashMap<StorageKey, StorageValue>,
+        references: &HashMap<StorageKey, u64>,
+    ) -> StorageResult<()> {
+        self.add_references(key_to_value, references)
+    }
+
+    /// Deletes a value without references. Referenced values are held by positions in the tries,
+    /// and can only be released (see [Self::release_reference]).
+    async fn delete(&mut self, key: &StorageKey) -> StorageResult<Option<StorageValue>> {
+        if self.reference_count(key)? > 0 {
+            return Err(StorageError::ReferencedKeyDeletion(key.clone()));
+        }
+        Ok(self.storage.delete(key))
+    }
+}
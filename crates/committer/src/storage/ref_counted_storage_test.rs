@@ -0,0 +1,161 @@
+use std::collections::HashMap;
+
+use crate::block_committer::commit::commit_state_diff;
+use crate::block_committer::input::ConfigImpl;
+use crate::hash::hash_trait::HashOutput;
+use crate::patricia_merkle_tree::internal_test_utils::initial_state_diff;
+use crate::storage::errors::StorageError;
+use crate::storage::map_storage::MapStorage;
+use crate::storage::ref_counted_storage::RefCountedStorage;
+use crate::storage::storage_trait::{AsyncStorage, StorageKey, StorageValue};
+
+fn entry(key: u8, value: u8) -> (StorageKey, StorageValue) {
+    (StorageKey(vec![key; 3]), StorageValue(vec![value; 5]))
+}
+
+#[tokio::test]
+async fn test_references_are_counted() {
+    let (key_1, value_1) = entry(1, 1);
+    let (key_2, value_2) = entry(2, 2);
+    let (untracked_key, _) = entry(3, 3);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+
+    assert_eq!(
+        storage.set(key_1.clone(), value_1.clone()).await.unwrap(),
+        None
+    );
+    storage
+        .mset(HashMap::from([(key_1.clone(), value_1.clone())]))
+        .await
+        .unwrap();
+    storage
+        .mset_with_references(
+            HashMap::from([
+                (key_1.clone(), value_1.clone()),
+                (key_2.clone(), value_2.clone()),
+            ]),
+            &HashMap::from([(key_2.clone(), 3)]),
+        )
+        .await
+        .unwrap();
+
+    // Only the given references are counted.
+    assert_eq!(storage.reference_count(&key_1).unwrap(), 0);
+    assert_eq!(storage.reference_count(&key_2).unwrap(), 3);
+    assert_eq!(storage.reference_count(&untracked_key).unwrap(), 0);
+    assert_eq!(
+        storage.mget(&[key_1, key_2, untracked_key]).await.unwrap(),
+        vec![Some(value_1), Some(value_2), None]
+    );
+}
+
+#[tokio::test]
+async fn test_release_reference() {
+    let (key, value) = entry(1, 1);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+    storage
+        .mset_with_references(
+            HashMap::from([(key.clone(), value.clone())]),
+            &HashMap::from([(key.clone(), 2)]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(storage.release_reference(&key).unwrap(), None);
+    assert_eq!(storage.reference_count(&key).unwrap(), 1);
+    assert_eq!(storage.get(&key).await.unwrap(), Some(value.clone()));
+
+    // Releasing the last reference deletes the value and its reference count.
+    assert_eq!(storage.release_reference(&key).unwrap(), Some(value));
+    assert_eq!(storage.reference_count(&key).unwrap(), 0);
+    assert!(storage.into_inner().storage.is_empty());
+}
+
+/// Values written without references may be held by any number of positions, so releasing them
+/// keeps them.
+#[tokio::test]
+async fn test_release_untracked_key() {
+    let (key, value) = entry(1, 1);
+    let mut storage = RefCountedStorage::new(MapStorage {
+        storage: HashMap::from([(key.clone(), value.clone())]),
+    });
+
+    assert_eq!(storage.release_reference(&key).unwrap(), None);
+    assert_eq!(storage.get(&key).await.unwrap(), Some(value));
+}
+
+/// Values stored without references stay untracked when written again with references.
+#[tokio::test]
+async fn test_untracked_key_is_not_counted() {
+    let (key, value) = entry(1, 1);
+    let mut storage = RefCountedStorage::new(MapStorage {
+        storage: HashMap::from([(key.clone(), value.clone())]),
+    });
+    storage
+        .mset_with_references(
+            HashMap::from([(key.clone(), value.clone())]),
+            &HashMap::from([(key.clone(), 1)]),
+        )
+        .await
+        .unwrap();
+
+    assert_eq!(storage.reference_count(&key).unwrap(), 0);
+    assert_eq!(storage.release_reference(&key).unwrap(), None);
+    assert_eq!(storage.get(&key).await.unwrap(), Some(value));
+}
+
+#[tokio::test]
+async fn test_delete_referenced_key() {
+    let (key, value) = entry(1, 1);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+    storage
+        .mset_with_references(
+            HashMap::from([(key.clone(), value.clone())]),
+            &HashMap::from([(key.clone(), 1)]),
+        )
+        .await
+        .unwrap();
+
+    assert!(matches!(
+        storage.delete(&key).await,
+        Err(StorageError::ReferencedKeyDeletion(deleted_key)) if deleted_key == key
+    ));
+    assert_eq!(storage.get(&key).await.unwrap(), Some(value));
+}
+
+#[tokio::test]
+async fn test_delete_untracked_key() {
+    let (key, value) = entry(1, 1);
+    let (missing_key, _) = entry(2, 2);
+    let mut storage = RefCountedStorage::new(MapStorage {
+        storage: HashMap::from([(key.clone(), value.clone())]),
+    });
+
+    assert_eq!(storage.delete(&key).await.unwrap(), Some(value));
+    assert_eq!(storage.delete(&missing_key).await.unwrap(), None);
+    assert!(storage.into_inner().storage.is_empty());
+}
+
+#[tokio::test]
+async fn test_only_node_references_are_counted() {
+    let config = ConfigImpl::new(false, log::LevelFilter::Debug).with_flat_state_index(true);
+    let mut storage = RefCountedStorage::new(MapStorage::default());
+    let filled_forest = commit_state_diff(
+        &storage,
+        &initial_state_diff(),
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        HashOutput::ROOT_OF_EMPTY_TREE,
+        &config,
+    )
+    .await
+    .unwrap();
+    assert!(!filled_forest.flat_state_entries.is_empty());
+    filled_forest.write_to_storage(&mut storage).await.unwrap();
+
+    for (key, n_references) in filled_forest.node_references() {
+        assert_eq!(storage.reference_count(&key).unwrap(), n_references);
+    }
+    for key in filled_forest.flat_state_entries.keys() {
+        assert_eq!(storage.reference_count(key).unwrap(), 0);
+    }
+}
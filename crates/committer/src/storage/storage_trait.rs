@@ -1,17 +1,19 @@
 use serde::{Serialize, Serializer};
 
 use crate::felt::Felt;
+use crate::storage::errors::StorageError;
 use std::collections::HashMap;
+use std::future::{ready, Future};
 
-#[derive(Debug, Eq, Hash, PartialEq)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+pub type StorageResult<T> = Result<T, StorageError>;
+
+#[derive(Clone, Debug, Eq, Hash, PartialEq)]
 pub struct StorageKey(pub Vec<u8>);
 
-#[derive(Debug, Eq, PartialEq, Serialize)]
-#[cfg_attr(any(test, feature = "testing"), derive(Clone))]
+#[derive(Clone, Debug, Eq, PartialEq, Serialize)]
 pub struct StorageValue(pub Vec<u8>);
 
-pub trait Storage: From<HashMap<StorageKey, StorageValue>> {
+pub trait Storage {
     /// Returns value from storage, if it exists.
     fn get(&self, key: &StorageKey) -> Option<&StorageValue>;
 
@@ -30,6 +32,97 @@ pub trait Storage: From<HashMap<StorageKey, StorageValue>> {
     fn delete(&mut self, key: &StorageKey) -> Option<StorageValue>;
 }
 
+/// An asynchronous storage which returns owned values, and can thus be backed by a remote
+/// database. Every (in-process) [Storage] is also an [AsyncStorage].
+pub trait AsyncStorage: Send + Sync {
+    /// Returns value from storage, if it exists.
+    fn get(
+        &self,
+        key: &StorageKey,
+    ) -> impl Future<Output = StorageResult<Option<StorageValue>>> + Send;
+
+    /// Sets value in storage. If key already exists, its value is overwritten and the old value is
+    /// returned.
+    fn set(
+        &mut self,
+        key: StorageKey,
+        value: StorageValue,
+    ) -> impl Future<Output = StorageResult<Option<StorageValue>>> + Send;
+
+    /// Returns values from storage in same order of given keys. Value is None for keys that do not
+    /// exist.
+    fn mget(
+        &self,
+        keys: &[StorageKey],
+    ) -> impl Future<Output = StorageResult<Vec<Option<StorageValue>>>> + Send;
+
+    /// Sets values in storage.
+    fn mset(
+        &mut self,
+        key_to_value: HashMap<StorageKey, StorageValue>,
+    ) -> impl Future<Output = StorageResult<()>> + Send;
+
+    /// Deletes value from storage and returns its value if it exists. Returns None if not.
+    fn delete(
+        &mut self,
+        key: &StorageKey,
+    ) -> impl Future<Output = StorageResult<Option<StorageValue>>> + Send;
+
+    /// Sets values in storage, given the number of references to each of them (e.g., the number of
+    /// positions in the tries holding a node). Keys missing from the references are not counted.
+    /// Storages which count references add them (see
+    /// [crate::storage::ref_counted_storage::RefCountedStorage]); others ignore them.
+    fn mset_with_references(
+        &mut self,
+        key_to_value: HashMap<StorageKey, StorageValue>,
+        _references: &HashMap<StorageKey, u64>,
+    ) -> impl Future<Output = StorageResult<()>> + Send {
+        self.mset(key_to_value)
+    }
+}
+
+impl<S: Storage + Send + Sync> AsyncStorage for S {
+    fn get(
+        &self,
+        key: &StorageKey,
+    ) -> impl Future<Output = StorageResult<Option<StorageValue>>> + Send {
+        ready(Ok(Storage::get(self, key).cloned()))
+    }
+
+    fn set(
+        &mut self,
+        key: StorageKey,
+        value: StorageValue,
+    ) -> impl Future<Output = StorageResult<Option<StorageValue>>> + Send {
+        ready(Ok(Storage::set(self, key, value)))
+    }
+
+    fn mget(
+        &self,
+        keys: &[StorageKey],
+    ) -> impl Future<Output = StorageResult<Vec<Option<StorageValue>>>> + Send {
+        ready(Ok(Storage::mget(self, keys)
+            .into_iter()
+            .map(|value| value.cloned())
+            .collect()))
+    }
+
+    fn mset(
+        &mut self,
+        key_to_value: HashMap<StorageKey, StorageValue>,
+    ) -> impl Future<Output = StorageResult<()>> + Send {
+        Storage::mset(self, key_to_value);
+        ready(Ok(()))
+    }
+
+    fn delete(
+        &mut self,
+        key: &StorageKey,
+    ) -> impl Future<Output = StorageResult<Option<StorageValue>>> + Send {
+        ready(Ok(Storage::delete(self, key)))
+    }
+}
+
 // TODO(Aviv, 17/07/2024); Split between Storage prefix representation (trait) and node
 // specific implementation (enum).
 #[derive(Clone, Debug)]
@@ -11,10 +11,16 @@ use std::{collections::HashMap, sync::Arc};
 
 use committer::{
     block_committer::input::StarknetStorageValue,
+    hash::hash_trait::HashOutput,
     patricia_merkle_tree::{
-        external_test_utils::tree_computation_flow, node_data::leaf::LeafModifications,
+        external_test_utils::{
+            create_tree_hashing_input, layered_tree_hashing, recursive_tree_hashing,
+            tree_computation_flow,
+        },
+        node_data::leaf::LeafModifications,
         types::NodeIndex,
     },
+    storage::map_storage::MapStorage,
 };
 use committer_cli::{commands::parse_and_commit, tests::utils::parse_from_python::TreeFlowInput};
 use criterion::{criterion_group, criterion_main, Criterion};
@@ -24,25 +30,36 @@ const SINGLE_TREE_FLOW_INPUT: &str = include_str!("tree_flow_inputs.json");
 const FLOW_TEST_INPUT: &str = include_str!("committer_flow_inputs.json");
 const OUTPUT_PATH: &str = "benchmark_output.txt";
 
-pub fn single_tree_flow_benchmark(criterion: &mut Criterion) {
+fn get_runtime() -> tokio::runtime::Runtime {
+    match CONCURRENCY_MODE {
+        true => tokio::runtime::Builder::new_multi_thread().build().unwrap(),
+        false => tokio::runtime::Builder::new_current_thread()
+            .build()
+            .unwrap(),
+    }
+}
+
+fn get_single_tree_flow_input() -> (
+    Arc<LeafModifications<StarknetStorageValue>>,
+    MapStorage,
+    HashOutput,
+) {
     let TreeFlowInput {
         leaf_modifications,
         storage,
         root_hash,
     } = serde_json::from_str(SINGLE_TREE_FLOW_INPUT).unwrap();
 
-    let runtime = match CONCURRENCY_MODE {
-        true => tokio::runtime::Builder::new_multi_thread().build().unwrap(),
-        false => tokio::runtime::Builder::new_current_thread()
-            .build()
-            .unwrap(),
-    };
-
     let leaf_modifications = leaf_modifications
         .into_iter()
         .map(|(k, v)| (NodeIndex::FIRST_LEAF + k, v))
         .collect::<LeafModifications<StarknetStorageValue>>();
-    let arc_leaf_modifications = Arc::new(leaf_modifications);
+    (Arc::new(leaf_modifications), storage, root_hash)
+}
+
+pub fn single_tree_flow_benchmark(criterion: &mut Criterion) {
+    let (arc_leaf_modifications, storage, root_hash) = get_single_tree_flow_input();
+    let runtime = get_runtime();
 
     criterion.bench_function("tree_computation_flow", |benchmark| {
         benchmark.iter(|| {
@@ -55,13 +72,33 @@ pub fn single_tree_flow_benchmark(criterion: &mut Criterion) {
     });
 }
 
+/// Compares the task-per-node hashing of the single tree flow with the bottom-up layered hashing.
+/// The skeleton is built once, outside the measured loop, so only the hashing is compared.
+pub fn single_tree_hashing_comparison_benchmark(criterion: &mut Criterion) {
+    let (arc_leaf_modifications, storage, root_hash) = get_single_tree_flow_input();
+    let runtime = get_runtime();
+    let input = runtime.block_on(create_tree_hashing_input(
+        arc_leaf_modifications,
+        &storage,
+        root_hash,
+    ));
+
+    let mut group = criterion.benchmark_group("tree_hashing");
+    group.bench_function("recursive", |benchmark| {
+        benchmark.iter(|| {
+            runtime.block_on(recursive_tree_hashing(&input));
+        })
+    });
+    group.bench_function("layered", |benchmark| {
+        benchmark.iter(|| {
+            runtime.block_on(layered_tree_hashing(&input));
+        })
+    });
+    group.finish();
+}
+
 pub fn full_committer_flow_benchmark(criterion: &mut Criterion) {
-    let runtime = match CONCURRENCY_MODE {
-        true => tokio::runtime::Builder::new_multi_thread().build().unwrap(),
-        false => tokio::runtime::Builder::new_current_thread()
-            .build()
-            .unwrap(),
-    };
+    let runtime = get_runtime();
 
     // TODO(Aner, 8/7/2024): use structs for deserialization.
     let input: HashMap<String, String> = serde_json::from_str(FLOW_TEST_INPUT).unwrap();
@@ -71,10 +108,14 @@ pub fn full_committer_flow_benchmark(criterion: &mut Criterion) {
     // to avoid disk IO in the benchmark.
     criterion.bench_function("full_committer_flow", |benchmark| {
         benchmark.iter(|| {
-            runtime.block_on(parse_and_commit(
-                committer_input_string,
-                OUTPUT_PATH.to_owned(),
-            ));
+            runtime
+                .block_on(parse_and_commit(
+                    committer_input_string,
+                    OUTPUT_PATH.to_owned(),
+                    None,
+                    false,
+                ))
+                .unwrap();
         })
     });
 }
@@ -82,6 +123,7 @@ pub fn full_committer_flow_benchmark(criterion: &mut Criterion) {
 criterion_group!(
     benches,
     single_tree_flow_benchmark,
+    single_tree_hashing_comparison_benchmark,
     full_committer_flow_benchmark
 );
 criterion_main!(benches);
@@ -1,26 +1,109 @@
 use committer::block_committer::{
-    commit::commit_block,
+    commit::{commit_block, commit_validated_state_diff},
+    errors::BlockCommitmentError,
     input::{Config, ConfigImpl, Input},
 };
+use committer::felt::Felt;
+use committer::hash::hash_trait::HashOutput;
+use committer::patricia_merkle_tree::consistency_check::check_storage_consistency;
+use committer::patricia_merkle_tree::filled_tree::forest::FilledForest;
+use committer::storage::errors::DeserializationError;
+use committer::storage::file_storage::FileStorage;
+use committer::storage::storage_trait::{Storage, StorageResult};
 
 use crate::{
     filled_tree_output::filled_forest::SerializedForest,
     parse_input::read::{parse_input, write_to_file},
 };
 
-pub async fn parse_and_commit(input_string: &str, output_path: String) {
-    let input = parse_input(input_string).expect("Failed to parse the given input.");
+#[derive(thiserror::Error, Debug)]
+pub enum CommitCommandError {
+    #[error("Failed to parse the given input: {0}")]
+    Parse(#[from] DeserializationError),
+    #[error(transparent)]
+    BlockCommitment(#[from] BlockCommitmentError),
+}
+
+pub async fn parse_and_commit(
+    input_string: &str,
+    output_path: String,
+    storage_path: Option<String>,
+    create_storage: bool,
+) -> Result<(), CommitCommandError> {
+    let input = parse_input(input_string)?;
     // Set the given log level.
     log::set_max_level(input.config.logger_level());
-    commit(input, output_path).await;
+    Ok(commit(input, output_path, storage_path, create_storage).await?)
 }
 
-pub async fn commit(input: Input<ConfigImpl>, output_path: String) {
-    let serialized_filled_forest = SerializedForest(
-        commit_block(input)
-            .await
-            .expect("Failed to commit the given block."),
-    );
-    let output = serialized_filled_forest.forest_to_output();
+/// Commits the given block and writes the output. If a storage path is given, the input storage is
+/// merged into the persistent storage at that path, the block is committed against it, and the
+/// new facts are persisted there as well. The storage file must exist, unless `create_storage` is
+/// set, in which case it must not.
+pub async fn commit(
+    input: Input<ConfigImpl>,
+    output_path: String,
+    storage_path: Option<String>,
+    create_storage: bool,
+) -> Result<(), BlockCommitmentError> {
+    let filled_forest = match storage_path {
+        Some(storage_path) => commit_to_file_storage(input, &storage_path, create_storage).await?,
+        None => commit_block(input).await?,
+    };
+    let output = SerializedForest(filled_forest).forest_to_output().await;
     write_to_file(&output_path, &output);
+    Ok(())
+}
+
+async fn commit_to_file_storage(
+    input: Input<ConfigImpl>,
+    storage_path: &str,
+    create_storage: bool,
+) -> Result<FilledForest, BlockCommitmentError> {
+    // Validated before the storage file is created or modified.
+    input.state_diff.validate()?;
+    let mut storage = if create_storage {
+        FileStorage::create(storage_path)?
+    } else {
+        FileStorage::open(storage_path)?
+    };
+    storage.mset(input.storage);
+    let filled_forest = commit_validated_state_diff(
+        &storage,
+        &input.state_diff,
+        input.contracts_trie_root_hash,
+        input.classes_trie_root_hash,
+        &input.config,
+    )
+    .await?;
+    filled_forest.write_to_storage(&mut storage).await?;
+    storage.flush()?;
+    Ok(filled_forest)
+}
+
+/// Checks the consistency of the tries with the given roots in the persistent storage at the given
+/// path, and writes the inconsistencies found. The storage file is only read, and is never created
+/// or repaired. Returns whether the tries are consistent.
+pub async fn check_consistency(
+    storage_path: &str,
+    contracts_trie_root_hash: &str,
+    classes_trie_root_hash: &str,
+    output_path: String,
+) -> StorageResult<bool> {
+    let storage = FileStorage::load(storage_path)?;
+    let parse_root = |root: &str| {
+        HashOutput(Felt::from_hex(root).expect("Failed to parse the given root hash."))
+    };
+    let inconsistencies = check_storage_consistency(
+        &storage,
+        parse_root(contracts_trie_root_hash),
+        parse_root(classes_trie_root_hash),
+    )
+    .await?;
+    let report: Vec<String> = inconsistencies
+        .iter()
+        .map(|inconsistency| inconsistency.to_string())
+        .collect();
+    write_to_file(&output_path, &report);
+    Ok(report.is_empty())
 }
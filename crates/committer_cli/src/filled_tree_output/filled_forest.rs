@@ -13,18 +13,25 @@ pub struct Output {
     contract_storage_root_hash: String,
     // New compiled class root.
     compiled_class_root_hash: String,
+    // New global state root, combining the two roots above.
+    global_root_hash: String,
 }
 
 impl SerializedForest {
-    pub fn forest_to_output(&self) -> Output {
+    pub async fn forest_to_output(&self) -> Output {
         let mut storage = MapStorage::default();
-        self.0.write_to_storage(&mut storage);
+        self.0
+            .write_to_storage(&mut storage)
+            .await
+            .expect("Failed to write the forest to a map storage.");
         let contract_storage_root_hash = self.0.get_contract_root_hash().0;
         let compiled_class_root_hash = self.0.get_compiled_class_root_hash().0;
+        let global_root_hash = self.0.get_global_root_hash().0;
         Output {
             storage,
             contract_storage_root_hash: contract_storage_root_hash.to_hex(),
             compiled_class_root_hash: compiled_class_root_hash.to_hex(),
+            global_root_hash: global_root_hash.to_hex(),
         }
     }
 }
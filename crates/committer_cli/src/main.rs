@@ -1,8 +1,9 @@
 use clap::{Args, Parser, Subcommand};
 use committer_cli::block_hash::{BlockCommitmentsInput, BlockHashInput};
-use committer_cli::commands::parse_and_commit;
+use committer_cli::commands::{check_consistency, parse_and_commit};
 use committer_cli::parse_input::read::{load_from_stdin, read_from_stdin, write_to_file};
 use committer_cli::tests::python_tests::PythonTest;
+use log::error;
 use simplelog::{ColorChoice, Config, LevelFilter, TermLogger, TerminalMode};
 use starknet_api::block_hash::block_hash_calculator::{
     calculate_block_commitments, calculate_block_hash,
@@ -39,6 +40,33 @@ enum Command {
         /// File path to output.
         #[clap(long, short = 'o', default_value = "stdout")]
         output_path: String,
+
+        /// Path of a persistent storage file to commit against. The new facts are written to it.
+        #[clap(long)]
+        storage_path: Option<String>,
+
+        /// Create a new storage file at the storage path, instead of opening an existing one.
+        #[clap(long, requires = "storage_path")]
+        create_storage: bool,
+    },
+    /// Checks that the tries with the given roots in a persistent storage file are complete and
+    /// that every node hashes to its key.
+    CheckConsistency {
+        /// File path to output.
+        #[clap(long, short = 'o', default_value = "stdout")]
+        output_path: String,
+
+        /// Path of the persistent storage file to check.
+        #[clap(long)]
+        storage_path: String,
+
+        /// Root hash of the contracts trie, as a hex string.
+        #[clap(long)]
+        contracts_trie_root_hash: String,
+
+        /// Root hash of the classes trie, as a hex string.
+        #[clap(long)]
+        classes_trie_root_hash: String,
     },
     PythonTest {
         /// File path to output.
@@ -70,9 +98,46 @@ async fn main() {
     let args = CommitterCliArgs::parse();
 
     match args.command {
-        Command::Commit { output_path } => {
+        Command::Commit {
+            output_path,
+            storage_path,
+            create_storage,
+        } => {
             // TODO(Aner, 15/7/24): try moving read_from_stdin into function.
-            parse_and_commit(&read_from_stdin(), output_path).await;
+            if let Err(error) = parse_and_commit(
+                &read_from_stdin(),
+                output_path,
+                storage_path,
+                create_storage,
+            )
+            .await
+            {
+                error!("Failed to commit the given block: {error}");
+                std::process::exit(1);
+            }
+        }
+
+        Command::CheckConsistency {
+            output_path,
+            storage_path,
+            contracts_trie_root_hash,
+            classes_trie_root_hash,
+        } => {
+            match check_consistency(
+                &storage_path,
+                &contracts_trie_root_hash,
+                &classes_trie_root_hash,
+                output_path,
+            )
+            .await
+            {
+                Ok(true) => {}
+                Ok(false) => std::process::exit(1),
+                Err(error) => {
+                    error!("Failed to check the consistency of the storage: {error}");
+                    std::process::exit(1);
+                }
+            }
         }
 
         Command::PythonTest {
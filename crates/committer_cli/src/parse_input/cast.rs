@@ -57,20 +57,20 @@ impl TryFrom<RawInput> for InputImpl {
 
         let mut storage_updates = HashMap::new();
         for outer_entry in raw_input.state_diff.storage_updates {
-            let inner_map = outer_entry
-                .storage_updates
-                .iter()
-                .map(|inner_entry| {
-                    (
-                        StarknetStorageKey(Felt::from_bytes_be_slice(&inner_entry.key)),
-                        StarknetStorageValue(Felt::from_bytes_be_slice(&inner_entry.value)),
-                    )
-                })
-                .collect();
+            let address = ContractAddress(Felt::from_bytes_be_slice(&outer_entry.address));
+            let mut inner_map = HashMap::new();
+            for inner_entry in outer_entry.storage_updates {
+                add_unique(
+                    &mut inner_map,
+                    &format!("starknet storage updates of {address:?}"),
+                    StarknetStorageKey(Felt::from_bytes_be_slice(&inner_entry.key)),
+                    StarknetStorageValue(Felt::from_bytes_be_slice(&inner_entry.value)),
+                )?;
+            }
             add_unique(
                 &mut storage_updates,
                 "starknet storage updates",
-                ContractAddress(Felt::from_bytes_be_slice(&outer_entry.address)),
+                address,
                 inner_map,
             )?;
         }
@@ -26,6 +26,12 @@ pub(crate) struct RawStorageEntry {
 pub(crate) struct RawConfigImpl {
     warn_on_trivial_modifications: bool,
     log_level: PythonLogLevel,
+    #[serde(default)]
+    layered_hashing: bool,
+    #[serde(default)]
+    flat_state_index: bool,
+    #[serde(default)]
+    verify_on_read: bool,
 }
 
 #[derive(Deserialize_repr, Debug, Default, Serialize)]
@@ -53,6 +59,9 @@ impl From<RawConfigImpl> for ConfigImpl {
             PythonLogLevel::Error | PythonLogLevel::Critical => LevelFilter::Error,
         };
         ConfigImpl::new(raw_config.warn_on_trivial_modifications, log_level)
+            .with_layered_hashing(raw_config.layered_hashing)
+            .with_flat_state_index(raw_config.flat_state_index)
+            .with_verify_on_read(raw_config.verify_on_read)
     }
 }
 
@@ -285,3 +285,41 @@ fn test_input_parsing_with_mapping_key_duplicate() {
         DeserializationError::KeyDuplicate(key) if key ==  expected_error
     ));
 }
+
+#[test]
+fn test_input_parsing_with_storage_update_key_duplicate() {
+    let input = r#"
+[
+    [],
+    [
+        [],
+        [],
+        [],
+        [
+            [
+                [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 2],
+                [
+                    [
+                        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5],
+                        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 7]
+                    ],
+                    [
+                        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 5],
+                        [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 8]
+                    ]
+                ]
+            ]
+        ]
+    ],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    [0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0, 0],
+    {"warn_on_trivial_modifications": false, "log_level": 30}
+]
+
+"#;
+    let expected_error = "starknet storage updates of ContractAddress(2): StarknetStorageKey(5)";
+    assert!(matches!(
+        parse_input(input).unwrap_err(),
+        DeserializationError::KeyDuplicate(key) if key == expected_error
+    ));
+}
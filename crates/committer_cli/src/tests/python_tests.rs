@@ -17,7 +17,6 @@ use committer::patricia_merkle_tree::node_data::inner_node::{
 };
 use committer::patricia_merkle_tree::node_data::leaf::ContractState;
 use committer::patricia_merkle_tree::types::SubTreeHeight;
-use log::error;
 
 use committer::patricia_merkle_tree::external_test_utils::single_tree_flow_test;
 use committer::patricia_merkle_tree::updated_skeleton_tree::hash_function::TreeHashFunctionImpl;
@@ -50,6 +49,7 @@ pub enum PythonTest {
     NodeKey,
     StorageSerialize,
     ComparePythonHashConstants,
+    GlobalRootHash,
     StorageNode,
     FilledForestOutput,
     TreeHeightComparison,
@@ -101,6 +101,7 @@ impl TryFrom<String> for PythonTest {
             "node_db_key_test" => Ok(Self::NodeKey),
             "storage_serialize_test" => Ok(Self::StorageSerialize),
             "compare_python_hash_constants" => Ok(Self::ComparePythonHashConstants),
+            "global_root_hash_test" => Ok(Self::GlobalRootHash),
             "storage_node_test" => Ok(Self::StorageNode),
             "filled_forest_output" => Ok(Self::FilledForestOutput),
             "compare_tree_height" => Ok(Self::TreeHeightComparison),
@@ -151,12 +152,17 @@ impl PythonTest {
             Self::StorageSerialize => storage_serialize_test(),
             Self::NodeKey => Ok(test_node_db_key()),
             Self::ComparePythonHashConstants => Ok(python_hash_constants_compare()),
+            Self::GlobalRootHash => {
+                let roots_input: HashMap<String, String> =
+                    serde_json::from_str(Self::non_optional_input(input)?)?;
+                test_global_root_hash(roots_input)
+            }
             Self::StorageNode => {
                 let storage_node_input: HashMap<String, String> =
                     serde_json::from_str(Self::non_optional_input(input)?)?;
                 test_storage_node(storage_node_input)
             }
-            Self::FilledForestOutput => filled_forest_output_test(),
+            Self::FilledForestOutput => filled_forest_output_test().await,
             Self::TreeHeightComparison => Ok(get_actual_tree_height()),
             Self::ParseTxOutput => {
                 let tx_output: TransactionOutputForHash =
@@ -573,6 +579,33 @@ fn python_hash_constants_compare() -> String {
     )
 }
 
+/// Computes the global state root from the roots of the contracts trie and the classes trie.
+/// # Arguments
+///
+/// * `roots_input` - A map holding the hex strings of the roots, under the keys
+///   `"contracts_trie_root_hash"` and `"classes_trie_root_hash"`.
+///
+/// # Returns
+///
+/// The hex string of the global root.
+fn test_global_root_hash(roots_input: HashMap<String, String>) -> Result<String, PythonTestError> {
+    let contracts_trie_root_hash = Felt::from_hex(get_or_key_not_found(
+        &roots_input,
+        "contracts_trie_root_hash",
+    )?)
+    .map_err(DeserializationError::from)?;
+    let classes_trie_root_hash = Felt::from_hex(get_or_key_not_found(
+        &roots_input,
+        "classes_trie_root_hash",
+    )?)
+    .map_err(DeserializationError::from)?;
+    let global_root_hash = TreeHashFunctionImpl::compute_global_root_hash(
+        HashOutput(contracts_trie_root_hash),
+        HashOutput(classes_trie_root_hash),
+    );
+    Ok(global_root_hash.0.to_hex())
+}
+
 /// Processes a map containing JSON strings for different node data.
 /// Creates `NodeData` objects for each node type, stores them in a storage, and serializes the map to a JSON string.
 ///
@@ -710,9 +743,9 @@ fn test_storage_node(data: HashMap<String, String>) -> Result<String, PythonTest
 }
 
 /// Generates a dummy random filled forest and serializes it to a JSON string.
-pub(crate) fn filled_forest_output_test() -> Result<String, PythonTestError> {
+pub(crate) async fn filled_forest_output_test() -> Result<String, PythonTestError> {
     let dummy_forest = SerializedForest(FilledForest::dummy_random(&mut rand::thread_rng(), None));
-    let output = dummy_forest.forest_to_output();
+    let output = dummy_forest.forest_to_output().await;
     let output_string = serde_json::to_string(&output).expect("Failed to serialize");
     Ok(output_string)
 }
@@ -146,7 +146,9 @@ pub async fn test_single_committer_flow(input: &str, output_path: &str) {
 
     let start = std::time::Instant::now();
     // Benchmark the committer flow test.
-    commit(committer_input.0, output_path.to_owned()).await;
+    commit(committer_input.0, output_path.to_owned(), None, false)
+        .await
+        .unwrap();
     let execution_time = std::time::Instant::now() - start;
 
     // Assert correctness of the output of the committer flow test.
@@ -41,7 +41,7 @@ pub fn parse_input_single_storage_tree_flow_test(input: &HashMap<String, String>
         .iter()
         .map(|(k, v)| {
             (
-                NodeIndex::new(U256::from_str_hex(k).unwrap()),
+                NodeIndex::new(U256::from_str_hex(k).unwrap()).unwrap(),
                 StarknetStorageValue(Felt::from_hex(v).unwrap()),
             )
         })
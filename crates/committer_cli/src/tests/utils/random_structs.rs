@@ -26,7 +26,7 @@ use rand::Rng;
 use rand_distr::num_traits::ToPrimitive;
 use rand_distr::{Distribution, Geometric};
 use std::cmp::min;
-use std::collections::HashMap;
+use std::collections::{HashMap, HashSet};
 use strum::IntoEnumIterator;
 
 pub trait RandomValue {
@@ -157,6 +157,7 @@ impl RandomValue for NodeIndex {
         };
 
         Self::new(get_random_u256(rng, U256::ONE, max_value + 1))
+            .expect("Random node index is unexpectedly out of range.")
     }
 }
 
@@ -248,6 +249,10 @@ impl DummyRandomValue for FilledForest {
             storage_tries,
             contracts_trie,
             classes_trie,
+            orphaned_node_keys: HashSet::new(),
+            released_node_references: HashMap::new(),
+            flat_state_entries: HashMap::new(),
+            previous_contracts_trie_root_hash: HashOutput::ROOT_OF_EMPTY_TREE,
         }
     }
 }